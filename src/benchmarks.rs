@@ -0,0 +1,258 @@
+use std::fmt;
+
+use rand::Rng;
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+
+use rng::StreamRng;
+use integrate::deterministic;
+use statistics::Statistics;
+use crosssection::{CrossSection, KleinNishinaCrossSection};
+use mc::{EastPointingSource, Event, Experiment, FreePath, Material, Point, Runner};
+
+
+/// The comparison of a simulated quantity with its analytic value.
+///
+/// This is returned by all benchmarks in this module. The `Display`
+/// implementation prints both values, the uncertainty, and the pull.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkResult {
+    /// A short description of the compared quantity.
+    pub name: String,
+    /// The Monte-Carlo estimate.
+    pub simulated: f64,
+    /// The statistical uncertainty of `simulated`.
+    pub error: f64,
+    /// The exact value.
+    pub analytic: f64,
+}
+
+impl BenchmarkResult {
+    /// Returns the deviation `simulated - analytic`.
+    pub fn deviation(&self) -> f64 {
+        self.simulated - self.analytic
+    }
+
+    /// Returns the deviation in units of the statistical uncertainty.
+    ///
+    /// If the uncertainty is zero, this is zero if the deviation is
+    /// zero as well, and infinite otherwise.
+    pub fn pull(&self) -> f64 {
+        let deviation = self.deviation();
+        if self.error > 0.0 {
+            deviation / self.error
+        } else if deviation == 0.0 {
+            0.0
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    /// Returns `true` if the simulated value agrees with the analytic
+    /// one within `n_sigma` standard deviations.
+    pub fn passes(&self, n_sigma: f64) -> bool {
+        self.pull().abs() <= n_sigma
+    }
+}
+
+impl fmt::Display for BenchmarkResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:.6} ± {:.6} (exact: {:.6}, pull: {:+.2})",
+            self.name,
+            self.simulated,
+            self.error,
+            self.analytic,
+            self.pull()
+        )
+    }
+}
+
+
+/// Estimates π from the fraction of random points in the unit square
+/// that lie inside the unit circle.
+///
+/// # Examples
+/// ```
+/// use mcgen::benchmarks::estimate_pi;
+///
+/// let result = estimate_pi(100_000, 42);
+/// assert!(result.passes(5.0), "{}", result);
+/// ```
+pub fn estimate_pi(n_samples: usize, seed: u64) -> BenchmarkResult {
+    let mut rng = StreamRng::new(seed).stream(0);
+    let stats = (0..n_samples)
+        .map(|_| {
+            let (x, y) = rng.gen::<(f64, f64)>();
+            if x * x + y * y <= 1.0 { 4.0 } else { 0.0 }
+        })
+        .collect::<Statistics<f64>>();
+    BenchmarkResult {
+        name: "pi".to_owned(),
+        simulated: stats.mean(),
+        error: stats.error_of_mean().unwrap_or(0.0),
+        analytic: ::std::f64::consts::PI,
+    }
+}
+
+
+/// Compares the angular distribution sampled from the Klein–Nishina
+/// cross-section with its exact shape.
+///
+/// `n_samples` scattering cosines are sampled at the given photon
+/// energy and binned into `n_bins` equal bins between -1 and 1. For
+/// each bin, the fraction of samples is compared with the integral of
+/// the differential cross-section over the bin, divided by the total
+/// cross-section. The uncertainty is the binomial error of the
+/// fraction.
+///
+/// # Panics
+/// This panics if `n_bins` is zero.
+pub fn klein_nishina_angular(
+    energy: Joule<f64>,
+    n_samples: usize,
+    n_bins: usize,
+    seed: u64,
+) -> Vec<BenchmarkResult> {
+    assert!(n_bins > 0, "no bins");
+    let xsection = KleinNishinaCrossSection;
+    let mut rng = StreamRng::new(seed).stream(0);
+    let bin_width = 2.0 / n_bins as f64;
+    let mut counts = vec![0usize; n_bins];
+    for _ in 0..n_samples {
+        let mu = *xsection.sample_mu(energy, &mut rng).value();
+        let bin = ((mu + 1.0) / bin_width) as usize;
+        counts[bin.min(n_bins - 1)] += 1;
+    }
+    let integral = |low: f64, high: f64| {
+        deterministic(
+            |mu| *(xsection.eval(energy, Unitless::new(mu)) / M2).value(),
+            low..high,
+            QUADRATURE_INTERVALS,
+        )
+    };
+    let total = integral(-1.0, 1.0);
+    counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            let low = -1.0 + bin_width * i as f64;
+            let high = low + bin_width;
+            let fraction = count as f64 / n_samples as f64;
+            BenchmarkResult {
+                name: format!("Klein-Nishina, mu in [{:+.3}, {:+.3}]", low, high),
+                simulated: fraction,
+                error: (fraction * (1.0 - fraction) / n_samples as f64).sqrt(),
+                analytic: integral(low, high) / total,
+            }
+        })
+        .collect()
+}
+
+
+/// Compares the uncollided transmission through a purely absorbing
+/// slab with its exact value.
+///
+/// A point source emits photons into the half-plane facing an infinite
+/// slab of the given thickness, see `EastPointingSource`. Every
+/// interaction inside the slab absorbs the photon, so only photons that
+/// cross it without interaction reach the detector behind it. The
+/// simulated transmission is the efficiency of the run. Because the
+/// sine of the emission angle is uniformly distributed, the exact
+/// transmission is the mean of `exp(-t / cos(theta))` over it, where
+/// `t` is the thickness in mean free paths.
+pub fn slab_attenuation(
+    thickness: Meter<f64>,
+    mean_free_path: Meter<f64>,
+    n_histories: usize,
+    seed: u64,
+) -> BenchmarkResult {
+    let slab = SlabExperiment {
+        // The source must lie left of `x_start()`, see `simulate_history()`.
+        source: EastPointingSource::new(Point::new(-1.0 * M, 0.0 * M), 100e3 * EV),
+        thickness,
+        mean_free_path,
+    };
+    let progress = Runner::new(&slab).run_streams(n_histories, &StreamRng::new(seed), |_| {});
+    let simulated = progress.efficiency().unwrap_or(0.0);
+    let t = *(thickness / mean_free_path).value();
+    let transmission = |sine: f64| (-t / (1.0 - sine * sine).sqrt()).exp();
+    BenchmarkResult {
+        name: format!("slab transmission, {} mean free paths", t),
+        simulated,
+        error: simulated * progress.relative_error().unwrap_or(0.0),
+        analytic: deterministic(transmission, -1.0..1.0, QUADRATURE_INTERVALS) / 2.0,
+    }
+}
+
+
+/// Runs all benchmarks with default parameters.
+///
+/// This is meant as a quick validation of the installation. With the
+/// default sample sizes, it takes a few seconds.
+pub fn run_all(seed: u64) -> Vec<BenchmarkResult> {
+    let mut results = vec![estimate_pi(1_000_000, seed)];
+    results.extend(klein_nishina_angular(661.7e3 * EV, 1_000_000, 10, seed));
+    for &t in &[0.5, 1.0, 3.0] {
+        results.push(slab_attenuation(t * 0.01 * M, 0.01 * M, 100_000, seed));
+    }
+    results
+}
+
+
+/// Private experiment of a purely absorbing slab between the source
+/// and the detector, see `slab_attenuation()`.
+struct SlabExperiment {
+    source: EastPointingSource,
+    thickness: Meter<f64>,
+    mean_free_path: Meter<f64>,
+}
+
+impl Experiment for SlabExperiment {
+    type Source = EastPointingSource;
+
+    fn source(&self) -> &Self::Source {
+        &self.source
+    }
+
+    fn x_start(&self) -> Meter<f64> {
+        0.0 * M
+    }
+
+    fn get_material(&self, location: &Point) -> Material {
+        if location.x() < 0.0 * M {
+            Material::Air
+        } else if location.x() <= self.thickness {
+            Material::Absorber
+        } else {
+            Material::Detector
+        }
+    }
+
+    fn get_mean_free_path(&self, _: Material, _: Joule<f64>) -> FreePath<f64> {
+        FreePath::Exp(self.mean_free_path)
+    }
+
+    fn gen_event<R: Rng>(&self, _: Material, _: Joule<f64>, _: &mut R) -> Event {
+        Event::Absorbed
+    }
+
+    fn gen_coherent_scatter<R: Rng>(&self, _: Material, _: Joule<f64>, _: &mut R) -> Unitless<f64> {
+        unreachable!("slab is purely absorbing")
+    }
+
+    fn gen_incoherent_scatter<R: Rng>(
+        &self,
+        _: Material,
+        _: Joule<f64>,
+        _: &mut R,
+    ) -> (Unitless<f64>, Joule<f64>) {
+        unreachable!("slab is purely absorbing")
+    }
+}
+
+
+/// The number of intervals of Simpson's rule used for exact values.
+const QUADRATURE_INTERVALS: usize = 10_000;
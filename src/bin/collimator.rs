@@ -3,6 +3,10 @@ extern crate mcgen;
 extern crate gnuplot;
 extern crate dimensioned;
 
+use std::env;
+use std::process;
+use std::path::Path;
+
 use rand::Rng;
 use rand::distributions::IndependentSample;
 
@@ -11,29 +15,131 @@ use dimensioned::{Dimensionless, Recip};
 use dimensioned::f64prefixes::*;
 
 use mcgen::mc::*;
+use mcgen::Error;
 use mcgen::Function;
 use mcgen::Contains;
 use mcgen::Histogram;
+use mcgen::StreamRng;
+use mcgen::DiscreteSampler;
 use mcgen::crosssection::*;
 
 
-fn choose<R: Rng>(rng: &mut R, weights: &[f64]) -> usize {
-    let choice = rng.gen_range(0.0, weights.iter().sum());
-    let mut threshold = 0.0;
-    for (i, weight) in weights.iter().enumerate() {
-        threshold += *weight;
-        if choice < threshold {
-            return i;
+const USAGE: &str = "usage: collimator <n_detected> [--histories N]
+                  [--slit-width CM] [--thickness CM]
+                  [--distance CM] [--energy-bins N] [--energy-max KEV]
+                  [--radius-bins N] [--radius-max M]
+                  [--energy-out FILE] [--radius-out FILE] [--events FILE]
+                  [--tracks N] [--tracks-out FILE] [--seed SEED]";
+
+
+/// The command-line arguments.
+///
+/// All lengths of the setup are given in centimeters. The collimator
+/// always begins at X = 0.5 cm.
+///
+/// By default, histories are simulated until `n_detected` photons have
+/// been detected. With `--histories`, a fixed number of histories is
+/// simulated instead.
+struct Args {
+    n_detected: usize,
+    n_histories: Option<usize>,
+    seed: u64,
+    slit_width: f64,
+    thickness: f64,
+    distance: f64,
+    energy_bins: usize,
+    energy_max: f64,
+    radius_bins: usize,
+    radius_max: f64,
+    energy_out: String,
+    radius_out: String,
+    events: Option<String>,
+    n_tracks: usize,
+    tracks_out: String,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            n_detected: 0,
+            n_histories: None,
+            seed: rand::thread_rng().gen(),
+            slit_width: 0.2,
+            thickness: 1.0,
+            distance: 11.5,
+            energy_bins: 666,
+            energy_max: 666.0,
+            radius_bins: 127,
+            radius_max: 1.27,
+            energy_out: "energy_hist.pdf".to_owned(),
+            radius_out: "radius_hist.pdf".to_owned(),
+            events: None,
+            n_tracks: 0,
+            tracks_out: "tracks.svg".to_owned(),
         }
     }
-    unreachable!();
 }
 
+
+fn get_args() -> Result<Args, Error> {
+    fn string(name: &str, value: Option<String>) -> Result<String, Error> {
+        value.ok_or_else(|| Error::domain(format!("missing value for {}", name)))
+    }
+
+    fn number<T: ::std::str::FromStr>(name: &str, value: Option<String>) -> Result<T, Error> {
+        string(name, value)?
+            .parse()
+            .map_err(|_| Error::domain(format!("not a number: {}", name)))
+    }
+
+    let mut result = Args::default();
+    let mut n_detected = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--histories" => result.n_histories = Some(number(&arg, args.next())?),
+            "--slit-width" => result.slit_width = number(&arg, args.next())?,
+            "--thickness" => result.thickness = number(&arg, args.next())?,
+            "--distance" => result.distance = number(&arg, args.next())?,
+            "--energy-bins" => result.energy_bins = number(&arg, args.next())?,
+            "--energy-max" => result.energy_max = number(&arg, args.next())?,
+            "--radius-bins" => result.radius_bins = number(&arg, args.next())?,
+            "--radius-max" => result.radius_max = number(&arg, args.next())?,
+            "--energy-out" => result.energy_out = string(&arg, args.next())?,
+            "--radius-out" => result.radius_out = string(&arg, args.next())?,
+            "--events" => result.events = Some(string(&arg, args.next())?),
+            "--seed" => result.seed = number(&arg, args.next())?,
+            "--tracks" => result.n_tracks = number(&arg, args.next())?,
+            "--tracks-out" => result.tracks_out = string(&arg, args.next())?,
+            _ if arg.starts_with("--") => {
+                return Err(Error::domain(format!("unknown option: {}\n{}", arg, USAGE)));
+            },
+            _ if n_detected.is_none() => n_detected = Some(number("n_detected", Some(arg))?),
+            _ => return Err(Error::domain(format!("unexpected argument: {}\n{}", arg, USAGE))),
+        }
+    }
+    result.n_detected = n_detected
+        .ok_or_else(|| Error::domain(format!("missing argument: n_detected\n{}", USAGE)))?;
+    if result.thickness <= 0.0 || result.slit_width < 0.0 {
+        return Err(Error::domain("collimator thickness and slit width must be positive"));
+    }
+    if result.distance < COLLIMATOR_START + result.thickness {
+        return Err(Error::domain("detector must lie behind the collimator"));
+    }
+    Ok(result)
+}
+
+
 /// Container for all the necessary information about the experiment.
 struct ThisTask {
     source: EastPointingSource,
+    collimator_x: (Meter<f64>, Meter<f64>),
+    hole_y: (Meter<f64>, Meter<f64>),
+    detector_x: Meter<f64>,
     coherent_xsection: CoherentCrossSection,
     incoherent_xsection: IncoherentCrossSection,
+    coherent_max: MaxCache,
+    incoherent_max: MaxCache,
     mfp_tot: Function<Joule<f64>, Meter<f64>>,
     mfp_coh: Function<Joule<f64>, Meter<f64>>,
     mfp_inc: Function<Joule<f64>, Meter<f64>>,
@@ -54,31 +160,46 @@ impl ThisTask {
     ///   on the photon energy (in keV).
     /// - `data/ISF.dat`: The incoherent scattering function of lead
     ///   (Pb) depending on the photon energy (in keV).
-    fn new() -> Self {
-        let mut mean_free_paths = Function::<f64>::multiple_from_file("data/MFWL.dat")
-            .expect("MFWL.dat")
-            .into_iter();
-        ThisTask {
-            source: EastPointingSource::new((0.0 * M, 0.0 * M).into(), 661.7 * KILO * EV),
-            coherent_xsection: CoherentCrossSection::new("data/AFF.dat").expect("AFF.dat"),
-            incoherent_xsection: IncoherentCrossSection::new("data/ISF.dat").expect("ISF.dat"),
-            mfp_tot: mean_free_paths
-                .next()
-                .expect("mfp_tot")
-                .scale(KILO * EV, CENTI * M),
-            mfp_coh: mean_free_paths
-                .next()
-                .expect("mfp_coh")
-                .scale(KILO * EV, CENTI * M),
-            mfp_inc: mean_free_paths
-                .next()
-                .expect("mfp_inc")
-                .scale(KILO * EV, CENTI * M),
-            mfp_pho: mean_free_paths
+    ///
+    /// The geometry of the setup is taken from `args`.
+    fn new(args: &Args) -> Result<Self, Error> {
+        let cm = CENTI * M;
+        let mut mean_free_paths = Function::<f64>::multiple_from_file("data/MFWL.dat")?
+            .into_iter()
+            .map(|mfp| mfp.scale(KILO * EV, CENTI * M));
+        let mut next_mfp = |name| {
+            mean_free_paths
                 .next()
-                .expect("mfp_pho")
-                .scale(KILO * EV, CENTI * M),
-        }
+                .ok_or_else(|| Error::domain(format!("MFWL.dat: missing {}", name)))
+        };
+        Ok(ThisTask {
+            source: EastPointingSource::new((0.0 * M, 0.0 * M).into(), 661.7 * KILO * EV),
+            collimator_x: (COLLIMATOR_START * cm, (COLLIMATOR_START + args.thickness) * cm),
+            hole_y: (-args.slit_width / 2.0 * cm, args.slit_width / 2.0 * cm),
+            detector_x: args.distance * cm,
+            coherent_xsection: CoherentCrossSection::new("data/AFF.dat")?,
+            incoherent_xsection: IncoherentCrossSection::new("data/ISF.dat")?,
+            coherent_max: MaxCache::new(100, 1.0 * KILO * EV, 662.0 * KILO * EV),
+            incoherent_max: MaxCache::new(100, 1.0 * KILO * EV, 662.0 * KILO * EV),
+            mfp_tot: next_mfp("mfp_tot")?,
+            mfp_coh: next_mfp("mfp_coh")?,
+            mfp_inc: next_mfp("mfp_inc")?,
+            mfp_pho: next_mfp("mfp_pho")?,
+        })
+    }
+
+    /// Returns the outlines of the collimator and the detector.
+    ///
+    /// The setup is infinitely high, so the outlines are cut off
+    /// `OUTLINE_HEIGHT` above and below the slit.
+    fn outline(&self) -> Vec<Outline> {
+        let height = OUTLINE_HEIGHT * CENTI * M;
+        let detector_x = (self.detector_x, self.detector_x + height);
+        vec![
+            Outline::rectangle(Material::Absorber, self.collimator_x, (self.hole_y.1, height)),
+            Outline::rectangle(Material::Absorber, self.collimator_x, (-height, self.hole_y.0)),
+            Outline::rectangle(Material::Detector, detector_x, (-height, height)),
+        ]
     }
 
     fn get_pb_mean_free_path(&self, energy: Joule<f64>) -> Meter<f64> {
@@ -98,7 +219,7 @@ impl ThisTask {
         let w_incoherent = self.mfp_inc.call(energy).recip() * M;
         let w_photo = self.mfp_pho.call(energy).recip() * M;
         let weights = [*w_coherent.value(), *w_incoherent.value(), *w_photo.value()];
-        match choose(rng, &weights) {
+        match DiscreteSampler::new(&weights).ind_sample(rng) {
             0 => Event::CoherentScatter,
             1 => Event::IncoherentScatter,
             2 => Event::Absorbed,
@@ -115,17 +236,14 @@ impl Experiment for ThisTask {
     }
 
     fn x_start(&self) -> Meter<f64> {
-        0.5 * CENTI * M
+        self.collimator_x.0
     }
 
     fn get_material(&self, location: &Point) -> Material {
         let (x, y) = location.to_tuple();
-        let collimator_x = (0.5 * CENTI * M, 1.5 * CENTI * M);
-        let hole_y = (-0.1 * CENTI * M, 0.1 * CENTI * M);
-
-        if collimator_x.contains(x) && !hole_y.contains(y) {
+        if self.collimator_x.contains(x) && !self.hole_y.contains(y) {
             Material::Absorber
-        } else if x > 11.5 * CENTI * M {
+        } else if x > self.detector_x {
             Material::Detector
         } else {
             Material::Air
@@ -154,7 +272,7 @@ impl Experiment for ThisTask {
         energy: Joule<f64>,
         rng: &mut R,
     ) -> Unitless<f64> {
-        let sampler = RejectionSampler::new(&self.coherent_xsection, energy);
+        let sampler = self.coherent_max.sampler(&self.coherent_xsection, energy);
         let mu = sampler.ind_sample(rng);
         let mut angle = mu.value().acos();
         if rng.gen::<bool>() {
@@ -169,13 +287,13 @@ impl Experiment for ThisTask {
         energy: Joule<f64>,
         rng: &mut R,
     ) -> (Unitless<f64>, Joule<f64>) {
-        let sampler = RejectionSampler::new(&self.incoherent_xsection, energy);
+        let sampler = self.incoherent_max.sampler(&self.incoherent_xsection, energy);
         let mu = sampler.ind_sample(rng);
         let mut angle = mu.value().acos();
         if rng.gen::<bool>() {
             angle *= -1.0;
         }
-        let new_energy = IncoherentCrossSection::compton_scatter(energy, mu);
+        let new_energy = self.incoherent_xsection.gen_scattered_energy(energy, mu, rng);
         (Unitless::new(angle), new_energy)
     }
 }
@@ -202,23 +320,92 @@ pub fn save_hist(hist: &Histogram, filename: &str) {
 }
 
 
+/// Prints the progress of a run on a single, updating line.
+fn print_progress(progress: &Progress, n_detected: Option<usize>) {
+    let percent = match n_detected {
+        Some(n_detected) => (100.0 * progress.detected() / n_detected as f64).min(100.0),
+        None => 100.0 * progress.histories() as f64 / progress.total_histories() as f64,
+    };
+    let rel_error = progress.relative_error().unwrap_or(1.0);
+    let eta = progress.eta().unwrap_or(0.0 * S) / S;
+    eprint!(
+        "\r{:5.1}% done, {:.0} detected, rel. error {:.2}%, ETA {:.0} s   ",
+        percent,
+        progress.detected(),
+        100.0 * rel_error,
+        eta.value()
+    );
+}
+
+
 fn main() {
-    let experiment = ThisTask::new();
-    let mut energy_hist = Histogram::new(666, 0.0, 666.0);
-    let mut radius_hist = Histogram::new(127, 0.0, 1.27);
+    if let Err(err) = run() {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
 
-    let n_particles = match ::std::env::args().skip(1).next() {
-        Some(s) => s.parse::<usize>().expect("not a number: n_particles"),
-        None => panic!("missing argument: n_particles"),
-    };
 
-    for _ in 0..n_particles {
-        let photon = simulate_particle(&experiment);
+fn run() -> Result<(), Error> {
+    let args = get_args()?;
+    let experiment = ThisTask::new(&args)?;
+    let mut manifest = RunManifest::new(Some(args.seed));
+    for path in DATA_FILES {
+        manifest.add_data_file(path)?;
+    }
+    let mut energy_hist = Histogram::new(args.energy_bins, 0.0, args.energy_max);
+    let mut radius_hist = Histogram::new(args.radius_bins, 0.0, args.radius_max);
+
+    let mut events = match args.events {
+        Some(ref path) => Some(EventWriter::create(path)?),
+        None => None,
+    };
+    let mut tracks = if args.n_tracks > 0 {
+        Some(TrackRecorder::new(args.n_tracks))
+    } else {
+        None
+    };
+    eprintln!("seed: {}", args.seed);
+    let streams = StreamRng::new(args.seed);
+    let mut tally = (&mut events, &mut tracks);
+    let on_detect = |photon: Photon| {
         let energy = photon.energy() / (KILO * EV);
         let radius = photon.location().y() / M;
         energy_hist.fill(*energy.value());
         radius_hist.fill(radius.value().abs());
+    };
+    let progress = match args.n_histories {
+        Some(n_histories) => Runner::new(&experiment)
+            .on_progress(|progress| print_progress(progress, None))
+            .run_streams_with_tally(n_histories, &streams, &mut tally, on_detect),
+        None => Runner::new(&experiment)
+            .on_progress(|progress| print_progress(progress, Some(args.n_detected)))
+            .run_streams_until_detected(args.n_detected, &streams, &mut tally, on_detect),
+    };
+    eprintln!();
+    if let Some(err) = progress.last_error() {
+        eprintln!("{} histories failed, last error: {}", progress.failed(), err);
     }
-    save_hist(&energy_hist, "energy_hist.pdf");
-    save_hist(&radius_hist, "radius_hist.pdf");
+    if let Some(events) = events {
+        events.finish()?;
+    }
+    if let Some(tracks) = tracks {
+        write_tracks(&args.tracks_out, tracks.tracks(), &experiment.outline())?;
+    }
+    manifest.set_wall_time(progress.elapsed());
+    manifest.write(Path::new(&args.energy_out).with_file_name("manifest.json"))?;
+    save_hist(&energy_hist, &args.energy_out);
+    save_hist(&radius_hist, &args.radius_out);
+    Ok(())
 }
+
+
+/// The data files read by `ThisTask::new()`.
+const DATA_FILES: &[&str] = &["data/MFWL.dat", "data/AFF.dat", "data/ISF.dat"];
+
+/// The X-coordinate in centimeters at which the collimator begins.
+const COLLIMATOR_START: f64 = 0.5;
+
+/// How far in centimeters the drawn outline of the setup extends in
+/// Y-direction.
+const OUTLINE_HEIGHT: f64 = 5.0;
@@ -3,21 +3,13 @@ extern crate rand;
 
 use rand::distributions::{Exp, IndependentSample, Normal, Range};
 
-use mcgen::IntoSampleIter;
-
 /// Replacement that takes a distribution instead of a closure.
 fn print_stats_and_time<D>(dist: D, sample_size: usize)
 where
     D: IndependentSample<f64>,
 {
     let mut rng = rand::thread_rng();
-    mcgen::print_stats_and_time(
-        || {
-            dist.into_sample_iter(&mut rng)
-                .take(sample_size)
-                .collect()
-        },
-    );
+    mcgen::print_stats_and_time(|| mcgen::expectation(|x| x, dist, sample_size, &mut rng));
 }
 
 
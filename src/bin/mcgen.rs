@@ -0,0 +1,333 @@
+extern crate rand;
+extern crate mcgen;
+#[macro_use]
+extern crate serde_json;
+extern crate dimensioned;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use rand::Rng;
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+use dimensioned::f64prefixes::*;
+
+use mcgen::mc::*;
+use mcgen::Error;
+use mcgen::Histogram;
+use mcgen::StreamRng;
+use mcgen::time::ProgressTimer;
+
+
+const USAGE: &str = "usage: mcgen run <config> [--histories N] [--seed SEED] [--out DIR]
+                 [--events FILE] [--tracks N] [--tracks-out FILE] [--live SECONDS]
+       mcgen scan <config> --param PATH --values V1,V2,...
+                  [--histories N] [--seed SEED] [--out DIR]";
+
+
+/// The subcommands of this program.
+#[derive(PartialEq)]
+enum Command {
+    /// Run a single experiment.
+    Run,
+    /// Run the experiment once per value of a parameter.
+    Scan,
+}
+
+
+/// The command-line arguments.
+struct Args {
+    command: Command,
+    config: PathBuf,
+    n_histories: usize,
+    seed: u64,
+    out_dir: PathBuf,
+    events: Option<PathBuf>,
+    n_tracks: usize,
+    tracks_out: Option<PathBuf>,
+    live: Option<f64>,
+    param: Option<String>,
+    values: Vec<f64>,
+}
+
+
+fn get_args() -> Result<Args, Error> {
+    let mut args = env::args().skip(1);
+    let command = match args.next() {
+        Some(ref command) if command == "run" => Command::Run,
+        Some(ref command) if command == "scan" => Command::Scan,
+        Some(command) => {
+            return Err(Error::domain(format!("unknown command: {}\n{}", command, USAGE)));
+        },
+        None => return Err(Error::domain(USAGE)),
+    };
+    let mut config = None;
+    let mut n_histories = 1_000_000;
+    let mut seed = rand::thread_rng().gen();
+    let mut out_dir = PathBuf::from("results");
+    let mut events = None;
+    let mut n_tracks = 0;
+    let mut tracks_out = None;
+    let mut live = None;
+    let mut param = None;
+    let mut values = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| Error::domain(format!("missing value for {}", arg)))
+        };
+        match arg.as_str() {
+            // Accept numbers like `1e7`.
+            "--histories" => {
+                n_histories = value()?
+                    .parse::<f64>()
+                    .map_err(|_| Error::domain("not a number: --histories"))?
+                    as usize;
+            },
+            "--seed" => {
+                seed = value()?
+                    .parse()
+                    .map_err(|_| Error::domain("not a number: --seed"))?;
+            },
+            "--out" => out_dir = PathBuf::from(value()?),
+            "--events" if command == Command::Run => events = Some(PathBuf::from(value()?)),
+            "--tracks" if command == Command::Run => {
+                n_tracks = value()?
+                    .parse()
+                    .map_err(|_| Error::domain("not a number: --tracks"))?;
+            },
+            "--tracks-out" if command == Command::Run => tracks_out = Some(PathBuf::from(value()?)),
+            "--live" if command == Command::Run => {
+                live = Some(value()?
+                    .parse()
+                    .map_err(|_| Error::domain("not a number: --live"))?);
+            },
+            "--param" if command == Command::Scan => param = Some(value()?),
+            "--values" if command == Command::Scan => {
+                values = value()?
+                    .split(',')
+                    .map(|v| v.trim().parse::<f64>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| Error::domain("not a list of numbers: --values"))?;
+            },
+            _ if arg.starts_with("--") => {
+                return Err(Error::domain(format!("unknown option: {}\n{}", arg, USAGE)));
+            },
+            _ if config.is_none() => config = Some(PathBuf::from(arg)),
+            _ => return Err(Error::domain(format!("unexpected argument: {}\n{}", arg, USAGE))),
+        }
+    }
+    let config = config.ok_or_else(|| Error::domain(format!("missing configuration\n{}", USAGE)))?;
+    if command == Command::Scan && (param.is_none() || values.is_empty()) {
+        return Err(Error::domain(format!("missing --param or --values\n{}", USAGE)));
+    }
+    Ok(Args {
+        command,
+        config,
+        n_histories,
+        seed,
+        out_dir,
+        events,
+        n_tracks,
+        tracks_out,
+        live,
+        param,
+        values,
+    })
+}
+
+
+/// Writes the bin centers and contents of a histogram as two
+/// tab-separated columns.
+fn write_histogram(path: &Path, header: &str, hist: &Histogram) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "{}", header)?;
+    for (center, count) in hist.bin_centers().zip(hist.bin_contents()) {
+        writeln!(file, "{}\t{}", center, count)?;
+    }
+    Ok(())
+}
+
+
+/// Writes the mean energy deposited per history in each material.
+fn write_dose(path: &Path, dose: &DoseTally) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "material\tE [J]\terror [J]")?;
+    for (material, stats) in dose.regions() {
+        let error = stats.error_of_mean().unwrap_or(0.0);
+        writeln!(file, "{:?}\t{:e}\t{:e}", material, stats.mean(), error)?;
+    }
+    Ok(())
+}
+
+
+/// Runs the experiment described by a configuration file.
+///
+/// The `run` subcommand writes its results to the output directory:
+/// - `spectrum.tsv`: the energy spectrum of detected photons in keV;
+/// - `deposited.tsv`: the spectrum of energy deposited in the detector
+///   per history in keV;
+/// - `dose.tsv`: the mean energy deposited per history in each
+///   material;
+/// - `summary.json`: the parameters and overall results of the run;
+/// - `manifest.json`: the seed, version, input checksums and host of
+///   the run, see `RunManifest`.
+///
+/// With `--events FILE`, every detected photon is additionally written
+/// to the given CSV file, see `DetectedEvent`. With `--tracks N`, the
+/// tracks of the first N photons are drawn on top of the setup and
+/// written to `tracks.svg` or the file given by `--tracks-out`, see
+/// `write_tracks()` for the supported formats. With `--live SECONDS`,
+/// the spectrum and the convergence of the efficiency are written to
+/// the output directory at this interval while the run is going on,
+/// see `LiveOutput`.
+///
+/// The `scan` subcommand runs the experiment once for each of the given
+/// values of a configuration parameter, see `Config::set_parameter()`.
+/// It writes `scan.tsv`, the efficiency of each point with its error,
+/// and `manifest.json`.
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+
+fn run() -> Result<(), Error> {
+    let args = get_args()?;
+    match args.command {
+        Command::Run => run_single(&args),
+        Command::Scan => run_scan(&args),
+    }
+}
+
+
+/// Creates the manifest of a run of the given configuration.
+fn new_manifest(args: &Args, config: &Config) -> Result<RunManifest, Error> {
+    let mut manifest = RunManifest::new(Some(args.seed));
+    manifest.set_config(&args.config)?;
+    for path in config.data_files() {
+        manifest.add_data_file(path)?;
+    }
+    Ok(manifest)
+}
+
+
+fn run_single(args: &Args) -> Result<(), Error> {
+    let config = Config::from_file(&args.config)?;
+    let mut manifest = new_manifest(args, &config)?;
+    let experiment = ConfigExperiment::new(&config)?;
+    let max_energy = config.source.energy.ceil();
+    let n_bins = max_energy as usize;
+    let mut energy_hist = Histogram::new(n_bins, 0.0, max_energy);
+    let events = match args.events {
+        Some(ref path) => Some(EventWriter::create(path)?),
+        None => None,
+    };
+    let tracks = if args.n_tracks > 0 {
+        Some(TrackRecorder::new(args.n_tracks))
+    } else {
+        None
+    };
+    let live = match args.live {
+        Some(interval) => {
+            let histogram = Histogram::new(n_bins, 0.0, max_energy);
+            Some(LiveOutput::new(&args.out_dir, histogram, KILO * EV, interval * S)?)
+        },
+        None => None,
+    };
+    let mut tally = (
+        (SpectrumTally::new(Histogram::new(n_bins, 0.0, max_energy), KILO * EV), DoseTally::new()),
+        ((events, tracks), live),
+    );
+
+    eprintln!("seed: {}", args.seed);
+    let streams = StreamRng::new(args.seed);
+    let mut timer = ProgressTimer::new(args.n_histories).print_to_terminal();
+    let progress = Runner::new(&experiment)
+        .on_progress(|progress| timer.tick(progress.histories()))
+        .run_streams_with_tally(args.n_histories, &streams, &mut tally, |photon| {
+            let energy = photon.energy() / (KILO * EV);
+            energy_hist.fill_weighted(*energy.value(), photon.weight());
+        });
+    eprintln!();
+    if let Some(err) = progress.last_error() {
+        eprintln!("failed: {} (last error: {})", progress.failed(), err);
+    }
+
+    let ((deposited, dose), ((events, tracks), live)) = tally;
+    if let Some(live) = live {
+        live.finish()?;
+    }
+    if let (Some(events), Some(path)) = (events, args.events.as_ref()) {
+        let written = events.written();
+        events.finish()?;
+        eprintln!("{} events written to {}", written, path.display());
+    }
+    fs::create_dir_all(&args.out_dir)?;
+    if let Some(tracks) = tracks {
+        let path = match args.tracks_out {
+            Some(ref path) => path.clone(),
+            None => args.out_dir.join("tracks.svg"),
+        };
+        write_tracks(&path, tracks.tracks(), &experiment.outline())?;
+        eprintln!("{} tracks written to {}", tracks.tracks().len(), path.display());
+    }
+    write_histogram(&args.out_dir.join("spectrum.tsv"), "E [keV]\tcounts", &energy_hist)?;
+    write_histogram(
+        &args.out_dir.join("deposited.tsv"),
+        "E [keV]\tcounts",
+        deposited.histogram(),
+    )?;
+    write_dose(&args.out_dir.join("dose.tsv"), &dose)?;
+    let summary = json!({
+        "config": args.config.to_string_lossy(),
+        "seed": args.seed,
+        "histories": progress.histories(),
+        "detected": progress.detected(),
+        "failed": progress.failed(),
+        "last_error": progress.last_error().map(|err| err.to_string()),
+        "efficiency": progress.efficiency(),
+        "relative_error": progress.relative_error(),
+        "elapsed": *(progress.elapsed() / S).value(),
+    });
+    let file = File::create(args.out_dir.join("summary.json"))?;
+    serde_json::to_writer_pretty(file, &summary)
+        .map_err(|err| Error::domain(format!("could not write summary: {}", err)))?;
+    manifest.set_wall_time(progress.elapsed());
+    manifest.write(args.out_dir.join("manifest.json"))?;
+    eprintln!("results written to {}", args.out_dir.display());
+    Ok(())
+}
+
+
+fn run_scan(args: &Args) -> Result<(), Error> {
+    let config = Config::from_file(&args.config)?;
+    let param = args.param.as_ref().expect("checked in get_args()");
+    let mut manifest = new_manifest(args, &config)?;
+    eprintln!("seed: {}", args.seed);
+    let mut timer = ProgressTimer::new(args.values.len()).print_to_terminal();
+    let mut n_done = 0;
+    let points = scan(args.values.iter().cloned(), args.n_histories, args.seed, |value| {
+        timer.tick(n_done);
+        n_done += 1;
+        let mut config = config.clone();
+        config.set_parameter(param, value)?;
+        Ok(ConfigExperiment::new(&config)?)
+    })?;
+    timer.tick(n_done);
+    eprintln!();
+    manifest.set_wall_time(timer.elapsed());
+
+    fs::create_dir_all(&args.out_dir)?;
+    let path = args.out_dir.join("scan.tsv");
+    write_scan_table(&points, BufWriter::new(File::create(&path)?))?;
+    manifest.write(args.out_dir.join("manifest.json"))?;
+    eprintln!("results written to {}", path.display());
+    Ok(())
+}
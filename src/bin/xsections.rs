@@ -4,51 +4,83 @@ extern crate gnuplot;
 extern crate dimensioned;
 
 use std::env;
+use std::process;
 
 use rand::thread_rng;
 use dimensioned::si::*;
 use dimensioned::Dimensionless;
 use dimensioned::f64prefixes::*;
 
+use mcgen::Error;
 use mcgen::IntoSampleIter;
 use mcgen::crosssection::*;
 use mcgen::Histogram;
+use mcgen::integrate::deterministic;
 
 
-fn plot_histogram<Tx, X, Ty, Y>(filename: &str, x: X, y: Y)
-where
-    Tx: gnuplot::DataType,
-    Ty: gnuplot::DataType,
-    X: IntoIterator<Item = Tx>,
-    Y: IntoIterator<Item = Ty>,
-{
+/// Plots the histogram of sampled values and the expected curve on top
+/// of it.
+fn plot_histogram(filename: &str, hist: &Histogram, curve: &[(f64, f64)]) {
     use gnuplot::AutoOption::*;
+    use gnuplot::PlotOption::*;
     use gnuplot::AxesCommon;
 
-    let mut hist = gnuplot::Figure::new();
-    hist.set_terminal("pdfcairo", filename)
+    let mut figure = gnuplot::Figure::new();
+    figure
+        .set_terminal("pdfcairo", filename)
         .axes2d()
         .set_x_label("µ", &[])
         .set_x_range(Fix(-1.0), Fix(1.0))
         .set_y_range(Fix(0.0), Auto)
-        .boxes(x, y, &[]);
-    hist.show();
+        .boxes(hist.bin_centers(), hist.bin_contents(), &[Caption("sampled")])
+        .lines(
+            curve.iter().map(|&(x, _)| x),
+            curve.iter().map(|&(_, y)| y),
+            &[Caption("analytic"), Color("red")],
+        );
+    figure.show();
+}
+
+
+/// Returns the expected content of a histogram bin as a function of µ.
+///
+/// This is the differential cross-section `dσ/dΩ`, normalized such that
+/// its integral over all bins equals the number of samples.
+fn expected_counts<XS>(
+    xsection: &XS,
+    energy: Joule<f64>,
+    n_bins: usize,
+    n_samples: usize,
+) -> Vec<(f64, f64)>
+where
+    XS: CrossSection,
+{
+    let eval = |mu: f64| *(xsection.eval(energy, Unitless::new(mu)) / M2).value();
+    let total = deterministic(eval, -1.0..1.0, CURVE_POINTS);
+    let bin_width = 2.0 / n_bins as f64;
+    let scale = n_samples as f64 * bin_width / total;
+    (0..CURVE_POINTS + 1)
+        .map(|i| -1.0 + 2.0 * i as f64 / CURVE_POINTS as f64)
+        .map(|mu| (mu, scale * eval(mu)))
+        .collect()
 }
 
 
-fn get_args() -> (String, String, usize, usize) {
+fn get_args() -> Result<(String, String, usize, usize), Error> {
     let mut args = env::args().skip(1);
-    let scatter_type = args.next().expect("missing argument: scatter type");
-    let element = args.next().expect("missing argument: element name");
-    let n_bins = args.next()
-        .map(|s| s.parse::<usize>())
-        .expect("missing argument: number of bins")
-        .expect("number of bins");
-    let n_samples = args.next()
-        .map(|s| s.parse::<usize>())
-        .expect("missing argument: number of samples")
-        .expect("number of samples");
-    (scatter_type, element, n_bins, n_samples)
+    let mut next = |name: &str| {
+        args.next()
+            .ok_or_else(|| Error::domain(format!("missing argument: {}", name)))
+    };
+    let scatter_type = next("scatter type")?;
+    let element = next("element name or energy in keV")?;
+    let n_bins = next("number of bins")?
+        .parse::<usize>()
+        .map_err(|_| Error::domain("not a number: number of bins"))?;
+    let n_samples = next("number of samples")?
+        .parse::<usize>()
+        .map_err(|_| Error::domain("not a number: number of samples"))?;
+    Ok((scatter_type, element, n_bins, n_samples))
 }
 
 
@@ -71,33 +103,54 @@ fn handle_cross_section<XS>(
             for mu in sample {
                 hist.fill(*mu.value());
             }
-            plot_histogram(filename, hist.bin_centers(), hist.bin_contents());
+            let curve = expected_counts(&xsection, energy, n_bins, n_samples);
+            plot_histogram(filename, &hist, &curve);
         },
     );
     println!("{:.2}", secs);
 }
 
 fn main() {
-    let (scatter_type, element, n_bins, n_samples) = get_args();
+    if let Err(err) = run() {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
 
+fn run() -> Result<(), Error> {
+    let (scatter_type, element, n_bins, n_samples) = get_args()?;
+
+    // Besides the two named sources, any energy in keV is accepted.
     let energy = match element.as_str() {
         "cerium" => 300.0 * KILO * EV,
         "caesium" => 661.7 * KILO * EV,
-        _ => panic!("bad element name"),
+        _ => match element.parse::<f64>() {
+            Ok(kev) if kev > 0.0 => kev * KILO * EV,
+            _ => return Err(Error::domain(format!("bad element name or energy: {}", element))),
+        },
     };
     let mut filename = element.clone();
+    if element.parse::<f64>().is_ok() {
+        filename.push_str("keV");
+    }
     filename.push('_');
     filename.push_str(&scatter_type);
     filename.push_str(".pdf");
     match scatter_type.as_str() {
         "coherent" => {
-            let coherent = CoherentCrossSection::new("data/AFF.dat").unwrap();
+            let coherent = CoherentCrossSection::new("data/AFF.dat")?;
             handle_cross_section(coherent, &filename, energy, n_bins, n_samples);
         },
         "incoherent" => {
-            let incoherent = IncoherentCrossSection::new("data/ISF.dat").unwrap();
+            let incoherent = IncoherentCrossSection::new("data/ISF.dat")?;
             handle_cross_section(incoherent, &filename, energy, n_bins, n_samples);
         },
-        _ => panic!("bad scatter type"),
+        _ => return Err(Error::domain(format!("bad scatter type: {}", scatter_type))),
     }
+    Ok(())
 }
+
+
+/// The number of intervals of the analytic curve.
+const CURVE_POINTS: usize = 400;
@@ -0,0 +1,82 @@
+use std::cell::Cell;
+
+use dimensioned::si::*;
+use dimensioned::f64prefixes::*;
+
+
+/// The physical constants used by the cross-section code.
+///
+/// The default values are the CODATA 2018 recommended values. For
+/// sensitivity studies, they can be overridden with `set()`. The
+/// constants are stored per thread, so an override only affects the
+/// thread on which `set()` has been called.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Constants {
+    /// The fine-structure constant `α`.
+    pub alpha: f64,
+    /// The classical electron radius `r_e`.
+    pub electron_radius: Meter<f64>,
+    /// The rest energy of the electron `m_e c²`.
+    pub electron_rest_energy: Joule<f64>,
+}
+
+impl Constants {
+    /// Returns the CODATA 2018 recommended values.
+    pub fn codata() -> Self {
+        Constants {
+            alpha: 7.297_352_569_3e-3,
+            electron_radius: 2.817_940_326_2e-15 * M,
+            electron_rest_energy: 510.998_950_00 * KILO * EV,
+        }
+    }
+}
+
+impl Default for Constants {
+    fn default() -> Self {
+        Self::codata()
+    }
+}
+
+
+thread_local! {
+    static CONSTANTS: Cell<Constants> = Cell::new(Constants::codata());
+}
+
+
+/// Returns the constants currently in use on this thread.
+pub fn get() -> Constants {
+    CONSTANTS.with(|constants| constants.get())
+}
+
+
+/// Overrides the constants used on this thread.
+///
+/// Cross-sections that have been tabulated before this call (e.g. by
+/// `CdfSampler` or `MaxCache`) keep the old values.
+pub fn set(constants: Constants) {
+    CONSTANTS.with(|cell| cell.set(constants));
+}
+
+
+/// Restores the CODATA values on this thread.
+pub fn reset() {
+    set(Constants::codata());
+}
+
+
+/// Returns the fine-structure constant `α`.
+pub fn alpha() -> f64 {
+    get().alpha
+}
+
+
+/// Returns the classical electron radius `r_e`.
+pub fn electron_radius() -> Meter<f64> {
+    get().electron_radius
+}
+
+
+/// Returns the rest energy of the electron `m_e c²`.
+pub fn electron_rest_energy() -> Joule<f64> {
+    get().electron_rest_energy
+}
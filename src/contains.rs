@@ -6,6 +6,8 @@ use std::cmp::PartialOrd;
 ///
 /// # Examples
 /// ```
+/// use mcgen::Contains;
+///
 /// assert!((0, 10).contains(5));
 /// assert!(!(0, 10).contains(12));
 /// ```
@@ -0,0 +1,268 @@
+use gnuplot::{Figure, AxesCommon, TickOption};
+use gnuplot::PlotOption::*;
+use gnuplot::AutoOption::*;
+
+use statistics::Statistics;
+
+
+/// The state of an estimate after a given number of samples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Checkpoint {
+    /// The number of samples taken so far.
+    pub samples: usize,
+    /// The mean of all samples taken so far.
+    pub mean: f64,
+    /// The statistical uncertainty of `mean`.
+    pub error: f64,
+}
+
+impl Checkpoint {
+    /// Returns the deviation of the mean from `reference`.
+    pub fn abs_error(&self, reference: f64) -> f64 {
+        self.mean - reference
+    }
+
+    /// Returns the deviation of the mean from `reference`, relative to
+    /// `reference`.
+    pub fn rel_error(&self, reference: f64) -> f64 {
+        self.mean / reference - 1.0
+    }
+}
+
+
+/// Records how a Monte-Carlo estimate converges with the sample size.
+///
+/// A study consumes an iterator of samples and records their mean and
+/// its uncertainty at logarithmically spaced checkpoints: by default
+/// at 10, 100, 1000, … samples. If a reference value is known, the
+/// absolute and relative deviation from it can be plotted as well.
+/// Several studies can be drawn in the same figure to compare
+/// different estimators of the same quantity.
+///
+/// # Examples
+/// ```
+/// use mcgen::convergence::ConvergenceStudy;
+///
+/// let mut study = ConvergenceStudy::new("constant", 1000).with_reference(1.0);
+/// study.fill((0..).map(|i| (i % 2) as f64 * 2.0));
+/// let samples: Vec<_> = study.checkpoints().iter().map(|c| c.samples).collect();
+/// assert_eq!(samples, [10, 100, 1000]);
+/// assert_eq!(study.abs_errors(), Some(vec![0.0, 0.0, 0.0]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConvergenceStudy {
+    name: String,
+    max_samples: usize,
+    per_decade: usize,
+    reference: Option<f64>,
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl ConvergenceStudy {
+    /// Creates an empty study that takes at most `max_samples`
+    /// samples.
+    ///
+    /// `name` is used as the caption in plots.
+    pub fn new<S: Into<String>>(name: S, max_samples: usize) -> Self {
+        ConvergenceStudy {
+            name: name.into(),
+            max_samples,
+            per_decade: 1,
+            reference: None,
+            checkpoints: Vec::new(),
+        }
+    }
+
+    /// Sets the exact value of the estimated quantity.
+    pub fn with_reference(mut self, reference: f64) -> Self {
+        self.reference = Some(reference);
+        self
+    }
+
+    /// Sets the number of checkpoints per factor of ten.
+    ///
+    /// # Panics
+    /// This panics if `per_decade` is zero.
+    pub fn with_checkpoints_per_decade(mut self, per_decade: usize) -> Self {
+        assert!(per_decade > 0, "no checkpoints");
+        self.per_decade = per_decade;
+        self
+    }
+
+    /// Returns the caption of this study.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the exact value of the estimated quantity, if known.
+    pub fn reference(&self) -> Option<f64> {
+        self.reference
+    }
+
+    /// Returns the checkpoints recorded by `fill()`.
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+
+    /// Takes samples from `sample` and records the checkpoints.
+    ///
+    /// Previously recorded checkpoints are discarded. If `sample` runs
+    /// out, the last incomplete checkpoint is dropped.
+    pub fn fill<I>(&mut self, mut sample: I)
+    where
+        I: Iterator<Item = f64>,
+    {
+        self.checkpoints.clear();
+        let mut stats = Statistics::new();
+        for samples in self.checkpoint_sizes() {
+            let missing = samples - stats.count() as usize;
+            stats.extend(sample.by_ref().take(missing));
+            let error = match stats.error_of_mean() {
+                Some(error) if stats.count() as usize == samples => error,
+                _ => break,
+            };
+            self.checkpoints.push(Checkpoint {
+                samples,
+                mean: stats.mean(),
+                error,
+            });
+        }
+    }
+
+    /// Returns the absolute deviation from the reference at each
+    /// checkpoint.
+    ///
+    /// If there is no reference value, this returns `None`.
+    pub fn abs_errors(&self) -> Option<Vec<f64>> {
+        self.reference.map(|reference| {
+            self.checkpoints
+                .iter()
+                .map(|checkpoint| checkpoint.abs_error(reference))
+                .collect()
+        })
+    }
+
+    /// Returns the relative deviation from the reference at each
+    /// checkpoint.
+    ///
+    /// If there is no reference value, this returns `None`.
+    pub fn rel_errors(&self) -> Option<Vec<f64>> {
+        self.reference.map(|reference| {
+            self.checkpoints
+                .iter()
+                .map(|checkpoint| checkpoint.rel_error(reference))
+                .collect()
+        })
+    }
+
+    /// Plots the mean with its uncertainty against the sample size.
+    ///
+    /// The figure is saved as PDF under the path `filename`. Each study
+    /// is drawn in its own color.
+    pub fn plot_means(studies: &[&Self], filename: &str, y_label: &str) {
+        let mut figure = Figure::new();
+        {
+            let axes = Self::log_axes(&mut figure, studies, filename, y_label);
+            for (study, &color) in studies.iter().zip(COLORS.iter().cycle()) {
+                let means = study.checkpoints.iter().map(|c| c.mean);
+                axes.y_error_lines(
+                    study.sizes(),
+                    means,
+                    study.errors(),
+                    &[Color(color), Caption(&study.name)],
+                );
+            }
+        }
+        figure.show();
+    }
+
+    /// Plots the deviation from the reference with the uncertainty of
+    /// the mean against the sample size.
+    ///
+    /// Studies without a reference value are skipped.
+    pub fn plot_abs_errors(studies: &[&Self], filename: &str, y_label: &str) {
+        let mut figure = Figure::new();
+        {
+            let axes = Self::log_axes(&mut figure, studies, filename, y_label);
+            for (study, &color) in studies.iter().zip(COLORS.iter().cycle()) {
+                if let Some(abs_errors) = study.abs_errors() {
+                    axes.y_error_lines(
+                        study.sizes(),
+                        abs_errors,
+                        study.errors(),
+                        &[Color(color), Caption(&study.name)],
+                    );
+                }
+            }
+        }
+        figure.show();
+    }
+
+    /// Plots the relative deviation from the reference against the
+    /// sample size.
+    ///
+    /// Studies without a reference value are skipped.
+    pub fn plot_rel_errors(studies: &[&Self], filename: &str, y_label: &str) {
+        let mut figure = Figure::new();
+        {
+            let axes = Self::log_axes(&mut figure, studies, filename, y_label);
+            for (study, &color) in studies.iter().zip(COLORS.iter().cycle()) {
+                if let Some(rel_errors) = study.rel_errors() {
+                    axes.points(study.sizes(), rel_errors, &[Color(color), Caption(&study.name)]);
+                }
+            }
+        }
+        figure.show();
+    }
+
+    /// Private function that returns the sample sizes at which
+    /// checkpoints are recorded.
+    fn checkpoint_sizes(&self) -> Vec<usize> {
+        let mut sizes = Vec::new();
+        for step in self.per_decade.. {
+            let size = (10f64.powf(step as f64 / self.per_decade as f64)).round() as usize;
+            if size > self.max_samples {
+                break;
+            }
+            if sizes.last() != Some(&size) {
+                sizes.push(size);
+            }
+        }
+        sizes
+    }
+
+    /// Private function that returns the sample size of each
+    /// checkpoint.
+    fn sizes(&self) -> Vec<usize> {
+        self.checkpoints.iter().map(|c| c.samples).collect()
+    }
+
+    /// Private function that returns the uncertainty of each
+    /// checkpoint.
+    fn errors(&self) -> Vec<f64> {
+        self.checkpoints.iter().map(|c| c.error).collect()
+    }
+
+    /// Private function that sets up a figure with a logarithmic
+    /// X-axis of sample sizes.
+    fn log_axes<'a>(
+        figure: &'a mut Figure,
+        studies: &[&Self],
+        filename: &str,
+        y_label: &str,
+    ) -> &'a mut gnuplot::Axes2D {
+        let max_samples = studies.iter().map(|study| study.max_samples).max().unwrap_or(10);
+        figure
+            .set_terminal("pdfcairo", filename)
+            .axes2d()
+            .set_x_label("Sample size", &[])
+            .set_x_range(Fix(1.0), Fix(max_samples as f64))
+            .set_x_log(Some(10.0))
+            .set_x_ticks(Some((Auto, 0)), &[TickOption::Format("10^{%T}")], &[])
+            .set_y_label(y_label, &[])
+    }
+}
+
+
+/// The colors used for the studies in a plot, in order.
+const COLORS: [&str; 6] = ["black", "red", "blue", "dark-green", "orange", "violet"];
@@ -0,0 +1,212 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+
+use logging;
+use constants;
+
+
+/// Common trait of all differential cross-sections of electrons.
+///
+/// This is the electron-specific counterpart of `CrossSection`. Instead
+/// of the scattering angle, electron interactions are described by the
+/// fraction `eps` of the electron's kinetic energy that is transferred
+/// to the secondary particle (a delta electron or a bremsstrahlung
+/// photon). The angles follow from the kinematics or are small enough
+/// to be neglected.
+///
+/// Both interactions diverge for small energy transfers. Thus, each
+/// cross-section has a cutoff energy: smaller transfers are considered
+/// part of the continuous energy loss, and only larger ones are
+/// simulated as discrete events.
+pub trait ElectronCrossSection {
+    /// Evaluates the cross-section `dσ/d(eps)` per atom at the given
+    /// kinetic energy and energy fraction `eps`.
+    fn eval(&self, energy: Joule<f64>, eps: Unitless<f64>) -> Meter2<f64>;
+
+    /// Returns the range of `eps` of discrete events at the given
+    /// kinetic energy.
+    ///
+    /// If the lower bound is not below the upper bound, no discrete
+    /// events are possible.
+    fn eps_range(&self, energy: Joule<f64>) -> (f64, f64);
+
+    /// Randomly generates a value of `eps` at the given kinetic energy.
+    ///
+    /// # Panics
+    /// This may panic if no discrete events are possible at `energy`.
+    fn sample_eps<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Unitless<f64>
+    where
+        Self: Sized;
+
+    /// Returns the total cross-section of discrete events.
+    ///
+    /// This is the integral of `eval()` over `eps_range()`. By default,
+    /// it is calculated numerically with Simpson's rule in `ln(eps)`.
+    fn total(&self, energy: Joule<f64>) -> Meter2<f64> {
+        let (low, high) = self.eps_range(energy);
+        if low >= high {
+            return 0.0 * M2;
+        }
+        let n = TOTAL_INTERVALS;
+        let h = (high / low).ln() / n as f64;
+        let eval = |i: usize| {
+            let eps = low * (h * i as f64).exp();
+            self.eval(energy, Unitless::new(eps)) * eps
+        };
+        let mut sum = eval(0) + eval(n);
+        for i in 1..n {
+            let factor = if i % 2 == 1 { 4.0 } else { 2.0 };
+            sum += eval(i) * factor;
+        }
+        sum * (h / 3.0)
+    }
+}
+
+
+/// The Møller cross-section of electron–electron scattering.
+///
+/// This describes the production of delta electrons by an electron
+/// that scatters on the atomic electrons, which are treated as free
+/// and at rest. Because both electrons are indistinguishable, the one
+/// with the lower energy is called the secondary, so that `eps` lies
+/// between the cutoff and 1/2.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MollerCrossSection {
+    electrons: f64,
+    cutoff: Joule<f64>,
+}
+
+impl MollerCrossSection {
+    /// Creates the cross-section of an atom with the given number of
+    /// electrons (i.e. `Z`) and a cutoff energy for delta electrons.
+    pub fn new(electrons: f64, cutoff: Joule<f64>) -> Self {
+        MollerCrossSection { electrons, cutoff }
+    }
+
+    /// Returns the cutoff energy for delta electrons.
+    pub fn cutoff(&self) -> Joule<f64> {
+        self.cutoff
+    }
+
+    /// Private function that returns `tau = T/(m_e c²)`, `gamma` and
+    /// `beta²`.
+    fn kinematics(energy: Joule<f64>) -> (f64, f64, f64) {
+        let tau = *(energy / constants::electron_rest_energy()).value();
+        let gamma = tau + 1.0;
+        let beta_squared = tau * (tau + 2.0) / (gamma * gamma);
+        (tau, gamma, beta_squared)
+    }
+}
+
+impl ElectronCrossSection for MollerCrossSection {
+    fn eval(&self, energy: Joule<f64>, eps: Unitless<f64>) -> Meter2<f64> {
+        let (tau, gamma, beta_squared) = Self::kinematics(energy);
+        let eps = *eps.value();
+        let bracket = 1.0 / (eps * eps) + 1.0 / ((1.0 - eps) * (1.0 - eps)) + (tau / gamma).powi(2) -
+            (2.0 * tau + 1.0) / (gamma * gamma) / (eps * (1.0 - eps));
+        let r_e = constants::electron_radius();
+        r_e * r_e * (2.0 * PI * self.electrons / (beta_squared * tau) * bracket)
+    }
+
+    fn eps_range(&self, energy: Joule<f64>) -> (f64, f64) {
+        (*(self.cutoff / energy).value(), 0.5)
+    }
+
+    fn sample_eps<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Unitless<f64> {
+        let (tau, gamma, _) = Self::kinematics(energy);
+        let (low, high) = self.eps_range(energy);
+        assert!(low < high, "energy below Møller cutoff");
+        let ratio = (tau / gamma).powi(2);
+        // Sample from `1/eps²` and accept with `eps²` times the
+        // bracket of `eval()`, which is bounded by `2 + ratio/4`.
+        let max = 2.0 + ratio / 4.0;
+        loop {
+            let eps = low / (1.0 - rng.gen::<f64>() * (1.0 - low / high));
+            let weight = 1.0 + (eps / (1.0 - eps)).powi(2) + ratio * eps * eps -
+                (1.0 - ratio) * eps / (1.0 - eps);
+            if logging::ELECTRON.count(rng.gen::<f64>() * max < weight) {
+                return Unitless::new(eps);
+            }
+        }
+    }
+}
+
+
+/// A simple bremsstrahlung cross-section.
+///
+/// This is the Bethe–Heitler cross-section in the limit of complete
+/// screening, including the contribution of the atomic electrons via
+/// the factor `Z(Z + 1)`. It is accurate for electrons well above
+/// 50 MeV and gives the right order of magnitude below. Here, `eps` is
+/// the fraction of the kinetic energy emitted as a photon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BremsstrahlungCrossSection {
+    z: f64,
+    cutoff: Joule<f64>,
+}
+
+impl BremsstrahlungCrossSection {
+    /// Creates the cross-section of an atom of atomic number `z` and a
+    /// cutoff energy for the emitted photons.
+    pub fn new(z: f64, cutoff: Joule<f64>) -> Self {
+        BremsstrahlungCrossSection { z, cutoff }
+    }
+
+    /// Returns the cutoff energy for bremsstrahlung photons.
+    pub fn cutoff(&self) -> Joule<f64> {
+        self.cutoff
+    }
+
+    /// Private method that evaluates the bracket of the Bethe–Heitler
+    /// formula at `y`, the photon energy divided by the total energy of
+    /// the electron.
+    fn bracket(&self, y: f64) -> f64 {
+        let log = (183.0 * self.z.powf(-1.0 / 3.0)).ln();
+        (1.0 - y + 0.75 * y * y) * log + (1.0 - y) / 9.0
+    }
+
+    /// Private function that returns the ratio of kinetic and total
+    /// energy of the electron.
+    fn kinetic_fraction(energy: Joule<f64>) -> f64 {
+        let total = energy + constants::electron_rest_energy();
+        *(energy / total).value()
+    }
+}
+
+impl ElectronCrossSection for BremsstrahlungCrossSection {
+    fn eval(&self, energy: Joule<f64>, eps: Unitless<f64>) -> Meter2<f64> {
+        let eps = *eps.value();
+        let y = eps * Self::kinetic_fraction(energy);
+        let r_e = constants::electron_radius();
+        r_e * r_e * (4.0 * constants::alpha() * self.z * (self.z + 1.0) / eps * self.bracket(y))
+    }
+
+    fn eps_range(&self, energy: Joule<f64>) -> (f64, f64) {
+        (*(self.cutoff / energy).value(), 1.0)
+    }
+
+    fn sample_eps<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Unitless<f64> {
+        let (low, high) = self.eps_range(energy);
+        assert!(low < high, "energy below bremsstrahlung cutoff");
+        let fraction = Self::kinetic_fraction(energy);
+        // Sample from `1/eps` and accept with the bracket, which is
+        // largest at `y = 0`.
+        let max = self.bracket(0.0);
+        loop {
+            let eps = low * (high / low).powf(rng.gen::<f64>());
+            if logging::ELECTRON.count(rng.gen::<f64>() * max < self.bracket(eps * fraction)) {
+                return Unitless::new(eps);
+            }
+        }
+    }
+}
+
+
+/// The number of intervals used by `ElectronCrossSection::total()`.
+///
+/// This must be even for Simpson's rule.
+const TOTAL_INTERVALS: usize = 200;
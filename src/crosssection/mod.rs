@@ -0,0 +1,1521 @@
+pub mod electron;
+pub mod validate;
+
+use std::ops::Deref;
+use std::sync::Arc;
+use std::cell::Cell;
+use std::path::Path;
+use std::f64::consts::PI;
+
+use rand::Rng;
+use rand::distributions::{self, Sample, IndependentSample};
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+use dimensioned::f64prefixes::*;
+
+use super::Function;
+use logging;
+use error::Error;
+use sample::Rejection;
+use constants::{self, electron_rest_energy};
+
+
+/// Common trait of all angular spectral cross-sections.
+pub trait CrossSection {
+    /// Evaluates the cross-section in an infinitesimal phase-space
+    /// volume around the given `energy` and `mu`.
+    ///
+    /// `mu` is `theta.cos()`, where `theta` is the angle between the
+    /// particle's original and new direction. `theta` lies between
+    /// 0° and 180°, so `mu` lies between +1 and –1.
+    fn eval(&self, energy: Joule<f64>, mu: Unitless<f64>) -> Meter2<f64>;
+
+    /// Returns the maximum angular spectral cross-section for a given
+    /// energy.
+    ///
+    /// This is necessary for the rejection method to work.
+    fn max(&self, energy: Joule<f64>) -> Meter2<f64>;
+
+    /// Returns the total cross-section, integrated over all angles.
+    ///
+    /// This is `2π` times the integral of `eval()` over `mu` from –1
+    /// to +1. By default, the integral is calculated numerically with
+    /// Simpson's rule on a fixed grid. Types with an analytic
+    /// expression should override this.
+    fn total(&self, energy: Joule<f64>) -> Meter2<f64> {
+        let n = TOTAL_INTERVALS;
+        let h = 2.0 / n as f64;
+        let eval = |i: usize| self.eval(energy, Unitless::new(-1.0 + h * i as f64));
+        let mut sum = eval(0) + eval(n);
+        for i in 1..n {
+            let factor = if i % 2 == 1 { 4.0 } else { 2.0 };
+            sum += eval(i) * factor;
+        }
+        sum * (2.0 * PI * h / 3.0)
+    }
+
+    /// Tabulates the cross-section at the given energy.
+    ///
+    /// The cross-section is evaluated at `n_points` equally spaced
+    /// values of `mu` from –1 to +1. The result can be plotted or
+    /// compared to a histogram of sampled values of `mu`.
+    ///
+    /// # Panics
+    /// This panics if `n_points` is less than two.
+    fn tabulate(&self, energy: Joule<f64>, n_points: usize) -> Function<Unitless<f64>, Meter2<f64>> {
+        assert!(n_points >= 2, "too few points");
+        let mu = |i: usize| Unitless::new(-1.0 + 2.0 * i as f64 / (n_points - 1) as f64);
+        let mut function = Function::with_capacity(n_points, mu(0), self.eval(energy, mu(0)));
+        function.extend((1..n_points).map(|i| (mu(i), self.eval(energy, mu(i)))));
+        function
+    }
+
+    /// Randomly generates a value of `mu` at the given energy.
+    ///
+    /// By default, this uses a `RejectionSampler`. Types that allow
+    /// faster sampling, e.g. analytically, should override this.
+    fn sample_mu<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Unitless<f64>
+    where
+        Self: Sized,
+    {
+        RejectionSampler::new(self, energy).gen_mu(rng)
+    }
+}
+
+
+/// A coherent scattering cross-section that depends on an atomic form
+/// factor.
+#[derive(Debug)]
+pub struct CoherentCrossSection {
+    form_factor: Function<Joule<f64>, Unitless<f64>>,
+}
+
+impl CoherentCrossSection {
+    /// Creates a cross-section with the atomic form factor from the
+    /// given file.
+    pub fn new<P>(form_factor_file: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let form_factor = Function::<f64>::from_file(form_factor_file)?
+            .scale(KILO * EV, Unitless::new(1.0));
+        let result = CoherentCrossSection { form_factor };
+        Ok(result)
+    }
+
+    /// Creates a cross-section with an analytic atomic form factor.
+    ///
+    /// The fit is tabulated on a logarithmic grid, see
+    /// `FormFactorFit::tabulate_form_factor()`.
+    pub fn from_fit(fit: &FormFactorFit) -> Self {
+        CoherentCrossSection { form_factor: fit.tabulate_form_factor() }
+    }
+
+    /// Evaluates the atomic form factor at the given energy and `mu`.
+    ///
+    /// `mu` is `cos(theta)`, where `theta` is the polar angle.
+    pub fn form_factor(&self, energy: Joule<f64>, mu: Unitless<f64>) -> Unitless<f64> {
+        self.form_factor.call(get_x(energy, mu))
+    }
+}
+
+impl CrossSection for CoherentCrossSection {
+    fn eval(&self, energy: Joule<f64>, mu: Unitless<f64>) -> Meter2<f64> {
+        let form_factor = self.form_factor(energy, mu);
+        r_e() * r_e() * (1.0 + mu * mu) / 2.0 * form_factor * form_factor
+    }
+
+    fn max(&self, energy: Joule<f64>) -> Meter2<f64> {
+        self.eval(energy, Unitless::new(1.0))
+    }
+}
+
+
+/// An incoherent scattering cross-section that depends on an
+/// incoherent scattering function.
+///
+/// Optionally, the cross-section may also carry the Compton profile of
+/// the atom. In this case, `gen_scattered_energy()` takes the momentum
+/// of the bound electrons into account (Doppler broadening).
+#[derive(Debug)]
+pub struct IncoherentCrossSection {
+    scattering_function: Function<Joule<f64>, Unitless<f64>>,
+    compton_profile: Option<ComptonProfile>,
+}
+
+impl IncoherentCrossSection {
+    /// Creates a cross-section with the atomic form factor from the
+    /// given file.
+    pub fn new<P>(scattering_function_file: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let scattering_function = Function::<f64>::from_file(scattering_function_file)?
+            .scale(KILO * EV, Unitless::new(1.0));
+        let result = IncoherentCrossSection {
+            scattering_function,
+            compton_profile: None,
+        };
+        Ok(result)
+    }
+
+    /// Creates a cross-section with an analytic incoherent scattering
+    /// function.
+    ///
+    /// The fit is tabulated on a logarithmic grid, see
+    /// `FormFactorFit::tabulate_scattering_function()`.
+    pub fn from_fit(fit: &FormFactorFit) -> Self {
+        IncoherentCrossSection {
+            scattering_function: fit.tabulate_scattering_function(),
+            compton_profile: None,
+        }
+    }
+
+    /// Adds the Compton profile from the given file.
+    ///
+    /// See `ComptonProfile::new()` for the file format.
+    pub fn with_compton_profile<P>(mut self, compton_profile_file: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        self.compton_profile = Some(ComptonProfile::new(compton_profile_file)?);
+        Ok(self)
+    }
+
+    /// Returns the Compton profile, if any.
+    pub fn compton_profile(&self) -> Option<&ComptonProfile> {
+        self.compton_profile.as_ref()
+    }
+
+    /// Calculates the resulting energy of the Compton formula.
+    ///
+    /// `mu` is `cos(theta)`, where `theta` is the polar angle. `energy`
+    /// is the incident particle's energy, the result is the energy
+    /// after
+    /// the scattering process.
+    pub fn compton_scatter(energy: Joule<f64>, mu: Unitless<f64>) -> Joule<f64> {
+        let kappa = energy / electron_rest_energy();
+        let kappa_antimu = kappa * (1.0 - mu);
+        energy / (1.0 + kappa_antimu)
+    }
+
+    /// Returns the energy of photons scattered by 180°.
+    ///
+    /// This is the smallest energy a photon can have after Compton
+    /// scattering. Photons backscattered in the material surrounding a
+    /// detector form the backscatter peak at this energy.
+    pub fn backscatter_peak(energy: Joule<f64>) -> Joule<f64> {
+        Self::compton_scatter(energy, Unitless::new(-1.0))
+    }
+
+    /// Returns the largest energy that can be transferred to the
+    /// electron in a single Compton scattering.
+    ///
+    /// This is `E 2k/(1 + 2k)`, where `k = E/(m_e c²)`.
+    pub fn max_energy_transfer(energy: Joule<f64>) -> Joule<f64> {
+        energy - Self::backscatter_peak(energy)
+    }
+
+    /// Returns the energy of the Compton edge in a detector spectrum.
+    ///
+    /// The edge lies at the largest energy that a single Compton
+    /// scattering deposits, i.e. at `max_energy_transfer()`. For
+    /// Cs-137 (661.7 keV), this is about 477 keV.
+    pub fn compton_edge(energy: Joule<f64>) -> Joule<f64> {
+        Self::max_energy_transfer(energy)
+    }
+
+    /// Randomly generates the energy after the scattering process.
+    ///
+    /// Without a Compton profile, this is simply `compton_scatter()`.
+    /// Otherwise, the projection `p_z` of the initial momentum of the
+    /// electron on the scattering vector is sampled from the profile
+    /// and the energy is calculated from the relativistic kinematics of
+    /// scattering on a moving electron (impulse approximation). This
+    /// broadens the energy distribution around the Compton energy.
+    ///
+    /// Values of `p_z` that would give a negative energy or one above
+    /// the incident energy are rejected. If no valid value is found
+    /// after a number of tries, the Compton energy is returned.
+    pub fn gen_scattered_energy<R: Rng>(&self, energy: Joule<f64>, mu: Unitless<f64>, rng: &mut R) -> Joule<f64> {
+        let compton = Self::compton_scatter(energy, mu);
+        let profile = match self.compton_profile {
+            Some(ref profile) => profile,
+            None => return compton,
+        };
+        for _ in 0..DOPPLER_TRIES {
+            let p = profile.gen_momentum(rng);
+            if let Some(scattered) = Self::doppler_scatter(energy, mu, p) {
+                return scattered;
+            }
+        }
+        compton
+    }
+
+    /// Randomly generates `mu` and the energy after the scattering
+    /// process together.
+    ///
+    /// Without a Compton profile, `mu` is sampled with `sample_mu()`
+    /// and the energy is given by `compton_scatter()`.
+    ///
+    /// Otherwise, `mu` and `p_z` are sampled jointly from the
+    /// double-differential cross-section in the impulse approximation:
+    /// `mu` is drawn from the Klein–Nishina cross-section and `p_z`
+    /// from the Compton profile, and the pair is rejected as a whole if
+    /// it gives an impossible energy (see `gen_scattered_energy()`). In
+    /// the impulse approximation, the fraction of accepted `p_z` at a
+    /// given angle takes the place of the incoherent scattering
+    /// function. Thus, angle and energy stay consistent with each
+    /// other, whereas sampling `mu` with `S(x, Z)` and the energy
+    /// independently accounts for binding twice.
+    pub fn gen_scatter<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> (Unitless<f64>, Joule<f64>) {
+        let profile = match self.compton_profile {
+            Some(ref profile) => profile,
+            None => {
+                let mu = self.sample_mu(energy, rng);
+                return (mu, Self::compton_scatter(energy, mu));
+            },
+        };
+        let kappa = *(energy / electron_rest_energy()).value();
+        loop {
+            let mu = Unitless::new(gen_klein_nishina(kappa, rng));
+            let p = profile.gen_momentum(rng);
+            let scattered = Self::doppler_scatter(energy, mu, p);
+            if logging::DOPPLER.count(scattered.is_some()) {
+                return (mu, scattered.expect("checked above"));
+            }
+        }
+    }
+
+    /// Private function that calculates the energy after scattering on
+    /// an electron with momentum projection `p` (in units of `m_e c`).
+    ///
+    /// If the result would be negative or above the incident energy,
+    /// `None` is returned.
+    fn doppler_scatter(energy: Joule<f64>, mu: Unitless<f64>, p: f64) -> Option<Joule<f64>> {
+        let tau_c = *(Self::compton_scatter(energy, mu) / energy).value();
+        let mu = *mu.value();
+        let t = p * p;
+        let a = 1.0 - t * tau_c * mu;
+        let b = 1.0 - t * tau_c * tau_c;
+        let discriminant = a * a - b * (1.0 - t);
+        if b <= 0.0 || discriminant < 0.0 {
+            return None;
+        }
+        let tau = tau_c / b * (a + p.signum() * discriminant.sqrt());
+        if tau > 0.0 && tau < 1.0 {
+            Some(energy * tau)
+        } else {
+            None
+        }
+    }
+
+    /// Evaluates the incoherent scattering function at the given
+    /// energy and `mu`.
+    ///
+    /// `mu` is `cos(theta)`, where `theta` is the polar angle.
+    pub fn scattering_function(&self, energy: Joule<f64>, mu: Unitless<f64>) -> Unitless<f64> {
+        self.scattering_function.call(get_x(energy, mu))
+    }
+
+    /// Calculates the total Klein–Nishina cross-section at the given
+    /// energy.
+    ///
+    /// This is the analytic integral of `klein_nishina()` over all
+    /// angles, i.e. the cross-section of a free electron at rest.
+    pub fn klein_nishina_total(energy: Joule<f64>) -> Meter2<f64> {
+        let k = *(energy / electron_rest_energy()).value();
+        let ln = (1.0 + 2.0 * k).ln();
+        let factor = (1.0 + k) / (k * k) * (2.0 * (1.0 + k) / (1.0 + 2.0 * k) - ln / k) + ln / (2.0 * k) -
+            (1.0 + 3.0 * k) / ((1.0 + 2.0 * k) * (1.0 + 2.0 * k));
+        r_e() * r_e() * (2.0 * PI * factor)
+    }
+
+    /// Calculates the Klein–Nishina cross-section at the given energy
+    /// and `mu`.
+    ///
+    /// `mu` is `cos(theta)`, where `theta` is the polar angle.
+    pub fn klein_nishina(&self, energy: Joule<f64>, mu: Unitless<f64>) -> Meter2<f64> {
+        KleinNishinaCrossSection.eval(energy, mu)
+    }
+}
+
+impl CrossSection for IncoherentCrossSection {
+    fn eval(&self, energy: Joule<f64>, mu: Unitless<f64>) -> Meter2<f64> {
+        self.klein_nishina(energy, mu) * self.scattering_function(energy, mu)
+    }
+
+    /// Searches the maximum of the cross-section at the given energy.
+    ///
+    /// The cross-section is first evaluated on a coarse grid of `mu`.
+    /// Around the largest value found, the maximum is then refined with
+    /// a golden-section search. Because the search may miss a narrow
+    /// peak between grid points, the result is increased by a safety
+    /// margin of a few percent.
+    ///
+    /// This is much tighter than the product of the maxima of the
+    /// Klein–Nishina factor and the scattering function, especially at
+    /// low energies. It is also more expensive, so use a `MaxCache` to
+    /// cache the results.
+    fn max(&self, energy: Joule<f64>) -> Meter2<f64> {
+        let eval = |mu: f64| *(self.eval(energy, Unitless::new(mu)) / M2).value();
+        let step = 2.0 / MAX_SCAN_INTERVALS as f64;
+        let mut best_mu = -1.0;
+        let mut best = eval(best_mu);
+        for i in 1..MAX_SCAN_INTERVALS + 1 {
+            let mu = -1.0 + step * i as f64;
+            let value = eval(mu);
+            if value > best {
+                best_mu = mu;
+                best = value;
+            }
+        }
+        // Golden-section search in the neighborhood of the best point.
+        let ratio = (5f64.sqrt() - 1.0) / 2.0;
+        let mut low = (best_mu - step).max(-1.0);
+        let mut high = (best_mu + step).min(1.0);
+        for _ in 0..MAX_REFINE_STEPS {
+            let left = high - ratio * (high - low);
+            let right = low + ratio * (high - low);
+            if eval(left) > eval(right) {
+                high = right;
+            } else {
+                low = left;
+            }
+        }
+        let best = best.max(eval((low + high) / 2.0));
+        best * MAX_SAFETY_MARGIN * M2
+    }
+
+    fn sample_mu<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Unitless<f64> {
+        KahnSampler::new(self, energy).gen_mu(rng)
+    }
+}
+
+
+/// An analytic fit of the atomic form factor.
+///
+/// The form factor is parameterized as a sum of four Gaussians and a
+/// constant in `s = sin(theta/2)/lambda`,
+/// `F(s) = sum(a_i exp(-b_i s²)) + c`, as in the International Tables
+/// for Crystallography. Such fits are only valid up to
+/// `s = 2 Å⁻¹`, i.e. `x = 24.8 keV`. Beyond that, the fit is continued
+/// with the asymptotic behavior `F ∝ s⁻⁴`.
+///
+/// The incoherent scattering function is approximated from the form
+/// factor as `S = Z - F²/Z`. This is exact for a single electron and
+/// has the correct limits for small and large `x`, but overestimates
+/// `S` in between.
+///
+/// This is an alternative to the tabulated data files if those are
+/// unavailable. Coefficients for some common elements are built in,
+/// see `for_element()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormFactorFit {
+    z: u32,
+    a: [f64; 4],
+    b: [f64; 4],
+    c: f64,
+}
+
+impl FormFactorFit {
+    /// Creates a fit with the given coefficients.
+    ///
+    /// `b` is given in `Å²`.
+    pub fn new(z: u32, a: [f64; 4], b: [f64; 4], c: f64) -> Self {
+        FormFactorFit { z, a, b, c }
+    }
+
+    /// Returns the built-in fit for the element with the given atomic
+    /// number.
+    ///
+    /// Fits are available for H, C, N, O, Al, Si, Fe, Cu, W and Pb. For
+    /// all other elements, `None` is returned.
+    pub fn for_element(z: u32) -> Option<Self> {
+        FORM_FACTOR_FITS
+            .iter()
+            .find(|&&(fit_z, _, _, _)| fit_z == z)
+            .map(|&(z, a, b, c)| Self::new(z, a, b, c))
+    }
+
+    /// Returns the atomic number of the element.
+    pub fn z(&self) -> u32 {
+        self.z
+    }
+
+    /// Evaluates the atomic form factor at the given `x`.
+    ///
+    /// `x` is `E * sin(theta/2)`, see `CoherentCrossSection`.
+    pub fn form_factor(&self, x: Joule<f64>) -> Unitless<f64> {
+        let s = *(x / (KILO * EV)).value() / HC_KEV_ANGSTROM;
+        let value = if s <= FIT_S_MAX {
+            self.gaussians(s)
+        } else {
+            self.gaussians(FIT_S_MAX) * (FIT_S_MAX / s).powi(4)
+        };
+        Unitless::new(value)
+    }
+
+    /// Evaluates the approximate incoherent scattering function at the
+    /// given `x`.
+    pub fn scattering_function(&self, x: Joule<f64>) -> Unitless<f64> {
+        let z = self.z as f64;
+        let f = *self.form_factor(x).value();
+        Unitless::new((z - f * f / z).max(0.0))
+    }
+
+    /// Tabulates the atomic form factor for use by
+    /// `CoherentCrossSection`.
+    ///
+    /// The grid spans the same range as the data files, from 0 to
+    /// `10⁹ keV`.
+    pub fn tabulate_form_factor(&self) -> Function<Joule<f64>, Unitless<f64>> {
+        self.tabulate(|x| self.form_factor(x))
+    }
+
+    /// Tabulates the incoherent scattering function for use by
+    /// `IncoherentCrossSection`.
+    pub fn tabulate_scattering_function(&self) -> Function<Joule<f64>, Unitless<f64>> {
+        self.tabulate(|x| self.scattering_function(x))
+    }
+
+    /// Private method that evaluates the sum of Gaussians.
+    fn gaussians(&self, s: f64) -> f64 {
+        let sum: f64 = self.a
+            .iter()
+            .zip(&self.b)
+            .map(|(a, b)| a * (-b * s * s).exp())
+            .sum();
+        sum + self.c
+    }
+
+    /// Private method that tabulates a function of `x`.
+    fn tabulate<F>(&self, func: F) -> Function<Joule<f64>, Unitless<f64>>
+    where
+        F: Fn(Joule<f64>) -> Unitless<f64>,
+    {
+        let zero = 0.0 * KILO * EV;
+        let mut function = Function::with_capacity(FIT_GRID_POINTS + 1, zero, func(zero));
+        for i in 0..FIT_GRID_POINTS {
+            // Twelve decades from 10⁻³ keV to 10⁹ keV.
+            let exponent = -3.0 + 12.0 * i as f64 / (FIT_GRID_POINTS - 1) as f64;
+            let x = 10f64.powf(exponent) * KILO * EV;
+            function.push(x, func(x));
+        }
+        function
+    }
+}
+
+
+/// The Thomson cross-section of a free electron.
+///
+/// This is the low-energy limit of both coherent and incoherent
+/// scattering, neglecting electron recoil and binding. It needs no
+/// tabulated data and is mostly useful for validation.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ThomsonCrossSection;
+
+impl CrossSection for ThomsonCrossSection {
+    fn eval(&self, _: Joule<f64>, mu: Unitless<f64>) -> Meter2<f64> {
+        r_e() * r_e() * (1.0 + mu * mu) / 2.0
+    }
+
+    fn max(&self, _: Joule<f64>) -> Meter2<f64> {
+        r_e() * r_e()
+    }
+
+    fn total(&self, _: Joule<f64>) -> Meter2<f64> {
+        r_e() * r_e() * (8.0 * PI / 3.0)
+    }
+}
+
+
+/// The Klein–Nishina cross-section of a free electron at rest.
+///
+/// This is `IncoherentCrossSection` without the incoherent scattering
+/// function, i.e. without binding effects. It needs no tabulated data
+/// and is a good approximation at high energies or for light elements.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KleinNishinaCrossSection;
+
+impl CrossSection for KleinNishinaCrossSection {
+    fn eval(&self, energy: Joule<f64>, mu: Unitless<f64>) -> Meter2<f64> {
+        let kappa = energy / electron_rest_energy();
+        let kappa_antimu = kappa * (1.0 - mu);
+        let alpha_func = 1.0 / (1.0 + kappa_antimu);
+        r_e() * r_e() / 2.0 * alpha_func * alpha_func * (alpha_func + kappa_antimu + mu * mu)
+    }
+
+    fn max(&self, energy: Joule<f64>) -> Meter2<f64> {
+        self.eval(energy, Unitless::new(1.0))
+    }
+
+    fn total(&self, energy: Joule<f64>) -> Meter2<f64> {
+        IncoherentCrossSection::klein_nishina_total(energy)
+    }
+
+    fn sample_mu<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Unitless<f64> {
+        let kappa = *(energy / electron_rest_energy()).value();
+        Unitless::new(gen_klein_nishina(kappa, rng))
+    }
+}
+
+
+/// The Compton profile `J(p_z)` of an atom.
+///
+/// The Compton profile is the distribution of the projection `p_z` of
+/// the momentum of the atom's electrons on the scattering vector. It is
+/// symmetric in `p_z` and normalized to the number of electrons.
+#[derive(Debug, Clone)]
+pub struct ComptonProfile {
+    /// The tabulated values of `p_z` in atomic units.
+    momenta: Vec<f64>,
+    /// The cumulative distribution at each tabulated `p_z`, normalized
+    /// to one at the last point.
+    cumulative: Vec<f64>,
+}
+
+impl ComptonProfile {
+    /// Reads a Compton profile from the given file.
+    ///
+    /// The file must have two columns, `p_z` in atomic units and
+    /// `J(p_z)` in inverse atomic units, as in the tables by Biggs et
+    /// al. Only non-negative values of `p_z` are given; the profile
+    /// is mirrored for negative ones.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be read or the profile vanishes
+    /// everywhere.
+    pub fn new<P>(compton_profile_file: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let profile = Function::<f64>::from_file(compton_profile_file)?;
+        let momenta = profile.xdata().to_vec();
+        let values = profile.ydata();
+        let mut cumulative = Vec::with_capacity(momenta.len());
+        let mut sum = 0.0;
+        cumulative.push(0.0);
+        for i in 1..momenta.len() {
+            sum += (values[i - 1] + values[i]) / 2.0 * (momenta[i] - momenta[i - 1]);
+            cumulative.push(sum);
+        }
+        if sum <= 0.0 {
+            return Err(Error::domain("Compton profile vanishes"));
+        }
+        for p in &mut cumulative {
+            *p /= sum;
+        }
+        Ok(ComptonProfile { momenta, cumulative })
+    }
+
+    /// Randomly generates a value of `p_z` in units of `m_e c`.
+    pub fn gen_momentum<R: Rng>(&self, rng: &mut R) -> f64 {
+        let u = rng.gen::<f64>();
+        let momentum = match self.cumulative.binary_search_by(|p| p.total_cmp(&u)) {
+            Ok(i) => self.momenta[i],
+            Err(i) => {
+                let i = i.max(1).min(self.momenta.len() - 1);
+                let (p0, p1) = (self.cumulative[i - 1], self.cumulative[i]);
+                let fraction = if p1 > p0 { (u - p0) / (p1 - p0) } else { 0.5 };
+                self.momenta[i - 1] + fraction * (self.momenta[i] - self.momenta[i - 1])
+            },
+        };
+        // One atomic unit of momentum is `alpha m_e c`.
+        let momentum = momentum * constants::alpha();
+        if rng.gen::<bool>() { momentum } else { -momentum }
+    }
+}
+
+
+/// The cross-section of pair production in the field of a nucleus.
+///
+/// Above the threshold of `2 m_e c²` (1.022 MeV), a photon may be
+/// converted into an electron–positron pair. This type uses Maximon's
+/// analytic expansions of the unscreened Bethe–Heitler cross-section,
+/// one for energies near the threshold and one for higher energies.
+/// Screening by the atomic electrons is neglected, which overestimates
+/// the cross-section by a few percent at energies of several MeV.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairProductionCrossSection {
+    z: f64,
+}
+
+impl PairProductionCrossSection {
+    /// Creates the cross-section for a nucleus of atomic number `z`.
+    pub fn new(z: f64) -> Self {
+        PairProductionCrossSection { z }
+    }
+
+    /// Returns the atomic number of the nucleus.
+    pub fn z(&self) -> f64 {
+        self.z
+    }
+
+    /// Returns the threshold energy `2 m_e c²`.
+    pub fn threshold() -> Joule<f64> {
+        2.0 * electron_rest_energy()
+    }
+
+    /// Evaluates the total cross-section per atom at the given energy.
+    ///
+    /// Below the threshold, this is zero.
+    pub fn eval(&self, energy: Joule<f64>) -> Meter2<f64> {
+        let k = *(energy / electron_rest_energy()).value();
+        if k <= 2.0 {
+            return 0.0 * M2;
+        }
+        let prefactor = r_e() * r_e() * (constants::alpha() * self.z * self.z);
+        let factor = if k < 4.0 {
+            let eps = (2.0 * k - 4.0) / (2.0 + k + 2.0 * (2.0 * k).sqrt());
+            let series = 1.0 + eps / 2.0 + 23.0 * eps.powi(2) / 40.0 + 11.0 * eps.powi(3) / 60.0 +
+                29.0 * eps.powi(4) / 960.0;
+            2.0 * PI / 3.0 * ((k - 2.0) / k).powi(3) * series
+        } else {
+            let ln = (2.0 * k).ln();
+            let r = 2.0 / k;
+            28.0 / 9.0 * ln - 218.0 / 27.0 +
+                r.powi(2) *
+                    (6.0 * ln - 7.0 / 2.0 + 2.0 / 3.0 * ln.powi(3) - ln.powi(2) -
+                         PI * PI / 3.0 * ln + 2.0 * ZETA_3 + PI * PI / 6.0) -
+                r.powi(4) * (3.0 / 16.0 * ln + 1.0 / 8.0) -
+                r.powi(6) * (29.0 / 2304.0 * ln - 77.0 / 13824.0)
+        };
+        prefactor * factor
+    }
+
+    /// Randomly splits the available energy between the electron and
+    /// the positron.
+    ///
+    /// The fraction `eps` of the photon energy that goes to the
+    /// electron (including its rest energy) is sampled from the
+    /// unscreened Bethe–Heitler distribution without its logarithmic
+    /// factor, `eps² + (1 - eps)² + 2/3 eps (1 - eps)`. The return
+    /// value contains the kinetic energies of electron and positron, in
+    /// this order.
+    ///
+    /// # Panics
+    /// This panics if `energy` is below the threshold.
+    pub fn gen_energy_split<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> (Joule<f64>, Joule<f64>) {
+        assert!(energy > Self::threshold(), "energy below pair-production threshold");
+        let rest_energy = electron_rest_energy();
+        let eps_min = *(rest_energy / energy).value();
+        let density = |eps: f64| eps * eps + (1.0 - eps) * (1.0 - eps) + 2.0 / 3.0 * eps * (1.0 - eps);
+        let envelope = distributions::Range::new(eps_min, 1.0 - eps_min);
+        let eps = Rejection::new(density, envelope, density(eps_min)).ind_sample(rng);
+        let electron = energy * eps - rest_energy;
+        let positron = energy * (1.0 - eps) - rest_energy;
+        (electron, positron)
+    }
+
+    /// Returns the energy of each of the two annihilation photons.
+    ///
+    /// The positron is assumed to annihilate at rest, so both photons
+    /// carry the energy `m_e c²` (511 keV) and are emitted in opposite
+    /// directions.
+    pub fn annihilation_energy() -> Joule<f64> {
+        electron_rest_energy()
+    }
+}
+
+
+/// Iterator that samples `mu` from a cross-section distribution using
+/// the rejection method.
+///
+/// The sampler either borrows the cross-section (see `new()`) or shares
+/// ownership of it (see `owned()`). In the latter case, the sampler is
+/// `Send` and `Sync` if the cross-section is, so it can be moved to
+/// worker threads.
+pub struct RejectionSampler<'a, XS>
+where
+    XS: 'a + CrossSection,
+{
+    dist: SharedRef<'a, XS>,
+    energy: Joule<f64>,
+    mu_dist: distributions::Range<f64>,
+    max_xsection: f64,
+}
+
+impl<'a, XS> RejectionSampler<'a, XS>
+where
+    XS: 'a + CrossSection,
+{
+    /// Creates a new sampler, sampling the cross-section at the given,
+    /// fixed energy.
+    pub fn new(dist: &'a XS, energy: Joule<f64>) -> Self {
+        Self::with_max(dist, energy, dist.max(energy))
+    }
+
+    /// Like `new()`, but uses a known maximum of the cross-section.
+    ///
+    /// This avoids calling `CrossSection::max()`, e.g. if the maximum
+    /// has been cached by a `MaxCache`. `max_xsection` must not be
+    /// smaller than the true maximum at `energy`, or the samples will
+    /// be biased.
+    pub fn with_max(dist: &'a XS, energy: Joule<f64>, max_xsection: Meter2<f64>) -> Self {
+        Self::from_shared_ref(SharedRef::Borrowed(dist), energy, max_xsection)
+    }
+
+    /// Private function that creates a sampler with a known maximum.
+    fn from_shared_ref(dist: SharedRef<'a, XS>, energy: Joule<f64>, max_xsection: Meter2<f64>) -> Self {
+        let max_xsection = *(max_xsection / M2).value();
+        let mu_dist = distributions::Range::new(-1.0, 1.0);
+
+        RejectionSampler {
+            dist,
+            energy,
+            mu_dist,
+            max_xsection,
+        }
+    }
+
+    /// Returns the cross-section that is being sampled.
+    pub fn dist(&self) -> &XS {
+        &self.dist
+    }
+
+    /// Produces a new `mu` value.
+    pub fn gen_mu<R: Rng>(&self, rng: &mut R) -> Unitless<f64> {
+        let target = |mu: f64| *(self.dist.eval(self.energy, Unitless::new(mu)) / M2).value();
+        let mu = Rejection::new(target, self.mu_dist, self.max_xsection).ind_sample(rng);
+        Unitless::new(mu)
+    }
+}
+
+impl<XS> RejectionSampler<'static, XS>
+where
+    XS: 'static + CrossSection,
+{
+    /// Creates a new sampler that shares ownership of the
+    /// cross-section.
+    ///
+    /// Unlike a sampler created with `new()`, this one does not borrow
+    /// anything and may outlive the scope in which it was created.
+    pub fn owned(dist: Arc<XS>, energy: Joule<f64>) -> Self {
+        let max_xsection = dist.max(energy);
+        Self::owned_with_max(dist, energy, max_xsection)
+    }
+
+    /// Like `owned()`, but uses a known maximum of the cross-section.
+    ///
+    /// See `with_max()` for details.
+    pub fn owned_with_max(dist: Arc<XS>, energy: Joule<f64>, max_xsection: Meter2<f64>) -> Self {
+        Self::from_shared_ref(SharedRef::Shared(dist), energy, max_xsection)
+    }
+}
+
+impl<'a, XS> Sample<Unitless<f64>> for RejectionSampler<'a, XS>
+where
+    XS: 'a + CrossSection,
+{
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> Unitless<f64> {
+        self.gen_mu(rng)
+    }
+}
+
+impl<'a, XS> IndependentSample<Unitless<f64>> for RejectionSampler<'a, XS>
+where
+    XS: 'a + CrossSection,
+{
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> Unitless<f64> {
+        self.gen_mu(rng)
+    }
+}
+
+/// Private type of a reference that is either borrowed or shared.
+enum SharedRef<'a, T: 'a> {
+    Borrowed(&'a T),
+    Shared(Arc<T>),
+}
+
+impl<'a, T: 'a> Deref for SharedRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match *self {
+            SharedRef::Borrowed(value) => value,
+            SharedRef::Shared(ref value) => value,
+        }
+    }
+}
+
+
+/// The number of intervals used by `CrossSection::total()`.
+///
+/// This must be even for Simpson's rule.
+const TOTAL_INTERVALS: usize = 200;
+
+/// The number of grid intervals of `IncoherentCrossSection::max()`.
+const MAX_SCAN_INTERVALS: usize = 64;
+
+/// The number of golden-section steps of `IncoherentCrossSection::max()`.
+const MAX_REFINE_STEPS: usize = 20;
+
+/// The factor by which `IncoherentCrossSection::max()` overestimates
+/// the maximum found.
+const MAX_SAFETY_MARGIN: f64 = 1.02;
+
+/// The number of tries of `IncoherentCrossSection::gen_scattered_energy()`.
+const DOPPLER_TRIES: usize = 100;
+
+/// The number of logarithmic grid points of `FormFactorFit::tabulate()`.
+const FIT_GRID_POINTS: usize = 241;
+
+/// The largest `s` in `Å⁻¹` for which form-factor fits are valid.
+const FIT_S_MAX: f64 = 2.0;
+
+/// `h c` in units of keV Å, to convert `x` to `s = x / (h c)`.
+const HC_KEV_ANGSTROM: f64 = 12.398_419_843;
+
+/// The built-in form-factor fits: `Z`, `a`, `b` (in `Å²`), and `c`.
+///
+/// The coefficients are those of the International Tables for
+/// Crystallography, Vol. C, Table 6.1.1.4.
+const FORM_FACTOR_FITS: [(u32, [f64; 4], [f64; 4], f64); 10] = [
+    (1, [0.489918, 0.262003, 0.196767, 0.049879], [20.6593, 7.74039, 49.5519, 2.20159], 0.001305),
+    (6, [2.31, 1.02, 1.5886, 0.865], [20.8439, 10.2075, 0.5687, 51.6512], 0.2156),
+    (7, [12.2126, 3.1322, 2.0125, 1.1663], [0.0057, 9.8933, 28.9975, 0.5826], -11.529),
+    (8, [3.0485, 2.2868, 1.5463, 0.867], [13.2771, 5.7011, 0.3239, 32.9089], 0.2508),
+    (13, [6.4202, 1.9002, 1.5936, 1.9646], [3.0387, 0.7426, 31.5472, 85.0886], 1.1151),
+    (14, [6.2915, 3.0353, 1.9891, 1.541], [2.4386, 32.3337, 0.6785, 81.6937], 1.1407),
+    (26, [11.7695, 7.3573, 3.5222, 2.3045], [4.7611, 0.3072, 15.3535, 76.8805], 1.0369),
+    (29, [13.338, 7.1676, 5.6158, 1.6735], [3.5828, 0.247, 11.3966, 64.8126], 1.191),
+    (74, [29.0818, 15.43, 14.4327, 5.11982], [1.72029, 9.2259, 0.321703, 57.056], 9.8875),
+    (82, [31.0617, 13.0637, 18.442, 5.9696], [0.6902, 2.3576, 8.618, 47.2579], 13.4118),
+];
+
+/// Apéry's constant `ζ(3)`.
+const ZETA_3: f64 = 1.2020569031595942;
+
+
+/// Sampler of `mu` using the rejection method with an adaptive
+/// envelope.
+///
+/// `RejectionSampler` uses a flat envelope at the maximum of the
+/// cross-section. For strongly forward-peaked cross-sections, such as
+/// coherent scattering at high energies, almost all candidates are
+/// rejected. This sampler divides the range of `mu` into segments and
+/// uses the maximum of the cross-section in each segment as a
+/// piecewise-constant envelope. A segment is first chosen with a
+/// probability proportional to the area under the envelope, then `mu`
+/// is sampled within it by the rejection method.
+///
+/// The maximum of a segment is taken as the larger of the values at
+/// its edges. This is exact if the cross-section is monotonic within
+/// each segment, which holds for sufficiently fine segments.
+pub struct EnvelopeSampler<'a, XS>
+where
+    XS: 'a + CrossSection,
+{
+    dist: &'a XS,
+    energy: Joule<f64>,
+    /// The segment maxima in square meters.
+    maxima: Vec<f64>,
+    /// The cumulative envelope area up to the end of each segment.
+    cumulative: Vec<f64>,
+}
+
+impl<'a, XS> EnvelopeSampler<'a, XS>
+where
+    XS: 'a + CrossSection,
+{
+    /// Creates a new sampler with `nsegments` segments of equal width,
+    /// sampling the cross-section at the given, fixed energy.
+    ///
+    /// # Panics
+    /// This panics if `nsegments` is zero.
+    pub fn new(dist: &'a XS, energy: Joule<f64>, nsegments: usize) -> Self {
+        assert!(nsegments > 0, "no segments");
+        let width = 2.0 / nsegments as f64;
+        let eval = |mu: f64| *(dist.eval(energy, Unitless::new(mu)) / M2).value();
+        let mut maxima = Vec::with_capacity(nsegments);
+        let mut cumulative = Vec::with_capacity(nsegments);
+        let mut area = 0.0;
+        let mut left = eval(-1.0);
+        for i in 0..nsegments {
+            let right = eval(-1.0 + width * (i + 1) as f64);
+            let max = left.max(right);
+            area += max * width;
+            maxima.push(max);
+            cumulative.push(area);
+            left = right;
+        }
+        EnvelopeSampler {
+            dist,
+            energy,
+            maxima,
+            cumulative,
+        }
+    }
+
+    /// Returns the area under the envelope.
+    ///
+    /// Dividing the integral of the cross-section over `mu` by this
+    /// gives the acceptance rate of the sampler.
+    pub fn envelope_area(&self) -> Meter2<f64> {
+        self.cumulative.last().cloned().unwrap_or(0.0) * M2
+    }
+
+    /// Produces a new `mu` value.
+    pub fn gen_mu<R: Rng>(&self, rng: &mut R) -> Unitless<f64> {
+        let total = self.cumulative[self.cumulative.len() - 1];
+        let width = 2.0 / self.maxima.len() as f64;
+        loop {
+            let choice = rng.gen_range(0.0, total);
+            // Find the first segment that ends beyond `choice`. This
+            // skips segments with an envelope of zero.
+            let segment = self.cumulative
+                .iter()
+                .position(|&area| area > choice)
+                .unwrap_or(self.maxima.len() - 1);
+            let low = -1.0 + width * segment as f64;
+            let mu = Unitless::new(rng.gen_range(low, low + width));
+            let random_xsection = rng.gen_range(0.0, self.maxima[segment]) * M2;
+            if logging::ENVELOPE.count(random_xsection < self.dist.eval(self.energy, mu)) {
+                return mu;
+            }
+        }
+    }
+}
+
+impl<'a, XS> Sample<Unitless<f64>> for EnvelopeSampler<'a, XS>
+where
+    XS: 'a + CrossSection,
+{
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> Unitless<f64> {
+        self.gen_mu(rng)
+    }
+}
+
+impl<'a, XS> IndependentSample<Unitless<f64>> for EnvelopeSampler<'a, XS>
+where
+    XS: 'a + CrossSection,
+{
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> Unitless<f64> {
+        self.gen_mu(rng)
+    }
+}
+
+
+/// Sampler of `mu` by inversion of a tabulated cumulative distribution.
+///
+/// At construction, the cross-section is evaluated on a grid of
+/// logarithmically spaced energies and equally spaced values of `mu`.
+/// For each energy, the cumulative distribution over `mu` is
+/// integrated with the trapezoidal rule and stored. Sampling then
+/// needs no rejection and no evaluation of the cross-section: The
+/// grid energy is chosen by interpolating randomly between the two
+/// neighbors of the requested energy, and `mu` is found by inverting
+/// the cumulative distribution with linear interpolation.
+///
+/// This trades a one-time setup cost and some memory for very fast
+/// sampling. Unlike the other samplers, it does not borrow the
+/// cross-section and works at any energy within its range.
+#[derive(Debug, Clone)]
+pub struct CdfSampler {
+    low: Joule<f64>,
+    high: Joule<f64>,
+    /// The cumulative distributions, one per grid energy, each
+    /// normalized to one at `mu = 1`.
+    cdfs: Vec<Vec<f64>>,
+}
+
+impl CdfSampler {
+    /// Creates a sampler for energies between `low` and `high`.
+    ///
+    /// The cross-section is tabulated at `nenergies` energies and
+    /// `nmu + 1` values of `mu`.
+    ///
+    /// # Panics
+    /// This panics if `nenergies` is less than two, `nmu` is zero,
+    /// `low` is not positive or not below `high`, or the cross-section
+    /// vanishes for all `mu` at any grid energy.
+    pub fn new<XS>(dist: &XS, low: Joule<f64>, high: Joule<f64>, nenergies: usize, nmu: usize) -> Self
+    where
+        XS: CrossSection,
+    {
+        assert!(nenergies >= 2 && nmu > 0, "grid too small");
+        assert!(low > 0.0 * J && low < high, "invalid energy range");
+        let ratio = *(high / low).value();
+        let cdfs = (0..nenergies)
+            .map(|i| {
+                let energy = low * ratio.powf(i as f64 / (nenergies - 1) as f64);
+                Self::tabulate(dist, energy, nmu)
+            })
+            .collect();
+        CdfSampler { low, high, cdfs }
+    }
+
+    /// Returns the range of energies covered by the table.
+    pub fn energy_range(&self) -> (Joule<f64>, Joule<f64>) {
+        (self.low, self.high)
+    }
+
+    /// Produces a new `mu` value at the given energy.
+    ///
+    /// Energies outside of the table's range are clamped to it.
+    pub fn gen_mu<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Unitless<f64> {
+        let cdf = &self.cdfs[self.choose_energy(energy, rng)];
+        let nmu = cdf.len() - 1;
+        let u = rng.gen::<f64>();
+        let i = match cdf.binary_search_by(|p| p.total_cmp(&u)) {
+            Ok(i) => return Unitless::new(-1.0 + 2.0 * i as f64 / nmu as f64),
+            Err(i) => i.max(1).min(nmu),
+        };
+        let (p0, p1) = (cdf[i - 1], cdf[i]);
+        let fraction = if p1 > p0 { (u - p0) / (p1 - p0) } else { 0.5 };
+        Unitless::new(-1.0 + 2.0 * (i as f64 - 1.0 + fraction) / nmu as f64)
+    }
+
+    /// Private function that tabulates the normalized cumulative
+    /// distribution at one energy.
+    fn tabulate<XS: CrossSection>(dist: &XS, energy: Joule<f64>, nmu: usize) -> Vec<f64> {
+        let eval = |i: usize| {
+            let mu = Unitless::new(-1.0 + 2.0 * i as f64 / nmu as f64);
+            *(dist.eval(energy, mu) / M2).value()
+        };
+        let mut cdf = Vec::with_capacity(nmu + 1);
+        let mut sum = 0.0;
+        let mut left = eval(0);
+        cdf.push(0.0);
+        for i in 1..nmu + 1 {
+            let right = eval(i);
+            sum += (left + right) / 2.0;
+            cdf.push(sum);
+            left = right;
+        }
+        assert!(sum > 0.0, "cross-section vanishes");
+        for p in &mut cdf {
+            *p /= sum;
+        }
+        cdf
+    }
+
+    /// Private method that randomly chooses one of the two grid
+    /// energies around `energy`.
+    ///
+    /// The closer grid energy is chosen with a higher probability, so
+    /// that the sampled distribution interpolates linearly in
+    /// log-energy.
+    fn choose_energy<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> usize {
+        let last = self.cdfs.len() - 1;
+        let position = (energy / self.low).value().ln() / (self.high / self.low).value().ln();
+        let position = (position * last as f64).max(0.0).min(last as f64);
+        let index = position.floor() as usize;
+        if index < last && rng.gen::<f64>() < position - index as f64 {
+            index + 1
+        } else {
+            index
+        }
+    }
+}
+
+
+/// Sampler of `mu` from a coherent cross-section using a cumulative
+/// table of the squared form factor.
+///
+/// The coherent cross-section is proportional to
+/// `(1 + mu²)/2 F²(x)`, where `x² = E² (1 - mu)/2`. Substituting `x²`
+/// for `mu`, the squared form factor becomes a distribution in `x²`
+/// that does not depend on the energy; only its upper limit `E²` does.
+/// This sampler tabulates the cumulative integral of `F²` over `x²`
+/// once. To sample, `x²` is found by inverting the table up to `E²`,
+/// and the result is accepted with probability `(1 + mu²)/2`, which is
+/// at least one half.
+///
+/// This is the technique used by EGS and PENELOPE. Unlike rejection
+/// against the whole cross-section, its efficiency does not degrade at
+/// high energies, where the form factor is sharply peaked.
+#[derive(Debug, Clone)]
+pub struct RayleighSampler {
+    max_energy: Joule<f64>,
+    /// The values of `x²` in keV².
+    x_squared: Vec<f64>,
+    /// The cumulative integral of `F²` up to each value of `x²`.
+    cumulative: Vec<f64>,
+}
+
+impl RayleighSampler {
+    /// Creates a sampler for energies up to `max_energy`.
+    ///
+    /// The table has `n_points` logarithmically spaced values of `x`
+    /// from `10⁻⁶ max_energy` to `max_energy`, plus `x = 0`.
+    ///
+    /// # Panics
+    /// This panics if `n_points` is less than two, `max_energy` is not
+    /// positive, or the form factor does not cover the required range
+    /// of `x`.
+    pub fn new(dist: &CoherentCrossSection, max_energy: Joule<f64>, n_points: usize) -> Self {
+        assert!(n_points >= 2, "grid too small");
+        assert!(max_energy > 0.0 * J, "energy must be positive");
+        let x_max = *(max_energy / (KILO * EV)).value();
+        let mut x_squared = Vec::with_capacity(n_points + 1);
+        let mut cumulative = Vec::with_capacity(n_points + 1);
+        let f_squared = |x: f64| {
+            let f = *dist.form_factor.call(x * KILO * EV).value();
+            f * f
+        };
+        let mut left = (0.0, f_squared(0.0));
+        x_squared.push(0.0);
+        cumulative.push(0.0);
+        let mut sum = 0.0;
+        for i in 0..n_points {
+            let x = x_max * 1e-6f64.powf(1.0 - i as f64 / (n_points - 1) as f64);
+            let right = (x * x, f_squared(x));
+            sum += (left.1 + right.1) / 2.0 * (right.0 - left.0);
+            x_squared.push(right.0);
+            cumulative.push(sum);
+            left = right;
+        }
+        RayleighSampler {
+            max_energy,
+            x_squared,
+            cumulative,
+        }
+    }
+
+    /// Returns the largest energy covered by the table.
+    pub fn max_energy(&self) -> Joule<f64> {
+        self.max_energy
+    }
+
+    /// Produces a new `mu` value at the given energy.
+    ///
+    /// # Panics
+    /// This panics if `energy` is above `max_energy()` or NaN.
+    pub fn gen_mu<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Unitless<f64> {
+        assert!(energy <= self.max_energy, "energy above table range");
+        let e_squared = (energy / (KILO * EV)).value().powi(2);
+        let area = self.cumulative_at(e_squared);
+        loop {
+            let x_squared = self.invert(rng.gen::<f64>() * area).min(e_squared);
+            let mu = 1.0 - 2.0 * x_squared / e_squared;
+            if logging::RAYLEIGH.count(rng.gen::<f64>() < (1.0 + mu * mu) / 2.0) {
+                return Unitless::new(mu);
+            }
+        }
+    }
+
+    /// Private method that interpolates the cumulative integral at the
+    /// given `x²`.
+    fn cumulative_at(&self, x_squared: f64) -> f64 {
+        let i = match self.x_squared.binary_search_by(|u| u.total_cmp(&x_squared)) {
+            Ok(i) => return self.cumulative[i],
+            Err(i) => i.max(1).min(self.x_squared.len() - 1),
+        };
+        let (u0, u1) = (self.x_squared[i - 1], self.x_squared[i]);
+        let (a0, a1) = (self.cumulative[i - 1], self.cumulative[i]);
+        a0 + (a1 - a0) * (x_squared - u0) / (u1 - u0)
+    }
+
+    /// Private method that finds the `x²` at which the cumulative
+    /// integral reaches `area`.
+    fn invert(&self, area: f64) -> f64 {
+        let i = match self.cumulative.binary_search_by(|a| a.total_cmp(&area)) {
+            Ok(i) => return self.x_squared[i],
+            Err(i) => i.max(1).min(self.cumulative.len() - 1),
+        };
+        let (u0, u1) = (self.x_squared[i - 1], self.x_squared[i]);
+        let (a0, a1) = (self.cumulative[i - 1], self.cumulative[i]);
+        let fraction = if a1 > a0 { (area - a0) / (a1 - a0) } else { 0.5 };
+        u0 + fraction * (u1 - u0)
+    }
+}
+
+
+/// A bank of samplers of `mu` on a grid of energies.
+///
+/// Samplers like `RejectionSampler` or `EnvelopeSampler` work at a
+/// fixed energy and may be expensive to set up. This bank creates one
+/// sampler per grid energy in advance. To sample at an arbitrary
+/// energy, one of the two neighboring grid energies is chosen randomly,
+/// the closer one with a higher probability, so that the sampled
+/// distribution interpolates linearly in log-energy. Energies outside
+/// of the grid are clamped to it.
+///
+/// The grid must be fine enough that the angular distribution changes
+/// little between neighboring energies.
+///
+/// # Example
+/// ```ignore
+/// let xsection = Arc::new(CoherentCrossSection::new("data/AFF.dat")?);
+/// let bank = SamplerBank::log_spaced(1.0 * KILO * EV, 1.0 * MEGA * EV, 100, |energy| {
+///     RejectionSampler::owned(xsection.clone(), energy)
+/// });
+/// let mu = bank.gen_mu(662.0 * KILO * EV, &mut rng);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SamplerBank<S> {
+    energies: Vec<Joule<f64>>,
+    samplers: Vec<S>,
+}
+
+impl<S> SamplerBank<S>
+where
+    S: IndependentSample<Unitless<f64>>,
+{
+    /// Creates a bank on the given grid of energies.
+    ///
+    /// `make_sampler` is called once for each grid energy.
+    ///
+    /// # Panics
+    /// This panics if `energies` is empty, contains energies that are
+    /// not positive, or is not strictly increasing.
+    pub fn new<F>(energies: Vec<Joule<f64>>, make_sampler: F) -> Self
+    where
+        F: FnMut(Joule<f64>) -> S,
+    {
+        assert!(!energies.is_empty(), "empty energy grid");
+        assert!(energies[0] > 0.0 * J, "energies must be positive");
+        assert!(
+            energies.windows(2).all(|pair| pair[0] < pair[1]),
+            "energies not strictly increasing"
+        );
+        let samplers = energies.iter().cloned().map(make_sampler).collect();
+        SamplerBank { energies, samplers }
+    }
+
+    /// Creates a bank on `n` logarithmically spaced energies from
+    /// `low` to `high`.
+    ///
+    /// # Panics
+    /// This panics if `n` is less than two, `low` is not positive, or
+    /// `low` is not below `high`.
+    pub fn log_spaced<F>(low: Joule<f64>, high: Joule<f64>, n: usize, make_sampler: F) -> Self
+    where
+        F: FnMut(Joule<f64>) -> S,
+    {
+        assert!(n >= 2, "grid too small");
+        assert!(low > 0.0 * J && low < high, "invalid energy range");
+        let ratio = *(high / low).value();
+        let energies = (0..n)
+            .map(|i| low * ratio.powf(i as f64 / (n - 1) as f64))
+            .collect();
+        Self::new(energies, make_sampler)
+    }
+
+    /// Returns the grid energies.
+    pub fn energies(&self) -> &[Joule<f64>] {
+        &self.energies
+    }
+
+    /// Returns the samplers, one per grid energy.
+    pub fn samplers(&self) -> &[S] {
+        &self.samplers
+    }
+
+    /// Produces a new `mu` value at the given energy.
+    pub fn gen_mu<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Unitless<f64> {
+        let index = self.choose_energy(energy, rng);
+        self.samplers[index].ind_sample(rng)
+    }
+
+    /// Private method that randomly chooses one of the two grid
+    /// energies around `energy`.
+    fn choose_energy<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> usize {
+        let last = self.energies.len() - 1;
+        let upper = match self.energies
+            .binary_search_by(|e| e.value_unsafe.total_cmp(&energy.value_unsafe))
+        {
+            Ok(i) => return i,
+            Err(0) => return 0,
+            Err(i) if i > last => return last,
+            Err(i) => i,
+        };
+        let (low, high) = (self.energies[upper - 1], self.energies[upper]);
+        let fraction = (energy / low).value().ln() / (high / low).value().ln();
+        if rng.gen::<f64>() < fraction {
+            upper
+        } else {
+            upper - 1
+        }
+    }
+}
+
+
+/// A cache of cross-section maxima for `RejectionSampler`.
+///
+/// Creating a `RejectionSampler` calls `CrossSection::max()`, which
+/// may have to walk through a table. If samplers are created anew for
+/// each scattering event, this is done over and over at similar
+/// energies. This cache divides an energy range into logarithmically
+/// spaced bins and remembers the maximum of each bin the first time it
+/// is needed.
+///
+/// The maximum of a bin is the larger of the maxima at its two edges.
+/// This is exact if the maximum changes monotonically within each bin,
+/// which holds for sufficiently fine bins. Energies outside of the
+/// range are not cached.
+///
+/// The cache does not borrow the cross-section, so that both can be
+/// stored in the same struct. Instead, the cross-section is passed to
+/// each method call; it must always be the same one. The cache uses
+/// interior mutability, so that it can be used from within methods of
+/// `Experiment`, which only get `&self`.
+#[derive(Debug, Clone)]
+pub struct MaxCache {
+    low: Joule<f64>,
+    high: Joule<f64>,
+    maxima: Vec<Cell<Option<f64>>>,
+}
+
+impl MaxCache {
+    /// Creates a new, empty cache with `nbins` bins between `low` and
+    /// `high`.
+    ///
+    /// # Panics
+    /// This panics if `nbins` is zero, `low` is not positive, or `low`
+    /// is not below `high`.
+    pub fn new(nbins: usize, low: Joule<f64>, high: Joule<f64>) -> Self {
+        assert!(nbins > 0, "no bins");
+        assert!(low > 0.0 * J && low < high, "invalid energy range");
+        MaxCache {
+            low,
+            high,
+            maxima: vec![Cell::new(None); nbins],
+        }
+    }
+
+    /// Returns an upper bound of the cross-section `dist` at `energy`.
+    pub fn max<XS: CrossSection>(&self, dist: &XS, energy: Joule<f64>) -> Meter2<f64> {
+        let bin = match self.find_bin(energy) {
+            Some(bin) => bin,
+            None => return dist.max(energy),
+        };
+        if let Some(max) = self.maxima[bin].get() {
+            return max * M2;
+        }
+        let (low, high) = self.bin_edges(bin);
+        let max_low = *(dist.max(low) / M2).value();
+        let max_high = *(dist.max(high) / M2).value();
+        let max = max_low.max(max_high);
+        self.maxima[bin].set(Some(max));
+        max * M2
+    }
+
+    /// Returns a sampler of `dist` at the given energy using the cached
+    /// maximum.
+    pub fn sampler<'a, XS: CrossSection>(&self, dist: &'a XS, energy: Joule<f64>) -> RejectionSampler<'a, XS> {
+        RejectionSampler::with_max(dist, energy, self.max(dist, energy))
+    }
+
+    /// Private method that returns the bin in which `energy` lies.
+    fn find_bin(&self, energy: Joule<f64>) -> Option<usize> {
+        if energy < self.low || energy >= self.high {
+            return None;
+        }
+        let position = (energy / self.low).value().ln() / (self.high / self.low).value().ln();
+        let nbins = self.maxima.len();
+        Some(((position * nbins as f64) as usize).min(nbins - 1))
+    }
+
+    /// Private method that returns the energies at both edges of a bin.
+    fn bin_edges(&self, bin: usize) -> (Joule<f64>, Joule<f64>) {
+        let ratio = *(self.high / self.low).value();
+        let nbins = self.maxima.len() as f64;
+        let low = self.low * ratio.powf(bin as f64 / nbins);
+        let high = self.low * ratio.powf((bin + 1) as f64 / nbins);
+        (low, high)
+    }
+}
+
+
+/// Sampler of `mu` from an incoherent cross-section using Kahn's
+/// method.
+///
+/// The free-electron Klein–Nishina part of the cross-section is
+/// sampled directly with Kahn's composition–rejection method, which
+/// does not need to evaluate the cross-section at all. The binding
+/// correction, i.e. the incoherent scattering function `S(x, Z)`, is
+/// then taken into account by a second rejection step, accepting each
+/// `mu` with probability `S(x)/S(E)`. Since `S` increases with `x`,
+/// `S(E)` is its maximum at the given energy.
+///
+/// Because the scattering function is evaluated only once per
+/// candidate, this is several times faster than `RejectionSampler`
+/// for energies like that of Cs-137.
+pub struct KahnSampler<'a> {
+    dist: &'a IncoherentCrossSection,
+    energy: Joule<f64>,
+    kappa: f64,
+    max_scatter: f64,
+}
+
+impl<'a> KahnSampler<'a> {
+    /// Creates a new sampler, sampling the cross-section at the given,
+    /// fixed energy.
+    pub fn new(dist: &'a IncoherentCrossSection, energy: Joule<f64>) -> Self {
+        let kappa = *(energy / electron_rest_energy()).value();
+        // `S(x)` increases with `x`, which is largest at `mu = -1`.
+        let max_scatter = *dist.scattering_function(energy, Unitless::new(-1.0)).value();
+        KahnSampler {
+            dist,
+            energy,
+            kappa,
+            max_scatter,
+        }
+    }
+
+    /// Produces a new `mu` value.
+    pub fn gen_mu<R: Rng>(&self, rng: &mut R) -> Unitless<f64> {
+        loop {
+            let mu = Unitless::new(gen_klein_nishina(self.kappa, rng));
+            let scatter = *self.dist.scattering_function(self.energy, mu).value();
+            if logging::KAHN.count(rng.gen::<f64>() * self.max_scatter < scatter) {
+                return mu;
+            }
+        }
+    }
+}
+
+impl<'a> Sample<Unitless<f64>> for KahnSampler<'a> {
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> Unitless<f64> {
+        self.gen_mu(rng)
+    }
+}
+
+impl<'a> IndependentSample<Unitless<f64>> for KahnSampler<'a> {
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> Unitless<f64> {
+        self.gen_mu(rng)
+    }
+}
+
+
+/// Private function that samples `mu` from the pure Klein–Nishina
+/// cross-section.
+///
+/// `kappa` is the photon energy in units of `m_e c²`. This uses Kahn's
+/// composition–rejection method.
+fn gen_klein_nishina<R: Rng>(kappa: f64, rng: &mut R) -> f64 {
+    let k = kappa;
+    loop {
+        let (r1, r2, r3) = rng.gen::<(f64, f64, f64)>();
+        // `eta` is the ratio of incident and scattered energy.
+        if r1 <= (1.0 + 2.0 * k) / (9.0 + 2.0 * k) {
+            let eta = 1.0 + 2.0 * k * r2;
+            if logging::KLEIN_NISHINA.count(r3 <= 4.0 * (1.0 / eta - 1.0 / (eta * eta))) {
+                return 1.0 - (eta - 1.0) / k;
+            }
+        } else {
+            let eta = (1.0 + 2.0 * k) / (1.0 + 2.0 * k * r2);
+            let mu = 1.0 - (eta - 1.0) / k;
+            if logging::KLEIN_NISHINA.count(r3 <= 0.5 * (mu * mu + 1.0 / eta)) {
+                return mu;
+            }
+        }
+    }
+}
+
+
+/// Returns the classical electron radius.
+fn r_e() -> Meter<f64> {
+    constants::electron_radius()
+}
+
+
+/// Calculates the parameter `x = E * sin(theta/2)`.
+fn get_x(energy: Joule<f64>, mu: Unitless<f64>) -> Joule<f64> {
+    let angle = mu.acos();
+    energy * (angle / 2.0).sin()
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+use dimensioned::f64prefixes::*;
+
+use function::Function;
+use super::CrossSection;
+
+
+/// The comparison of a cross-section and a reference at one energy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValidationPoint {
+    /// The energy at which both have been evaluated.
+    pub energy: Joule<f64>,
+    /// The total cross-section calculated by `CrossSection::total()`.
+    pub computed: Meter2<f64>,
+    /// The reference cross-section.
+    pub reference: Meter2<f64>,
+}
+
+impl ValidationPoint {
+    /// Returns the relative deviation `computed/reference - 1`.
+    ///
+    /// If the reference is zero, this is zero if the computed value is
+    /// zero as well, and infinite otherwise.
+    pub fn deviation(&self) -> f64 {
+        let reference = *(self.reference / M2).value();
+        let computed = *(self.computed / M2).value();
+        if reference != 0.0 {
+            computed / reference - 1.0
+        } else if computed == 0.0 {
+            0.0
+        } else {
+            f64::INFINITY
+        }
+    }
+}
+
+
+/// The result of comparing a cross-section against reference data.
+///
+/// The `Display` implementation prints one line per energy in keV,
+/// with both cross-sections in barn and the relative deviation.
+#[derive(Debug, Clone)]
+pub struct Validation {
+    points: Vec<ValidationPoint>,
+}
+
+impl Validation {
+    /// Returns the comparisons at all energies.
+    pub fn points(&self) -> &[ValidationPoint] {
+        &self.points
+    }
+
+    /// Returns the comparison with the largest absolute deviation.
+    ///
+    /// If no energies have been compared, `None` is returned.
+    pub fn worst(&self) -> Option<&ValidationPoint> {
+        self.points.iter().fold(None, |worst: Option<&ValidationPoint>, point| match worst {
+            Some(worst) if worst.deviation().abs() >= point.deviation().abs() => Some(worst),
+            _ => Some(point),
+        })
+    }
+
+    /// Returns the largest absolute relative deviation.
+    ///
+    /// If no energies have been compared, this is zero.
+    pub fn max_deviation(&self) -> f64 {
+        self.worst().map_or(0.0, |point| point.deviation().abs())
+    }
+
+    /// Returns `true` if the absolute relative deviation is within
+    /// `tolerance` at all energies.
+    pub fn passes(&self, tolerance: f64) -> bool {
+        self.max_deviation() <= tolerance
+    }
+}
+
+impl fmt::Display for Validation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "E [keV]\tcomputed [b]\treference [b]\tdeviation")?;
+        for point in &self.points {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{:+.4}",
+                (point.energy / (KILO * EV)).value(),
+                (point.computed / BARN).value(),
+                (point.reference / BARN).value(),
+                point.deviation(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+
+/// Compares a cross-section against a reference at the given energies.
+///
+/// At each energy, the cross-section is integrated over all angles with
+/// `CrossSection::total()` and compared to the value of `reference`.
+/// The reference must be given per atom, e.g. as returned by
+/// `XcomTable::coherent()`. Mass attenuation coefficients must first
+/// be divided by the number of atoms per unit mass.
+///
+/// # Panics
+/// This panics if any energy lies outside of the domain of
+/// `reference`.
+pub fn compare_at<XS>(
+    xsection: &XS,
+    reference: &Function<Joule<f64>, Meter2<f64>>,
+    energies: &[Joule<f64>],
+) -> Validation
+where
+    XS: CrossSection,
+{
+    let points = energies
+        .iter()
+        .map(|&energy| {
+            ValidationPoint {
+                energy,
+                computed: xsection.total(energy),
+                reference: reference.call(energy),
+            }
+        })
+        .collect();
+    Validation { points }
+}
+
+
+/// Compares a cross-section against a reference at all energies of
+/// the reference table.
+///
+/// See `compare_at()` for details.
+pub fn compare<XS>(xsection: &XS, reference: &Function<Joule<f64>, Meter2<f64>>) -> Validation
+where
+    XS: CrossSection,
+{
+    compare_at(xsection, reference, reference.xdata())
+}
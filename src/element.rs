@@ -0,0 +1,188 @@
+use std::fmt;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use error;
+
+use dimensioned::si::*;
+
+use crosssection::{CoherentCrossSection, IncoherentCrossSection};
+use mc::material::ElementCrossSections;
+
+
+/// Symbols and standard atomic weights (in u) of the elements, sorted
+/// by atomic number.
+///
+/// For elements without stable isotopes, the mass number of the
+/// longest-lived isotope is given.
+const ELEMENTS: [(&str, f64); 100] = [
+    ("H", 1.008), ("He", 4.0026), ("Li", 6.94), ("Be", 9.0122), ("B", 10.81),
+    ("C", 12.011), ("N", 14.007), ("O", 15.999), ("F", 18.998), ("Ne", 20.180),
+    ("Na", 22.990), ("Mg", 24.305), ("Al", 26.982), ("Si", 28.085), ("P", 30.974),
+    ("S", 32.06), ("Cl", 35.45), ("Ar", 39.948), ("K", 39.098), ("Ca", 40.078),
+    ("Sc", 44.956), ("Ti", 47.867), ("V", 50.942), ("Cr", 51.996), ("Mn", 54.938),
+    ("Fe", 55.845), ("Co", 58.933), ("Ni", 58.693), ("Cu", 63.546), ("Zn", 65.38),
+    ("Ga", 69.723), ("Ge", 72.630), ("As", 74.922), ("Se", 78.971), ("Br", 79.904),
+    ("Kr", 83.798), ("Rb", 85.468), ("Sr", 87.62), ("Y", 88.906), ("Zr", 91.224),
+    ("Nb", 92.906), ("Mo", 95.95), ("Tc", 98.0), ("Ru", 101.07), ("Rh", 102.91),
+    ("Pd", 106.42), ("Ag", 107.87), ("Cd", 112.41), ("In", 114.82), ("Sn", 118.71),
+    ("Sb", 121.76), ("Te", 127.60), ("I", 126.90), ("Xe", 131.29), ("Cs", 132.91),
+    ("Ba", 137.33), ("La", 138.91), ("Ce", 140.12), ("Pr", 140.91), ("Nd", 144.24),
+    ("Pm", 145.0), ("Sm", 150.36), ("Eu", 151.96), ("Gd", 157.25), ("Tb", 158.93),
+    ("Dy", 162.50), ("Ho", 164.93), ("Er", 167.26), ("Tm", 168.93), ("Yb", 173.05),
+    ("Lu", 174.97), ("Hf", 178.49), ("Ta", 180.95), ("W", 183.84), ("Re", 186.21),
+    ("Os", 190.23), ("Ir", 192.22), ("Pt", 195.08), ("Au", 196.97), ("Hg", 200.59),
+    ("Tl", 204.38), ("Pb", 207.2), ("Bi", 208.98), ("Po", 209.0), ("At", 210.0),
+    ("Rn", 222.0), ("Fr", 223.0), ("Ra", 226.0), ("Ac", 227.0), ("Th", 232.04),
+    ("Pa", 231.04), ("U", 238.03), ("Np", 237.0), ("Pu", 244.0), ("Am", 243.0),
+    ("Cm", 247.0), ("Bk", 247.0), ("Cf", 251.0), ("Es", 252.0), ("Fm", 257.0),
+];
+
+
+/// Returns the atomic number of the element with the given symbol.
+///
+/// The symbol is case-sensitive, e.g. `"Pb"`. If no such element is
+/// known, `None` is returned.
+pub fn atomic_number(symbol: &str) -> Option<u32> {
+    ELEMENTS
+        .iter()
+        .position(|&(s, _)| s == symbol)
+        .map(|i| i as u32 + 1)
+}
+
+
+/// Returns the symbol of the element with the given atomic number.
+///
+/// If no such element is known, `None` is returned.
+pub fn symbol(z: u32) -> Option<&'static str> {
+    element(z).map(|(symbol, _)| symbol)
+}
+
+
+/// Returns the standard atomic weight of the element with the given
+/// atomic number.
+///
+/// If no such element is known, `None` is returned.
+pub fn atomic_mass(z: u32) -> Option<Kilogram<f64>> {
+    element(z).map(|(_, mass)| mass * U)
+}
+
+
+/// Private function that looks up an element by atomic number.
+fn element(z: u32) -> Option<(&'static str, f64)> {
+    if z == 0 {
+        return None;
+    }
+    ELEMENTS.get(z as usize - 1).cloned()
+}
+
+
+/// All interaction data of a single chemical element.
+///
+/// The data is read from a data directory with one subdirectory per
+/// element, named after the element's symbol. Each subdirectory must
+/// contain three files:
+/// - `AFF.dat`: the atomic form factor,
+/// - `ISF.dat`: the incoherent scattering function,
+/// - `XS.dat`: the coherent, incoherent and photo-effect
+///   cross-sections, as described in `ElementCrossSections::from_file()`.
+///
+/// For example, the form factor of lead is read from
+/// `<data_dir>/Pb/AFF.dat`.
+#[derive(Debug)]
+pub struct ElementData {
+    z: u32,
+    coherent: CoherentCrossSection,
+    incoherent: IncoherentCrossSection,
+    cross_sections: ElementCrossSections,
+}
+
+impl ElementData {
+    /// Loads the data of the element with the given symbol.
+    ///
+    /// # Errors
+    /// This fails if the symbol is unknown or any file cannot be read.
+    pub fn load<P>(symbol: &str, data_dir: P) -> Result<Self, ElementError>
+    where
+        P: AsRef<Path>,
+    {
+        let z = atomic_number(symbol).ok_or_else(|| ElementError::UnknownElement(symbol.to_owned()))?;
+        Self::load_z(z, data_dir)
+    }
+
+    /// Loads the data of the element with the given atomic number.
+    ///
+    /// # Errors
+    /// This fails if the atomic number is unknown or any file cannot be
+    /// read.
+    pub fn load_z<P>(z: u32, data_dir: P) -> Result<Self, ElementError>
+    where
+        P: AsRef<Path>,
+    {
+        let (symbol, mass) = element(z).ok_or_else(|| ElementError::UnknownElement(z.to_string()))?;
+        let dir: PathBuf = data_dir.as_ref().join(symbol);
+        Ok(ElementData {
+            z,
+            coherent: CoherentCrossSection::new(dir.join("AFF.dat"))?,
+            incoherent: IncoherentCrossSection::new(dir.join("ISF.dat"))?,
+            cross_sections: ElementCrossSections::from_file(dir.join("XS.dat"), mass * U)?,
+        })
+    }
+
+    /// Returns the atomic number of the element.
+    pub fn z(&self) -> u32 {
+        self.z
+    }
+
+    /// Returns the symbol of the element.
+    pub fn symbol(&self) -> &'static str {
+        ELEMENTS[self.z as usize - 1].0
+    }
+
+    /// Returns the standard atomic weight of the element.
+    pub fn atomic_mass(&self) -> Kilogram<f64> {
+        self.cross_sections.atomic_mass()
+    }
+
+    /// Returns the coherent angular cross-section.
+    pub fn coherent(&self) -> &CoherentCrossSection {
+        &self.coherent
+    }
+
+    /// Returns the incoherent angular cross-section.
+    pub fn incoherent(&self) -> &IncoherentCrossSection {
+        &self.incoherent
+    }
+
+    /// Returns the total cross-sections of all interactions.
+    pub fn cross_sections(&self) -> &ElementCrossSections {
+        &self.cross_sections
+    }
+}
+
+
+/// The error type returned by `ElementData::load()`.
+#[derive(Debug)]
+pub enum ElementError {
+    /// No element with the given symbol or atomic number is known.
+    UnknownElement(String),
+    /// A data file could not be read.
+    Data(error::Error),
+}
+
+impl fmt::Display for ElementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ElementError::UnknownElement(ref name) => write!(f, "unknown element: {}", name),
+            ElementError::Data(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for ElementError {}
+
+impl From<error::Error> for ElementError {
+    fn from(err: error::Error) -> Self {
+        ElementError::Data(err)
+    }
+}
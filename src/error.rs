@@ -0,0 +1,102 @@
+use std::io;
+use std::fmt;
+use std::error;
+
+use csv;
+
+use formats::FormatError;
+use element::ElementError;
+use mc::config::ConfigError;
+use mc::experiment::TransportError;
+
+
+/// The common error type of this crate.
+///
+/// Functions that can fail because of their input, e.g. a malformed
+/// data file or a value outside of a function's domain, return this
+/// type instead of panicking. The module-specific error types can be
+/// converted into it, so that `?` works across module boundaries.
+///
+/// Constructors whose arguments are usually fixed by the program rather
+/// than read from data, e.g. grid sizes and energy ranges of samplers,
+/// still panic on invalid arguments. They document this in a
+/// `# Panics` section.
+#[derive(Debug)]
+pub enum Error {
+    /// A file could not be read.
+    Io(io::Error),
+    /// A CSV data file could not be read or parsed.
+    Csv(csv::Error),
+    /// A value lies outside of the range in which it is valid, e.g. an
+    /// energy outside of a tabulated cross-section or an empty data
+    /// file.
+    Domain(String),
+    /// The transport of a particle failed.
+    Transport(TransportError),
+}
+
+impl Error {
+    /// Creates a `Domain` error with the given message.
+    pub fn domain<S: Into<String>>(message: S) -> Self {
+        Error::Domain(message.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(f, "could not read file: {}", err),
+            Error::Csv(ref err) => write!(f, "could not read data file: {}", err),
+            Error::Domain(ref message) => write!(f, "{}", message),
+            Error::Transport(ref err) => write!(f, "transport failed: {}", err),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(err: csv::Error) -> Self {
+        Error::Csv(err)
+    }
+}
+
+impl From<TransportError> for Error {
+    fn from(err: TransportError) -> Self {
+        Error::Transport(err)
+    }
+}
+
+impl From<FormatError> for Error {
+    fn from(err: FormatError) -> Self {
+        match err {
+            FormatError::Io(err) => Error::Io(err),
+            err @ FormatError::Parse { .. } => Error::Domain(err.to_string()),
+        }
+    }
+}
+
+impl From<ElementError> for Error {
+    fn from(err: ElementError) -> Self {
+        match err {
+            ElementError::Data(err) => err,
+            err @ ElementError::UnknownElement(_) => Error::Domain(err.to_string()),
+        }
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(err: ConfigError) -> Self {
+        match err {
+            ConfigError::Io(err) => Error::Io(err),
+            ConfigError::Data(err) => err,
+            err => Error::Domain(err.to_string()),
+        }
+    }
+}
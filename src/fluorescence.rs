@@ -0,0 +1,201 @@
+use std::fs::File;
+use std::path::Path;
+use std::io::{BufRead, BufReader};
+
+use rand::Rng;
+
+use dimensioned::si::*;
+use dimensioned::f64prefixes::*;
+
+use formats::FormatError;
+
+
+/// A row of `K_SHELL_DATA`.
+type KShellRow = (u32, f64, f64, f64, [(f64, f64); 4]);
+
+
+/// Built-in K-shell data: `Z`, K-edge energy in keV, fluorescence
+/// yield, K-shell fraction of the photo-effect, and the energies (in
+/// keV) and relative intensities of the Kα1, Kα2, Kβ1 and Kβ2 lines.
+///
+/// Edge and line energies are from the X-ray data booklet, yields from
+/// Krause (1979). The K-shell fractions are `1 - 1/r`, where `r` is
+/// the K-edge jump ratio.
+const K_SHELL_DATA: [KShellRow; 10] = [
+    (26, 7.112, 0.347, 0.88, [(6.404, 1.0), (6.391, 0.50), (7.058, 0.17), (7.108, 0.0)]),
+    (29, 8.979, 0.454, 0.87, [(8.048, 1.0), (8.028, 0.51), (8.905, 0.17), (8.977, 0.0)]),
+    (42, 20.000, 0.765, 0.85, [(17.479, 1.0), (17.374, 0.52), (19.608, 0.24), (19.965, 0.04)]),
+    (47, 25.514, 0.831, 0.84, [(22.163, 1.0), (21.990, 0.53), (24.942, 0.26), (25.456, 0.05)]),
+    (50, 29.200, 0.859, 0.84, [(25.271, 1.0), (25.044, 0.54), (28.486, 0.27), (29.109, 0.06)]),
+    (53, 33.169, 0.884, 0.83, [(28.612, 1.0), (28.317, 0.54), (32.295, 0.28), (33.042, 0.07)]),
+    (55, 35.985, 0.897, 0.83, [(30.973, 1.0), (30.625, 0.54), (34.987, 0.28), (35.822, 0.07)]),
+    (74, 69.525, 0.950, 0.80, [(59.318, 1.0), (57.982, 0.58), (67.244, 0.22), (69.067, 0.08)]),
+    (82, 88.004, 0.963, 0.79, [(74.969, 1.0), (72.805, 0.60), (84.936, 0.23), (87.300, 0.08)]),
+    (83, 90.526, 0.965, 0.79, [(77.108, 1.0), (74.815, 0.60), (87.343, 0.23), (89.784, 0.08)]),
+];
+
+
+/// K-shell fluorescence data of a single chemical element.
+///
+/// If a photon above the K-edge is absorbed by the photo-effect, it
+/// ionizes the K-shell with a probability given by the K-shell
+/// fraction. The vacancy is then filled either by emission of a
+/// characteristic X-ray, with a probability given by the fluorescence
+/// yield, or by emission of an Auger electron. The X-ray line is
+/// chosen according to the relative line intensities.
+///
+/// Fluorescence from the L-shell and higher shells is neglected; the
+/// resulting X-rays are usually absorbed very close to their origin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FluorescenceData {
+    z: u32,
+    k_edge: Joule<f64>,
+    k_yield: f64,
+    k_fraction: f64,
+    /// The line energies and their cumulative relative intensities,
+    /// normalized to one.
+    lines: Vec<(Joule<f64>, f64)>,
+}
+
+impl FluorescenceData {
+    /// Creates new fluorescence data.
+    ///
+    /// `lines` contains the energy and relative intensity of each
+    /// K line. The intensities need not be normalized.
+    ///
+    /// # Panics
+    /// This panics if `lines` is empty, any intensity is negative, or
+    /// all intensities are zero.
+    pub fn new(
+        z: u32,
+        k_edge: Joule<f64>,
+        k_yield: f64,
+        k_fraction: f64,
+        lines: &[(Joule<f64>, f64)],
+    ) -> Self {
+        assert!(
+            lines.iter().all(|&(_, intensity)| intensity >= 0.0),
+            "negative line intensity"
+        );
+        let total: f64 = lines.iter().map(|&(_, intensity)| intensity).sum();
+        assert!(total > 0.0, "no X-ray lines");
+        let mut sum = 0.0;
+        let lines = lines
+            .iter()
+            .map(|&(energy, intensity)| {
+                sum += intensity / total;
+                (energy, sum)
+            })
+            .collect();
+        FluorescenceData {
+            z,
+            k_edge,
+            k_yield,
+            k_fraction,
+            lines,
+        }
+    }
+
+    /// Returns the built-in data of the element with the given atomic
+    /// number.
+    ///
+    /// Data is available for Fe, Cu, Mo, Ag, Sn, I, Cs, W, Pb and Bi.
+    /// For all other elements, `None` is returned.
+    pub fn for_element(z: u32) -> Option<Self> {
+        let kev = KILO * EV;
+        K_SHELL_DATA
+            .iter()
+            .find(|&&(data_z, _, _, _, _)| data_z == z)
+            .map(|&(z, k_edge, k_yield, k_fraction, lines)| {
+                let lines: Vec<_> = lines
+                    .iter()
+                    .map(|&(energy, intensity)| (energy * kev, intensity))
+                    .collect();
+                Self::new(z, k_edge * kev, k_yield, k_fraction, &lines)
+            })
+    }
+
+    /// Reads fluorescence data of several elements from a file.
+    ///
+    /// Each non-empty line that does not start with `#` describes one
+    /// element with whitespace-separated columns: the atomic number,
+    /// the K-edge energy in keV, the fluorescence yield, the K-shell
+    /// fraction, and any number of pairs of line energy in keV and
+    /// relative intensity.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be read or any line is malformed.
+    pub fn from_file<P>(path: P) -> Result<Vec<Self>, FormatError>
+    where
+        P: AsRef<Path>,
+    {
+        let kev = KILO * EV;
+        let reader = BufReader::new(File::open(path)?);
+        let mut result = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message| FormatError::Parse { line: i + 1, message };
+            let numbers = line.split_whitespace()
+                .map(|word| word.parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| error("invalid number"))?;
+            if numbers.len() < 6 || numbers.len() % 2 != 0 {
+                return Err(error("expected four columns and pairs of line energy and intensity"));
+            }
+            let lines: Vec<_> = numbers[4..]
+                .chunks(2)
+                .map(|pair| (pair[0] * kev, pair[1]))
+                .collect();
+            if lines.iter().all(|&(_, intensity)| intensity <= 0.0) ||
+                lines.iter().any(|&(_, intensity)| intensity < 0.0)
+            {
+                return Err(error("invalid line intensities"));
+            }
+            let data = Self::new(numbers[0] as u32, numbers[1] * kev, numbers[2], numbers[3], &lines);
+            result.push(data);
+        }
+        Ok(result)
+    }
+
+    /// Returns the atomic number of the element.
+    pub fn z(&self) -> u32 {
+        self.z
+    }
+
+    /// Returns the energy of the K-edge.
+    pub fn k_edge(&self) -> Joule<f64> {
+        self.k_edge
+    }
+
+    /// Returns the K-shell fluorescence yield.
+    pub fn k_yield(&self) -> f64 {
+        self.k_yield
+    }
+
+    /// Returns the fraction of photo-effect events above the K-edge
+    /// that ionize the K-shell.
+    pub fn k_fraction(&self) -> f64 {
+        self.k_fraction
+    }
+
+    /// Randomly decides whether a photo-effect event emits an X-ray.
+    ///
+    /// `energy` is the energy of the absorbed photon. If an X-ray is
+    /// emitted, its energy is returned, otherwise `None`. This is
+    /// suitable for `Experiment::gen_fluorescence()`.
+    pub fn gen_xray<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Option<Joule<f64>> {
+        if energy < self.k_edge || rng.gen::<f64>() >= self.k_fraction * self.k_yield {
+            return None;
+        }
+        let choice = rng.gen::<f64>();
+        self.lines
+            .iter()
+            .find(|&&(_, cumulative)| choice < cumulative)
+            .or_else(|| self.lines.last())
+            .map(|&(energy, _)| energy)
+    }
+}
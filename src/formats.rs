@@ -0,0 +1,313 @@
+use std::io;
+use std::fmt;
+use std::fs::File;
+use std::error::Error;
+use std::path::Path;
+use std::io::{BufRead, BufReader};
+
+use dimensioned::si::*;
+use dimensioned::f64prefixes::*;
+
+use function::Function;
+use mc::material::ElementCrossSections;
+
+
+/// A table of photon cross-sections in the format of NIST XCOM.
+///
+/// XCOM (https://physics.nist.gov/xcom) prints tables with eight
+/// columns: the photon energy in MeV, the coherent and incoherent
+/// scattering cross-sections, the photo-effect cross-section, the
+/// pair-production cross-sections in the nuclear and in the electron
+/// field, and the total attenuation with and without coherent
+/// scattering. The cross-sections must have been requested in units of
+/// barn per atom.
+///
+/// Header lines are skipped. Lines of absorption edges, which are
+/// prefixed with the name of the edge (e.g. `K`), are read normally.
+/// At an edge, the table thus contains the same energy twice.
+#[derive(Debug, Clone)]
+pub struct XcomTable {
+    energies: Vec<Joule<f64>>,
+    /// The five cross-section columns after the energy, in barn.
+    columns: [Vec<f64>; 5],
+}
+
+impl XcomTable {
+    /// Reads an XCOM table from a file.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be read, a data line has fewer
+    /// than six columns, or any number cannot be parsed.
+    pub fn from_file<P>(path: P) -> Result<Self, FormatError>
+    where
+        P: AsRef<Path>,
+    {
+        let reader = BufReader::new(File::open(path)?);
+        let mut energies = Vec::new();
+        let mut columns: [Vec<f64>; 5] = Default::default();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let mut words: Vec<&str> = line.split_whitespace().collect();
+            // Skip the name of an absorption edge.
+            if words.len() > 1 && parse_float(words[0]).is_none() && parse_float(words[1]).is_some() {
+                words.remove(0);
+            }
+            // Skip header and empty lines.
+            let energy = match words.first().and_then(|word| parse_float(word)) {
+                Some(energy) => energy,
+                None => continue,
+            };
+            if words.len() < 6 {
+                return Err(FormatError::parse(i + 1, "expected at least six columns"));
+            }
+            energies.push(energy * MEGA * EV);
+            for (column, word) in columns.iter_mut().zip(&words[1..6]) {
+                let value = parse_float(word).ok_or_else(|| FormatError::parse(i + 1, "invalid number"))?;
+                column.push(value);
+            }
+        }
+        if energies.is_empty() {
+            return Err(FormatError::parse(0, "no data"));
+        }
+        Ok(XcomTable { energies, columns })
+    }
+
+    /// Returns the tabulated energies.
+    pub fn energies(&self) -> &[Joule<f64>] {
+        &self.energies
+    }
+
+    /// Returns the coherent scattering cross-section.
+    pub fn coherent(&self) -> Function<Joule<f64>, Meter2<f64>> {
+        self.column(0)
+    }
+
+    /// Returns the incoherent scattering cross-section.
+    pub fn incoherent(&self) -> Function<Joule<f64>, Meter2<f64>> {
+        self.column(1)
+    }
+
+    /// Returns the photo-effect cross-section.
+    pub fn photo(&self) -> Function<Joule<f64>, Meter2<f64>> {
+        self.column(2)
+    }
+
+    /// Returns the pair-production cross-section in the nuclear field.
+    pub fn pair_nuclear(&self) -> Function<Joule<f64>, Meter2<f64>> {
+        self.column(3)
+    }
+
+    /// Returns the pair-production cross-section in the electron field.
+    pub fn pair_electron(&self) -> Function<Joule<f64>, Meter2<f64>> {
+        self.column(4)
+    }
+
+    /// Converts the table into the cross-sections of an element.
+    ///
+    /// Pair production is not included.
+    pub fn to_element(&self, atomic_mass: Kilogram<f64>) -> ElementCrossSections {
+        ElementCrossSections::new(atomic_mass, self.coherent(), self.incoherent(), self.photo())
+    }
+
+    /// Private method that turns a column into a function.
+    fn column(&self, index: usize) -> Function<Joule<f64>, Meter2<f64>> {
+        let column = &self.columns[index];
+        let mut function = Function::with_capacity(column.len(), self.energies[0], column[0] * BARN);
+        function.extend(
+            self.energies[1..]
+                .iter()
+                .zip(&column[1..])
+                .map(|(&energy, &value)| (energy, value * BARN)),
+        );
+        function
+    }
+}
+
+
+/// Reaction descriptor `C` of coherent scattering in EPDL.
+pub const EPDL_COHERENT: u32 = 71;
+/// Reaction descriptor `C` of incoherent scattering in EPDL.
+pub const EPDL_INCOHERENT: u32 = 72;
+/// Reaction descriptor `C` of the photo-effect in EPDL.
+pub const EPDL_PHOTO: u32 = 73;
+/// Reaction descriptor `C` of pair production in the nuclear field in
+/// EPDL.
+pub const EPDL_PAIR_NUCLEAR: u32 = 74;
+/// Reaction descriptor `C` of pair production in the electron field
+/// (triplet production) in EPDL.
+pub const EPDL_PAIR_ELECTRON: u32 = 75;
+/// Reaction property `I` of integrated cross-sections in EPDL.
+pub const EPDL_CROSS_SECTION: u32 = 0;
+
+
+/// A single table of an EPDL file.
+///
+/// The Evaluated Photon Data Library (EPDL97, EPICS2017) is
+/// distributed in the ASCII format of the LLNL Evaluated Nuclear Data
+/// Library (ENDL). A file consists of tables, each with two header
+/// lines followed by data lines with two numbers each. A `1` in column
+/// 72 marks the end of a table.
+///
+/// Each table is identified by the reaction descriptor `C` (e.g.
+/// `EPDL_COHERENT`), the reaction property `I` (e.g.
+/// `EPDL_CROSS_SECTION`), and the reaction modifier `S`. Integrated
+/// cross-sections are given in barn against the energy in MeV.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpdlTable {
+    /// The atomic number of the element.
+    pub z: u32,
+    /// The reaction descriptor `C`.
+    pub c: u32,
+    /// The reaction property `I`.
+    pub i: u32,
+    /// The reaction modifier `S`.
+    pub s: u32,
+    /// The subshell designator `X1`, if any.
+    pub x1: f64,
+    /// The data points of the table.
+    pub data: Vec<(f64, f64)>,
+}
+
+impl EpdlTable {
+    /// Reads all tables from an EPDL file.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be read or is not in ENDL format.
+    pub fn read_all<P>(path: P) -> Result<Vec<Self>, FormatError>
+    where
+        P: AsRef<Path>,
+    {
+        let reader = BufReader::new(File::open(path)?);
+        let mut tables = Vec::new();
+        let mut lines = reader.lines().enumerate();
+        while let Some((n, first)) = lines.next() {
+            let first = first?;
+            if first.trim().is_empty() {
+                continue;
+            }
+            let (n, second) = match lines.next() {
+                Some((n, line)) => (n, line?),
+                None => return Err(FormatError::parse(n + 1, "incomplete table header")),
+            };
+            let mut table = EpdlTable {
+                z: parse_field(&first, 0, 3).ok_or_else(|| FormatError::parse(n, "invalid Z"))? as u32,
+                c: parse_field(&second, 0, 2).ok_or_else(|| FormatError::parse(n + 1, "invalid C"))? as u32,
+                i: parse_field(&second, 2, 5).ok_or_else(|| FormatError::parse(n + 1, "invalid I"))? as u32,
+                s: parse_field(&second, 5, 8).unwrap_or(0.0) as u32,
+                x1: parse_field(&second, 21, 32).unwrap_or(0.0),
+                data: Vec::new(),
+            };
+            loop {
+                let (n, line) = match lines.next() {
+                    Some((n, line)) => (n, line?),
+                    None => return Err(FormatError::parse(n + 1, "unterminated table")),
+                };
+                if line.get(71..72) == Some("1") {
+                    break;
+                }
+                let x = parse_field(&line, 0, 11).ok_or_else(|| FormatError::parse(n + 1, "invalid number"))?;
+                let y = parse_field(&line, 11, 22).ok_or_else(|| FormatError::parse(n + 1, "invalid number"))?;
+                table.data.push((x, y));
+            }
+            tables.push(table);
+        }
+        Ok(tables)
+    }
+
+    /// Reads the table with the given `C` and `I` from an EPDL file.
+    ///
+    /// If there are several such tables, the first one is returned.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be read, is not in ENDL format, or
+    /// contains no such table.
+    pub fn read<P>(path: P, c: u32, i: u32) -> Result<Self, FormatError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::read_all(path)?
+            .into_iter()
+            .find(|table| table.c == c && table.i == i)
+            .ok_or_else(|| FormatError::parse(0, "table not found"))
+    }
+
+    /// Interprets the table as a cross-section in barn against the
+    /// energy in MeV.
+    ///
+    /// # Panics
+    /// This panics if the table is empty.
+    pub fn to_cross_section(&self) -> Function<Joule<f64>, Meter2<f64>> {
+        let mut points = self.data
+            .iter()
+            .map(|&(energy, xsection)| (energy * MEGA * EV, xsection * BARN));
+        let (energy, xsection) = points.next().expect("empty table");
+        let mut function = Function::with_capacity(self.data.len(), energy, xsection);
+        function.extend(points);
+        function
+    }
+}
+
+
+/// The error type returned by the parsers in this module.
+#[derive(Debug)]
+pub enum FormatError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The file is malformed. `line` is the one-based number of the
+    /// offending line, or zero if the error concerns the whole file.
+    Parse { line: usize, message: &'static str },
+}
+
+impl FormatError {
+    /// Private function that creates a `Parse` error.
+    fn parse(line: usize, message: &'static str) -> Self {
+        FormatError::Parse { line, message }
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FormatError::Io(ref err) => write!(f, "could not read file: {}", err),
+            FormatError::Parse { line: 0, message } => write!(f, "{}", message),
+            FormatError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl Error for FormatError {}
+
+impl From<io::Error> for FormatError {
+    fn from(err: io::Error) -> Self {
+        FormatError::Io(err)
+    }
+}
+
+
+/// Private function that parses a Fortran floating-point number.
+///
+/// Besides the usual formats, this accepts numbers with an exponent
+/// but without the letter `E`, e.g. `1.2345-3`.
+fn parse_float(s: &str) -> Option<f64> {
+    let s = s.trim();
+    if let Ok(x) = s.parse() {
+        return Some(x);
+    }
+    let split = s.char_indices()
+        .skip(1)
+        .find(|&(i, c)| (c == '+' || c == '-') && !s[..i].ends_with(['e', 'E']))
+        .map(|(i, _)| i)?;
+    let mantissa: f64 = s[..split].parse().ok()?;
+    let exponent: i32 = s[split..].trim_start_matches('+').parse().ok()?;
+    Some(mantissa * 10f64.powi(exponent))
+}
+
+
+/// Private function that parses a fixed-width field of a line.
+///
+/// `start` and `end` are zero-based character columns. Fields that
+/// extend beyond the end of the line are truncated.
+fn parse_field(line: &str, start: usize, end: usize) -> Option<f64> {
+    let end = end.min(line.len());
+    line.get(start..end).and_then(parse_float)
+}
@@ -8,6 +8,8 @@ use std::ops::{Add, Sub, Mul, Div, Range};
 use csv;
 use serde::Deserialize;
 
+use error::Error;
+
 
 /// A trait alias that simplifies the signature of `Number`.
 ///
@@ -41,6 +43,7 @@ impl<T: Primitive + Add<Output = Self> + Sub<Output = Self>> Number for T {}
 /// This type allows reading CSV files as function definitions and then
 /// evaluating the function via linear interpolation.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Function<X, Y = X>
 where
     X: Number,
@@ -63,6 +66,21 @@ impl<X: Number, Y: Number> Function<X, Y> {
         }
     }
 
+    /// Like `new()`, but checks the point like `try_push()`.
+    ///
+    /// # Errors
+    /// This fails with `Error::Domain` if `x` or `y` is not comparable
+    /// to itself; for example by being NaN.
+    pub fn try_new(x: X, y: Y) -> Result<Self, Error> {
+        if x.partial_cmp(&x).is_none() {
+            return Err(Error::domain(format!("not a number: {:?}", x)));
+        }
+        if y.partial_cmp(&y).is_none() {
+            return Err(Error::domain(format!("not a number: {:?}", y)));
+        }
+        Ok(Function::new(x, y))
+    }
+
     /// Creates a function that initially contains only one point.
     ///
     /// Additionally, the contained vectors are initialized with the
@@ -130,20 +148,33 @@ impl<X: Number, Y: Number> Function<X, Y> {
     /// It also panics if `x` or `y` is not comparable to other values;
     /// for example by being NaN.
     pub fn push(&mut self, x: X, y: Y) {
+        self.try_push(x, y).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `push()`, but fails instead of panicking.
+    ///
+    /// # Errors
+    /// This fails with `Error::Domain` if `x` is less than the last
+    /// X-value added or if `x` or `y` is not comparable to other
+    /// values. In this case, the function is left unchanged.
+    pub fn try_push(&mut self, x: X, y: Y) -> Result<(), Error> {
         use std::cmp::Ordering::*;
 
         let last_x = self.domain().end;
-        match X::panicking_cmp(&x, &last_x) {
-            Less => panic!("point out of order: {:?}", x),
+        match x.partial_cmp(&last_x) {
+            Some(Less) => return Err(Error::domain(format!("point out of order: {:?}", x))),
+            None => return Err(Error::domain(format!("not a number: {:?}", x))),
             _ => {},
         }
-        if Y::panicking_cmp(&y, &self.ymin) == Less {
-            self.ymin = y;
-        } else if Y::panicking_cmp(&y, &self.ymax) == Greater {
-            self.ymax = y;
+        match (y.partial_cmp(&self.ymin), y.partial_cmp(&self.ymax)) {
+            (Some(Less), _) => self.ymin = y,
+            (_, Some(Greater)) => self.ymax = y,
+            (Some(_), Some(_)) => {},
+            _ => return Err(Error::domain(format!("not a number: {:?}", y))),
         }
         self.xdata.push(x);
         self.ydata.push(y);
+        Ok(())
     }
 
     /// Scales the X-axis with a constant factor.
@@ -229,16 +260,28 @@ where
     /// # Panics
     /// This panics if `x` lies not within the domain of this function.
     pub fn call(&self, x: X) -> Y {
+        self.try_call(x).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `call()`, but fails instead of panicking.
+    ///
+    /// # Errors
+    /// This fails with `Error::Domain` if `x` lies not within the
+    /// domain of this function.
+    pub fn try_call(&self, x: X) -> Result<Y, Error> {
+        if x.partial_cmp(&x).is_none() {
+            return Err(Error::domain(format!("not a number: {:?}", x)));
+        }
         let iend = match self.xdata.binary_search_by(|x1| x1.panicking_cmp(&x)) {
-            Ok(i) => return self.ydata[i],
+            Ok(i) => return Ok(self.ydata[i]),
             Err(i) => i,
         };
         if iend == 0 || iend == self.xdata.len() {
-            panic!("out of bounds: {:?}", x)
+            return Err(Error::domain(format!("out of bounds: {:?}", x)));
         }
         let left = (self.xdata[iend - 1], self.ydata[iend - 1]);
         let right = (self.xdata[iend], self.ydata[iend]);
-        Self::interpolate(left, right, x)
+        Ok(Self::interpolate(left, right, x))
     }
 
     /// Interpolate between two points.
@@ -271,12 +314,10 @@ where
     /// lines starting with `'#'`.
     ///
     /// # Errors
-    /// This function fails if the file cannot be read or any number
-    /// cannot be parsed.
-    ///
-    /// # Panics
-    /// This panics if any number gets parsed as NaN.
-    pub fn from_file<P>(path: P) -> csv::Result<Self>
+    /// This function fails if the file cannot be read, is empty, any
+    /// number cannot be parsed, or the X-values are out of order or
+    /// NaN.
+    pub fn from_file<P>(path: P) -> Result<Self, Error>
     where
         P: AsRef<Path>,
     {
@@ -285,13 +326,13 @@ where
 
         let mut func = if let Some(record) = records.next() {
             let (x, y) = record?.deserialize(None)?;
-            Function::new(x, y)
+            Function::try_new(x, y)?
         } else {
-            panic!("empty file");
+            return Err(Error::domain("empty file"));
         };
         for record in records {
             let (x, y) = record?.deserialize(None)?;
-            func.push(x, y);
+            func.try_push(x, y)?;
         }
         Ok(func)
     }
@@ -307,12 +348,8 @@ where
     /// CSV file with four columns creates three functions.
     ///
     /// # Errors
-    /// This function fails if the file cannot be read or any number
-    /// cannot be parsed.
-    ///
-    /// # Panics
-    /// This panics if any number gets parsed as NaN.
-    pub fn multiple_from_file<P>(path: P) -> csv::Result<Vec<Self>>
+    /// This function fails under the same conditions as `from_file()`.
+    pub fn multiple_from_file<P>(path: P) -> Result<Vec<Self>, Error>
     where
         P: AsRef<Path>,
     {
@@ -322,16 +359,16 @@ where
         let mut funcs = if let Some(record) = records.next() {
             let (x, ys): (X, Vec<Y>) = record?.deserialize(None)?;
             ys.into_iter()
-                .map(|y| Function::new(x, y))
-                .collect::<Vec<_>>()
+                .map(|y| Function::try_new(x, y))
+                .collect::<Result<Vec<_>, _>>()?
         } else {
-            panic!("empty file");
+            return Err(Error::domain("empty file"));
         };
 
         for record in records {
             let (x, ys): (X, Vec<Y>) = record?.deserialize(None)?;
             for (y, func) in ys.into_iter().zip(&mut funcs) {
-                func.push(x, y);
+                func.try_push(x, y)?;
             }
         }
         Ok(funcs)
@@ -1,33 +1,249 @@
+use std::fmt::Debug;
+use std::cmp::Ordering;
+use std::path::Path;
+use std::ops::{Add, Sub, Mul, Div, AddAssign};
+
+use csv;
+
+use error::Error;
 use contains::Contains;
 
+
+/// The trait of floating-point types that can be binned in a
+/// `Histogram`.
+///
+/// This is implemented for `f32` and `f64`. Single precision halves the
+/// memory needed for bin edges, which matters for histogram-heavy
+/// workloads on constrained hardware.
+///
+/// Only histograms are generic over the precision. `Statistics` and the
+/// geometry types remain `f64`: the former divides by `f64` sample
+/// counts and the latter is built on dimensioned `f64` quantities.
+pub trait Float
+where
+    Self: Copy + Debug + PartialOrd,
+    Self: Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self>,
+{
+    /// Converts a bin count or index to this type.
+    fn from_usize(n: usize) -> Self;
+
+    /// Converts a double-precision value to this type.
+    fn from_f64(x: f64) -> Self;
+
+    /// Converts this value to double precision.
+    fn to_f64(self) -> f64;
+}
+
+impl Float for f32 {
+    fn from_usize(n: usize) -> Self {
+        n as f32
+    }
+
+    fn from_f64(x: f64) -> Self {
+        x as f32
+    }
+
+    fn to_f64(self) -> f64 {
+        f64::from(self)
+    }
+}
+
+impl Float for f64 {
+    fn from_usize(n: usize) -> Self {
+        n as f64
+    }
+
+    fn from_f64(x: f64) -> Self {
+        x
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+
 /// Histograms count for a range of values which occurred how often.
-pub struct Histogram {
-    range: (f64, f64),
-    edges: Box<[f64]>,
-    weights: Box<[u32]>,
+///
+/// The binned values are `f64` by default, but may also be `f32`, see
+/// `Float`. The bin contents and weights are always `f64`, since single
+/// precision stops counting entries beyond 2^24.
+///
+/// Each entry may carry a fractional statistical weight, as is the case
+/// with variance-reduction techniques. The content of a bin is the sum
+/// of the weights of its entries. The sum of their squares is tracked
+/// as well, so that the statistical error of each bin is available.
+///
+/// # Examples
+/// ```
+/// use mcgen::Histogram;
+///
+/// let mut hist = Histogram::<f32>::new(4, 0.0, 2.0);
+/// hist.extend(vec![0.1, 0.2, 1.7]);
+/// hist.fill_weighted(1.7, 0.5);
+/// assert_eq!(hist.bin_contents(), &[2.0, 0.0, 0.0, 1.5]);
+/// assert_eq!(hist.bin_sums_of_squares(), &[2.0, 0.0, 0.0, 1.25]);
+/// ```
+///
+/// Histograms with the same bins can be added with `+` and `+=`, e.g.
+/// to combine the partial results of parallel runs:
+/// ```
+/// use mcgen::Histogram;
+///
+/// let mut signal = Histogram::new(2, 0.0, 2.0);
+/// signal.extend(vec![0.5, 1.5, 1.5]);
+/// let mut background = Histogram::new(2, 0.0, 2.0);
+/// background.extend(vec![1.5]);
+/// let total = signal + &background;
+/// assert_eq!(total.bin_contents(), &[1.0, 3.0]);
+/// ```
+///
+/// Histograms can be saved to and loaded from CSV files with
+/// `to_csv()` and `from_csv()`. With the feature `serde`, they
+/// also implement `Serialize` and `Deserialize`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Histogram<T = f64> {
+    range: (T, T),
+    edges: Box<[T]>,
+    weights: Box<[f64]>,
+    squares: Box<[f64]>,
 }
 
-impl Histogram {
+impl<T: Float> Histogram<T> {
     /// Creates a new histogram with `nbins` bins filling the range
     /// from `low` to `high`.
-    pub fn new(nbins: usize, low: f64, high: f64) -> Self {
+    pub fn new(nbins: usize, low: T, high: T) -> Self {
         let nedges = nbins + 1;
         let mut edges = Vec::with_capacity(nedges);
-        let bin_width = (high - low) / (nbins as f64);
+        let bin_width = (high - low) / T::from_usize(nbins);
         for i in 0..nedges {
-            edges.push(low + bin_width * (i as f64));
+            edges.push(low + bin_width * T::from_usize(i));
         }
         // Turn the vectors into boxed slices because we no longe need
         // the `capacity` field.
         Histogram {
             edges: edges.into_boxed_slice(),
-            weights: vec![0; nbins].into_boxed_slice(),
+            weights: vec![0.0; nbins].into_boxed_slice(),
+            squares: vec![0.0; nbins].into_boxed_slice(),
             range: (low, high),
         }
     }
 
+    /// Creates a new histogram with bins between the given edges.
+    ///
+    /// The bins may have different widths, e.g. to cover several
+    /// decades of energy.
+    ///
+    /// # Panics
+    /// This panics if there are fewer than two edges or if the edges
+    /// are not strictly increasing.
+    ///
+    /// # Examples
+    /// ```
+    /// use mcgen::Histogram;
+    ///
+    /// let mut hist = Histogram::with_edges(vec![0.0, 1.0, 10.0, 100.0]);
+    /// hist.extend(vec![0.5, 5.0, 50.0, 60.0]);
+    /// assert_eq!(hist.bin_contents(), &[1.0, 1.0, 2.0]);
+    /// ```
+    pub fn with_edges(edges: Vec<T>) -> Self {
+        assert!(edges.len() >= 2, "need at least two bin edges");
+        assert!(
+            edges.windows(2).all(|pair| pair[0] < pair[1]),
+            "bin edges must be strictly increasing"
+        );
+        let nbins = edges.len() - 1;
+        Histogram {
+            range: (edges[0], edges[nbins]),
+            edges: edges.into_boxed_slice(),
+            weights: vec![0.0; nbins].into_boxed_slice(),
+            squares: vec![0.0; nbins].into_boxed_slice(),
+        }
+    }
+
+    /// Creates a new histogram with `nbins` logarithmically spaced
+    /// bins filling the range from `low` to `high`.
+    ///
+    /// # Panics
+    /// This panics if `nbins` is zero, or if `low` is not positive or
+    /// not less than `high`.
+    pub fn with_log_bins(nbins: usize, low: T, high: T) -> Self {
+        assert!(nbins > 0, "need at least one bin");
+        let (log_low, log_high) = (low.to_f64().ln(), high.to_f64().ln());
+        assert!(log_low.is_finite() && log_low < log_high, "invalid logarithmic range");
+        let step = (log_high - log_low) / nbins as f64;
+        let mut edges: Vec<T> = (0..nbins)
+            .map(|i| T::from_f64((log_low + step * i as f64).exp()))
+            .collect();
+        // Avoid rounding errors at both ends of the range.
+        edges[0] = low;
+        edges.push(high);
+        Self::with_edges(edges)
+    }
+
+    /// Reads a histogram from a CSV file written by `to_csv()`.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be read, if any record is
+    /// malformed, if there are no bins, or if the bins are not
+    /// adjacent and in increasing order.
+    pub fn from_csv<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut reader = csv::Reader::from_path(path)?;
+        let mut edges = Vec::new();
+        let mut weights = Vec::new();
+        let mut squares = Vec::new();
+        for record in reader.deserialize() {
+            let bin: BinRecord = record?;
+            let (low, high) = (T::from_f64(bin.low), T::from_f64(bin.high));
+            match edges.last() {
+                None => edges.push(low),
+                Some(&previous) if previous == low => {},
+                Some(_) => return Err(Error::domain("histogram bins are not adjacent")),
+            }
+            match low.partial_cmp(&high) {
+                Some(Ordering::Less) => edges.push(high),
+                _ => return Err(Error::domain("histogram bin edges are not increasing")),
+            }
+            weights.push(bin.content);
+            squares.push(bin.sum_of_squares);
+        }
+        if weights.is_empty() {
+            return Err(Error::domain("histogram without bins"));
+        }
+        let mut hist = Self::with_edges(edges);
+        hist.weights = weights.into_boxed_slice();
+        hist.squares = squares.into_boxed_slice();
+        Ok(hist)
+    }
+
+    /// Writes the histogram as CSV to the file at `path`.
+    ///
+    /// Each bin is written as one row with its low and high edge, its
+    /// content, and the sum of its squared weights. The first line is
+    /// a header. Unlike gnuplot output, this preserves all information
+    /// and can be read back with `from_csv()`.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be written.
+    pub fn to_csv<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let mut writer = csv::Writer::from_path(path)?;
+        let bins = self.bin_low_edges().iter().zip(self.bin_high_edges());
+        let contents = self.weights.iter().zip(self.squares.iter());
+        for ((&low, &high), (&content, &sum_of_squares)) in bins.zip(contents) {
+            writer.serialize(BinRecord {
+                low: low.to_f64(),
+                high: high.to_f64(),
+                content,
+                sum_of_squares,
+            })?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
     /// Returns the lower and upper limit of the histogram.
-    pub fn range(&self) -> &(f64, f64) {
+    pub fn range(&self) -> &(T, T) {
         &self.range
     }
 
@@ -44,18 +260,26 @@ impl Histogram {
     }
 
     /// Returns the width of each bin of this histogram.
-    pub fn bin_width(&self) -> f64 {
+    ///
+    /// For histograms with bins of different widths, this is their
+    /// mean width; use `bin_widths()` instead.
+    pub fn bin_width(&self) -> T {
         let &(low, high) = self.range();
-        (high - low) / (self.num_bins() as f64)
+        (high - low) / T::from_usize(self.num_bins())
+    }
+
+    /// Returns the width of each individual bin.
+    pub fn bin_widths(&self) -> Vec<T> {
+        self.edges.windows(2).map(|bin| bin[1] - bin[0]).collect()
     }
 
     /// Returns the low edges of the histogram's bins.
-    pub fn bin_low_edges(&self) -> &[f64] {
+    pub fn bin_low_edges(&self) -> &[T] {
         &self.edges[..self.num_bins()]
     }
 
     /// Returns the high edges of the histogram's bins.
-    pub fn bin_high_edges(&self) -> &[f64] {
+    pub fn bin_high_edges(&self) -> &[T] {
         &self.edges[1..]
     }
 
@@ -63,33 +287,153 @@ impl Histogram {
     ///
     /// This method returns an iterator instead of a slice because the
     /// bin centers are calculated on the fly.
-    pub fn bin_centers(&self) -> BinCenters {
+    pub fn bin_centers(&self) -> BinCenters<'_, T> {
         BinCenters {
-            low_edges: self.bin_low_edges().iter(),
-            bin_width: self.bin_width(),
+            bins: self.edges.windows(2),
         }
     }
 
     /// Returns the contents of each of the histogram's bins.
-    pub fn bin_contents(&self) -> &[u32] {
+    ///
+    /// The content of a bin is the sum of the weights of its entries.
+    pub fn bin_contents(&self) -> &[f64] {
         self.weights.as_ref()
     }
 
+    /// Returns the sum of the squared weights in each bin.
+    pub fn bin_sums_of_squares(&self) -> &[f64] {
+        self.squares.as_ref()
+    }
+
+    /// Returns the statistical error of each bin's content.
+    ///
+    /// This is the square root of the sum of the squared weights. For
+    /// unweighted entries, it is the familiar `sqrt(N)`.
+    pub fn bin_errors(&self) -> Vec<f64> {
+        self.squares.iter().map(|&square| square.sqrt()).collect()
+    }
+
     /// Increases the bin located at `x` by one.
     ///
     /// If `x` lies outside of the range of the histogram, this method
     /// does nothing.
-    pub fn fill(&mut self, x: f64) {
+    pub fn fill(&mut self, x: T) {
         self.fill_by(x, 1)
     }
 
-    /// Increases the bin located at `x` by `weight`.
+    /// Adds `count` entries of weight one to the bin located at `x`.
     ///
     /// If `x` lies outside of the range of the histogram, this method
     /// does nothing.
-    pub fn fill_by(&mut self, x: f64, weight: u32) {
+    pub fn fill_by(&mut self, x: T, count: u32) {
+        if let Some(i) = self.find_bin(x) {
+            self.weights[i] += f64::from(count);
+            self.squares[i] += f64::from(count);
+        }
+    }
+
+    /// Adds a single entry of weight `weight` to the bin located at
+    /// `x`.
+    ///
+    /// If `x` lies outside of the range of the histogram, this method
+    /// does nothing.
+    pub fn fill_weighted(&mut self, x: T, weight: f64) {
         if let Some(i) = self.find_bin(x) {
             self.weights[i] += weight;
+            self.squares[i] += weight * weight;
+        }
+    }
+
+    /// Adds the bin contents of another histogram to `self`.
+    ///
+    /// This allows filling several histograms on different threads and
+    /// combining them at the end.
+    ///
+    /// # Panics
+    /// This panics if both histograms have different bins.
+    pub fn merge(&mut self, other: &Histogram<T>) {
+        assert!(self.has_same_bins(other), "histograms with different bins");
+        for (weight, other) in self.weights.iter_mut().zip(other.weights.iter()) {
+            *weight += *other;
+        }
+        for (square, other) in self.squares.iter_mut().zip(other.squares.iter()) {
+            *square += *other;
+        }
+    }
+
+    /// Returns `true` if both histograms have exactly the same bin
+    /// edges.
+    ///
+    /// Only such histograms can be combined bin by bin.
+    pub fn has_same_bins(&self, other: &Histogram<T>) -> bool {
+        self.edges == other.edges
+    }
+
+    /// Multiplies the content of each bin by `factor`.
+    ///
+    /// The statistical errors are scaled accordingly. This can be used
+    /// e.g. to normalize a spectrum to the number of histories.
+    pub fn scale(&mut self, factor: f64) {
+        for weight in self.weights.iter_mut() {
+            *weight *= factor;
+        }
+        for square in self.squares.iter_mut() {
+            *square *= factor * factor;
+        }
+    }
+
+    /// Subtracts the bin contents of another histogram from `self`.
+    ///
+    /// Both histograms are assumed to be statistically independent,
+    /// e.g. a measurement and its background. Their errors are added
+    /// in quadrature.
+    ///
+    /// # Panics
+    /// This panics if both histograms have different bins.
+    pub fn subtract(&mut self, other: &Histogram<T>) {
+        assert!(self.has_same_bins(other), "histograms with different bins");
+        for (weight, other) in self.weights.iter_mut().zip(other.weights.iter()) {
+            *weight -= *other;
+        }
+        for (square, other) in self.squares.iter_mut().zip(other.squares.iter()) {
+            *square += *other;
+        }
+    }
+
+    /// Divides the bin contents of `self` by those of another
+    /// histogram.
+    ///
+    /// Both histograms are assumed to be statistically independent and
+    /// their relative errors are added in quadrature. Bins in which the
+    /// denominator is zero are set to zero.
+    ///
+    /// # Panics
+    /// This panics if both histograms have different bins.
+    ///
+    /// # Examples
+    /// ```
+    /// use mcgen::Histogram;
+    ///
+    /// let mut detected = Histogram::new(2, 0.0, 2.0);
+    /// detected.extend(vec![(0.5, 1u32), (1.5, 4u32)]);
+    /// let mut emitted = Histogram::new(2, 0.0, 2.0);
+    /// emitted.extend(vec![(1.5, 16u32)]);
+    /// detected.divide(&emitted);
+    /// assert_eq!(detected.bin_contents(), &[0.0, 0.25]);
+    /// ```
+    pub fn divide(&mut self, other: &Histogram<T>) {
+        assert!(self.has_same_bins(other), "histograms with different bins");
+        let numerators = self.weights.iter_mut().zip(self.squares.iter_mut());
+        let denominators = other.weights.iter().zip(other.squares.iter());
+        for ((weight, square), (&other, &other_square)) in numerators.zip(denominators) {
+            if other == 0.0 {
+                *weight = 0.0;
+                *square = 0.0;
+                continue;
+            }
+            let ratio = *weight / other;
+            *square = *square / (other * other) + ratio * ratio * other_square / (other * other);
+            *weight = ratio;
         }
     }
 
@@ -97,33 +441,346 @@ impl Histogram {
     ///
     /// If `x` lies outside of the range of this histogram, `None` is
     /// returned.
-    pub fn find_bin(&self, x: f64) -> Option<usize> {
+    pub fn find_bin(&self, x: T) -> Option<usize> {
         if !self.range.contains(x) {
             return None;
         }
-        for (i, bin) in self.edges.windows(2).enumerate() {
-            let bin = (bin[0], bin[1]);
-            if bin.contains(x) {
-                return Some(i);
+        // Values on an edge between two bins belong to the lower one.
+        Some(self.bin_high_edges().partition_point(|&edge| edge < x))
+    }
+}
+
+impl<'a, T: Float> AddAssign<&'a Histogram<T>> for Histogram<T> {
+    /// Adds the bin contents of `other` to `self`, see `merge()`.
+    fn add_assign(&mut self, other: &'a Histogram<T>) {
+        self.merge(other);
+    }
+}
+
+impl<T: Float> AddAssign for Histogram<T> {
+    /// Adds the bin contents of `other` to `self`, see `merge()`.
+    fn add_assign(&mut self, other: Histogram<T>) {
+        self.merge(&other);
+    }
+}
+
+impl<'a, T: Float> Add<&'a Histogram<T>> for Histogram<T> {
+    type Output = Histogram<T>;
+
+    /// Returns a histogram with the bin contents of both operands,
+    /// see `merge()`.
+    fn add(mut self, other: &'a Histogram<T>) -> Self::Output {
+        self.merge(other);
+        self
+    }
+}
+
+impl<T: Float> Add for Histogram<T> {
+    type Output = Histogram<T>;
+
+    /// Returns a histogram with the bin contents of both operands,
+    /// see `merge()`.
+    fn add(mut self, other: Histogram<T>) -> Self::Output {
+        self.merge(&other);
+        self
+    }
+}
+
+impl<T: Float> Extend<T> for Histogram<T> {
+    /// Successively `fill`s all values of the iterator into `self`.
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        for x in iter {
+            self.fill(x);
+        }
+    }
+}
+
+impl<T: Float> Extend<(T, u32)> for Histogram<T> {
+    /// Successively fills all counted values of the iterator into
+    /// `self` via `fill_by`.
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (T, u32)>,
+    {
+        for (x, count) in iter {
+            self.fill_by(x, count);
+        }
+    }
+}
+
+impl<T: Float> Extend<(T, f64)> for Histogram<T> {
+    /// Successively fills all weighted values of the iterator into
+    /// `self` via `fill_weighted`.
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (T, f64)>,
+    {
+        for (x, weight) in iter {
+            self.fill_weighted(x, weight);
+        }
+    }
+}
+
+
+/// Two-dimensional histogram of pairs of values.
+///
+/// The X- and Y-axis are divided into bins of equal width each. Like
+/// `Histogram`, entries may carry fractional weights and the sum of
+/// their squares is tracked.
+///
+/// The bin contents are stored row by row, with one row per Y-bin.
+/// This is the layout expected by the `image()` and `surface()` plots
+/// of the `gnuplot` crate, e.g.:
+///
+/// ```ignore
+/// let (nx, ny) = hist.num_bins();
+/// figure.axes2d().image(hist.bin_contents(), ny, nx, Some(hist.matrix_dimensions()), &[]);
+/// ```
+///
+/// # Examples
+/// ```
+/// use mcgen::Histogram2D;
+///
+/// let mut hist = Histogram2D::new(2, 0.0, 2.0, 3, 0.0, 3.0);
+/// hist.extend(vec![(0.5, 0.5), (1.5, 2.5), (1.5, 2.7)]);
+/// assert_eq!(hist.bin_content(1, 2), 2.0);
+/// assert_eq!(hist.bin_contents(), &[1.0, 0.0, 0.0, 0.0, 0.0, 2.0]);
+/// assert_eq!(hist.projection_x().bin_contents(), &[1.0, 2.0]);
+/// assert_eq!(hist.projection_y().bin_contents(), &[1.0, 0.0, 2.0]);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Histogram2D<T = f64> {
+    x_axis: Histogram<T>,
+    y_axis: Histogram<T>,
+    weights: Box<[f64]>,
+    squares: Box<[f64]>,
+}
+
+impl<T: Float> Histogram2D<T> {
+    /// Creates a new histogram with `nx` bins from `xlow` to `xhigh`
+    /// along the X-axis and `ny` bins from `ylow` to `yhigh` along the
+    /// Y-axis.
+    pub fn new(nx: usize, xlow: T, xhigh: T, ny: usize, ylow: T, yhigh: T) -> Self {
+        Histogram2D {
+            x_axis: Histogram::new(nx, xlow, xhigh),
+            y_axis: Histogram::new(ny, ylow, yhigh),
+            weights: vec![0.0; nx * ny].into_boxed_slice(),
+            squares: vec![0.0; nx * ny].into_boxed_slice(),
+        }
+    }
+
+    /// Returns the lower and upper limit of the X-axis.
+    pub fn x_range(&self) -> &(T, T) {
+        self.x_axis.range()
+    }
+
+    /// Returns the lower and upper limit of the Y-axis.
+    pub fn y_range(&self) -> &(T, T) {
+        self.y_axis.range()
+    }
+
+    /// Returns the number of bins along the X- and the Y-axis.
+    pub fn num_bins(&self) -> (usize, usize) {
+        (self.x_axis.num_bins(), self.y_axis.num_bins())
+    }
+
+    /// Returns the bin edges along the X-axis.
+    pub fn x_bin_edges(&self) -> &[T] {
+        &self.x_axis.edges
+    }
+
+    /// Returns the bin edges along the Y-axis.
+    pub fn y_bin_edges(&self) -> &[T] {
+        &self.y_axis.edges
+    }
+
+    /// Returns the centers of the bins along the X-axis.
+    pub fn x_bin_centers(&self) -> BinCenters<'_, T> {
+        self.x_axis.bin_centers()
+    }
+
+    /// Returns the centers of the bins along the Y-axis.
+    pub fn y_bin_centers(&self) -> BinCenters<'_, T> {
+        self.y_axis.bin_centers()
+    }
+
+    /// Returns the coordinates of the first and the last bin center as
+    /// `(x_first, y_first, x_last, y_last)`.
+    ///
+    /// This is the `dimensions` argument of gnuplot's matrix plots.
+    pub fn matrix_dimensions(&self) -> (f64, f64, f64, f64) {
+        let first_and_last = |axis: &Histogram<T>| {
+            let half_width = axis.bin_width().to_f64() / 2.0;
+            let &(low, high) = axis.range();
+            (low.to_f64() + half_width, high.to_f64() - half_width)
+        };
+        let (x_first, x_last) = first_and_last(&self.x_axis);
+        let (y_first, y_last) = first_and_last(&self.y_axis);
+        (x_first, y_first, x_last, y_last)
+    }
+
+    /// Returns the content of the bin with the given X- and Y-index.
+    ///
+    /// # Panics
+    /// This panics if either index is out of range.
+    pub fn bin_content(&self, ix: usize, iy: usize) -> f64 {
+        self.weights[self.index(ix, iy)]
+    }
+
+    /// Returns the contents of all bins, row by row.
+    ///
+    /// Each row contains the bins of one Y-bin, in order of increasing
+    /// X. The rows are ordered by increasing Y.
+    pub fn bin_contents(&self) -> &[f64] {
+        self.weights.as_ref()
+    }
+
+    /// Returns the contents of all bins, one row per Y-bin.
+    pub fn rows(&self) -> ::std::slice::Chunks<'_, f64> {
+        self.weights.chunks(self.x_axis.num_bins().max(1))
+    }
+
+    /// Returns the sum of the squared weights in each bin, in the same
+    /// order as `bin_contents()`.
+    pub fn bin_sums_of_squares(&self) -> &[f64] {
+        self.squares.as_ref()
+    }
+
+    /// Returns the statistical error of each bin's content, in the same
+    /// order as `bin_contents()`.
+    pub fn bin_errors(&self) -> Vec<f64> {
+        self.squares.iter().map(|&square| square.sqrt()).collect()
+    }
+
+    /// Increases the bin located at `(x, y)` by one.
+    ///
+    /// If the point lies outside of the range of the histogram, this
+    /// method does nothing.
+    pub fn fill(&mut self, point: (T, T)) {
+        self.fill_weighted(point, 1.0)
+    }
+
+    /// Adds a single entry of weight `weight` to the bin located at
+    /// `(x, y)`.
+    ///
+    /// If the point lies outside of the range of the histogram, this
+    /// method does nothing.
+    pub fn fill_weighted(&mut self, point: (T, T), weight: f64) {
+        if let Some((ix, iy)) = self.find_bin(point) {
+            let i = self.index(ix, iy);
+            self.weights[i] += weight;
+            self.squares[i] += weight * weight;
+        }
+    }
+
+    /// Adds the bin contents of another histogram to `self`.
+    ///
+    /// # Panics
+    /// This panics if both histograms have different bins.
+    pub fn merge(&mut self, other: &Histogram2D<T>) {
+        assert!(
+            self.x_axis.edges == other.x_axis.edges && self.y_axis.edges == other.y_axis.edges,
+            "histograms with different bins"
+        );
+        for (weight, other) in self.weights.iter_mut().zip(other.weights.iter()) {
+            *weight += *other;
+        }
+        for (square, other) in self.squares.iter_mut().zip(other.squares.iter()) {
+            *square += *other;
+        }
+    }
+
+    /// Returns the X- and Y-index of the bin in which `(x, y)` lies.
+    ///
+    /// If the point lies outside of the range of this histogram, `None`
+    /// is returned.
+    pub fn find_bin(&self, (x, y): (T, T)) -> Option<(usize, usize)> {
+        let ix = self.x_axis.find_bin(x)?;
+        let iy = self.y_axis.find_bin(y)?;
+        Some((ix, iy))
+    }
+
+    /// Returns the projection onto the X-axis.
+    ///
+    /// Each bin of the result contains the sum over all Y-bins.
+    pub fn projection_x(&self) -> Histogram<T> {
+        let (nx, ny) = self.num_bins();
+        let &(low, high) = self.x_range();
+        let mut result = Histogram::new(nx, low, high);
+        for ix in 0..nx {
+            for iy in 0..ny {
+                let i = self.index(ix, iy);
+                result.weights[ix] += self.weights[i];
+                result.squares[ix] += self.squares[i];
             }
         }
-        unreachable!()
+        result
     }
+
+    /// Returns the projection onto the Y-axis.
+    ///
+    /// Each bin of the result contains the sum over all X-bins.
+    pub fn projection_y(&self) -> Histogram<T> {
+        let (nx, ny) = self.num_bins();
+        let &(low, high) = self.y_range();
+        let mut result = Histogram::new(ny, low, high);
+        for iy in 0..ny {
+            for ix in 0..nx {
+                let i = self.index(ix, iy);
+                result.weights[iy] += self.weights[i];
+                result.squares[iy] += self.squares[i];
+            }
+        }
+        result
+    }
+
+    /// Private method that returns the position of a bin in
+    /// `self.weights`.
+    fn index(&self, ix: usize, iy: usize) -> usize {
+        let (nx, ny) = self.num_bins();
+        assert!(ix < nx && iy < ny, "bin index out of range");
+        iy * nx + ix
+    }
+}
+
+impl<T: Float> Extend<(T, T)> for Histogram2D<T> {
+    /// Successively `fill`s all points of the iterator into `self`.
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (T, T)>,
+    {
+        for point in iter {
+            self.fill(point);
+        }
+    }
+}
+
+
+/// Private type of a row written by `Histogram::to_csv()`.
+#[derive(Serialize, Deserialize)]
+struct BinRecord {
+    low: f64,
+    high: f64,
+    content: f64,
+    sum_of_squares: f64,
 }
 
 
 /// Iterator over bin centers, returned by `Histogram::bin_centers()`.
-pub struct BinCenters<'a> {
-    low_edges: ::std::slice::Iter<'a, f64>,
-    bin_width: f64,
+pub struct BinCenters<'a, T: 'a = f64> {
+    bins: ::std::slice::Windows<'a, T>,
 }
 
-impl<'a> Iterator for BinCenters<'a> {
-    type Item = f64;
+impl<'a, T: Float> Iterator for BinCenters<'a, T> {
+    type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.low_edges
+        self.bins
             .next()
-            .map(|low_edge| low_edge + self.bin_width / 2.0)
+            .map(|bin| bin[0] + (bin[1] - bin[0]) / T::from_f64(2.0))
     }
 }
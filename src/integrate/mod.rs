@@ -0,0 +1,741 @@
+pub mod qmc;
+pub mod vegas;
+
+use std::fmt;
+use std::ops;
+
+use rand::Rng;
+use rand::distributions::{Sample, IndependentSample};
+
+use dimensioned::si::Second;
+
+use super::{time, IntoSampleIter, Stat, Statistics};
+
+
+/// Struct for Monte-Carlo integration of 1D real functions.
+///
+/// This struct is exposed to allow continuous inspection of
+/// the integration result and uncertainty via `Statistics`.
+///
+/// `X` may be a dimensioned quantity like `Joule<f64>`; the result then
+/// carries the unit of `Y` times `X`.
+///
+/// For the general use case, where you just just want the result
+/// of the integration, use the function `integrate()`.
+pub struct Integrate<F, X, Y = X>
+where
+    F: FnMut(X) -> Y,
+    X: Copy + ops::Add<Output = X> + ops::Sub<Output = X> + ops::Mul<f64, Output = X>,
+    Y: ops::Mul<X>,
+{
+    start: X,
+    width: X,
+    func: F,
+}
+
+impl<F, X, Y> Integrate<F, X, Y>
+where
+    F: FnMut(X) -> Y,
+    X: Copy + ops::Add<Output = X> + ops::Sub<Output = X> + ops::Mul<f64, Output = X>,
+    Y: ops::Mul<X>,
+{
+    /// Creates a new object that integrates `f` in the given `range`.
+    pub fn new(f: F, range: ops::Range<X>) -> Self {
+        Integrate {
+            func: f,
+            start: range.start,
+            width: range.end - range.start,
+        }
+    }
+}
+
+impl<F, X, Y> Sample<<Y as ops::Mul<X>>::Output> for Integrate<F, X, Y>
+where
+    F: FnMut(X) -> Y,
+    X: Copy + ops::Add<Output = X> + ops::Sub<Output = X> + ops::Mul<f64, Output = X>,
+    Y: ops::Mul<X>,
+{
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> <Y as ops::Mul<X>>::Output {
+        let x = self.start + self.width * rng.gen::<f64>();
+        (self.func)(x) * self.width
+    }
+}
+
+impl<F, X, Y> IndependentSample<<Y as ops::Mul<X>>::Output> for Integrate<F, X, Y>
+where
+    F: Fn(X) -> Y,
+    X: Copy + ops::Add<Output = X> + ops::Sub<Output = X> + ops::Mul<f64, Output = X>,
+    Y: ops::Mul<X>,
+{
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> <Y as ops::Mul<X>>::Output {
+        let x = self.start + self.width * rng.gen::<f64>();
+        (self.func)(x) * self.width
+    }
+}
+
+
+/// Integrates a function `f(x)` in a given `range`.
+///
+/// The argument `sample_size` specifies how many iterations should be
+/// done to get the integration result. `rng` is used as a source of
+/// randomness.
+///
+/// The returned `Statistics` object provides the integration result
+/// via its `mean()` method. The integration precision is given by the
+/// method `error_of_mean()`. If `x` is a dimensioned quantity, e.g. an
+/// energy, the result has the unit of `f(x)` times that of `x`.
+pub fn integrate<F, X, Y, R>(
+    f: F,
+    range: ops::Range<X>,
+    sample_size: usize,
+    rng: &mut R,
+) -> Statistics<Y::Output>
+where
+    F: FnMut(X) -> Y,
+    X: Copy + ops::Add<Output = X> + ops::Sub<Output = X> + ops::Mul<f64, Output = X>,
+    Y: ops::Mul<X>,
+    Y::Output: Stat,
+    R: Rng,
+{
+    Integrate::new(f, range)
+        .into_sample_iter(rng)
+        .take(sample_size)
+        .collect()
+}
+
+
+/// Integrates a function `f(x)` in a given `range` until a target
+/// precision is reached.
+///
+/// Samples are drawn in batches of 1000 until the relative error,
+/// `error_of_mean() / mean()`, drops below `rel_err`. As a safeguard
+/// against integrands whose mean is zero or whose variance is
+/// infinite, at most 10⁸ evaluations are done.
+///
+/// The returned `Statistics` object is used like that of
+/// `integrate()`. The number of evaluations is returned as well; if it
+/// equals the maximum, the target precision has not been reached.
+pub fn integrate_to_precision<F, X, Y, R>(
+    f: F,
+    range: ops::Range<X>,
+    rel_err: f64,
+    rng: &mut R,
+) -> (Statistics<Y::Output>, usize)
+where
+    F: FnMut(X) -> Y,
+    X: Copy + ops::Add<Output = X> + ops::Sub<Output = X> + ops::Mul<f64, Output = X>,
+    Y: ops::Mul<X>,
+    Y::Output: Stat<StdDev = Y::Output>
+        + PartialOrd
+        + ops::Mul<f64, Output = Y::Output>
+        + ops::Neg<Output = Y::Output>,
+    R: Rng,
+{
+    let mut samples = Integrate::new(f, range).into_sample_iter(rng);
+    let mut stats = Statistics::new();
+    let mut evaluations = 0;
+    while evaluations < MAX_EVALUATIONS {
+        stats.extend(samples.by_ref().take(PRECISION_BATCH));
+        evaluations += PRECISION_BATCH;
+        if let Some(error) = stats.error_of_mean() {
+            let tolerance = stats.mean() * rel_err;
+            if error <= tolerance || error <= -tolerance {
+                break;
+            }
+        }
+    }
+    (stats, evaluations)
+}
+
+
+/// The result of `integrate_region()`.
+#[derive(Debug, Clone, Default)]
+pub struct RegionIntegral {
+    stats: Statistics<f64>,
+    hits: usize,
+}
+
+impl RegionIntegral {
+    /// Returns the statistics of the individual samples.
+    ///
+    /// Each sample is either the volume of the bounding box (a hit) or
+    /// zero (a miss).
+    pub fn stats(&self) -> &Statistics<f64> {
+        &self.stats
+    }
+
+    /// Returns the estimated volume of the region.
+    ///
+    /// Without samples, this is zero.
+    pub fn value(&self) -> f64 {
+        if self.stats.count() > 0 {
+            self.stats.mean()
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the uncertainty of the estimated volume.
+    ///
+    /// With less than two samples, `None` is returned.
+    pub fn error(&self) -> Option<f64> {
+        self.stats.error_of_mean()
+    }
+
+    /// Returns the number of samples that hit the region.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Returns the fraction of samples that hit the region.
+    ///
+    /// This is the acceptance efficiency of rejection sampling from the
+    /// region with the same bounding box. Without samples, this is
+    /// zero.
+    pub fn efficiency(&self) -> f64 {
+        match self.stats.count() {
+            0 => 0.0,
+            count => self.hits as f64 / f64::from(count),
+        }
+    }
+}
+
+
+/// Estimates the volume of an implicitly defined region.
+///
+/// The region is given by an `indicator` function, which returns
+/// `true` for all points inside of it. It must lie completely within
+/// `bounding_box`, which gives the range of each coordinate. Points are
+/// sampled uniformly from the box `sample_size` times, and the volume
+/// is the fraction of hits times the volume of the box
+/// ("hit-or-miss" integration).
+///
+/// Because the efficiency is reported as well, this can be used to
+/// judge how well a bounding box fits a region before using it for
+/// rejection sampling.
+///
+/// # Panics
+/// This panics if `bounding_box` is empty.
+pub fn integrate_region<F, R>(
+    mut indicator: F,
+    bounding_box: &[ops::Range<f64>],
+    sample_size: usize,
+    rng: &mut R,
+) -> RegionIntegral
+where
+    F: FnMut(&[f64]) -> bool,
+    R: Rng,
+{
+    assert!(!bounding_box.is_empty(), "empty bounding box");
+    let volume: f64 = bounding_box.iter().map(|range| range.end - range.start).product();
+    let mut result = RegionIntegral::default();
+    let mut point = vec![0.0; bounding_box.len()];
+    for _ in 0..sample_size {
+        for (coordinate, range) in point.iter_mut().zip(bounding_box) {
+            *coordinate = rng.gen_range(range.start, range.end);
+        }
+        if indicator(&point) {
+            result.hits += 1;
+            result.stats.push(volume);
+        } else {
+            result.stats.push(0.0);
+        }
+    }
+    result
+}
+
+
+/// Integrates a function `f(x)` in a given `range` deterministically.
+///
+/// This uses Simpson's rule with `n` intervals, rounded up to the next
+/// even number. It is meant as a reference to check Monte-Carlo results
+/// of one-dimensional integrals against; the bounds on `X` and `Y` are
+/// the same as for `integrate()`. To integrate a tabulated `Function`,
+/// pass `|x| function.call(x)`.
+///
+/// # Panics
+/// This panics if `n` is zero.
+pub fn deterministic<F, X, Y>(mut f: F, range: ops::Range<X>, n: usize) -> <Y as ops::Mul<X>>::Output
+where
+    F: FnMut(X) -> Y,
+    X: Copy + ops::Add<Output = X> + ops::Sub<Output = X> + ops::Mul<f64, Output = X>,
+    Y: ops::Add<Output = Y> + ops::Mul<f64, Output = Y> + ops::Mul<X>,
+{
+    assert!(n > 0, "no intervals");
+    let n = n + n % 2;
+    let h = (range.end - range.start) * (1.0 / n as f64);
+    let mut sum = f(range.start) + f(range.end);
+    for i in 1..n {
+        let factor = if i % 2 == 1 { 4.0 } else { 2.0 };
+        sum = sum + f(range.start + h * i as f64) * factor;
+    }
+    sum * (1.0 / 3.0) * h
+}
+
+
+/// Integrates a function `f(x)` in a given `range` with the trapezoid
+/// rule.
+///
+/// Unlike Simpson's rule, this does not assume that `f` is smooth and
+/// thus is better suited for functions with kinks, such as linearly
+/// interpolated `Function`s. See `deterministic()` for details.
+///
+/// # Panics
+/// This panics if `n` is zero.
+pub fn trapezoid<F, X, Y>(mut f: F, range: ops::Range<X>, n: usize) -> <Y as ops::Mul<X>>::Output
+where
+    F: FnMut(X) -> Y,
+    X: Copy + ops::Add<Output = X> + ops::Sub<Output = X> + ops::Mul<f64, Output = X>,
+    Y: ops::Add<Output = Y> + ops::Mul<f64, Output = Y> + ops::Mul<X>,
+{
+    assert!(n > 0, "no intervals");
+    let h = (range.end - range.start) * (1.0 / n as f64);
+    let mut sum = (f(range.start) + f(range.end)) * 0.5;
+    for i in 1..n {
+        sum = sum + f(range.start + h * i as f64);
+    }
+    sum * h
+}
+
+
+/// Trait of distributions whose probability density is known.
+///
+/// This is needed for importance sampling, see `integrate_weighted()`.
+pub trait Pdf<X> {
+    /// The type of the probability density, e.g. `f64` or the
+    /// reciprocal of a dimensioned `X`.
+    type Density;
+
+    /// Evaluates the probability density at `x`.
+    fn pdf(&self, x: X) -> Self::Density;
+}
+
+
+/// Adapter that combines a distribution with its probability density.
+///
+/// This allows using any distribution of `rand` for importance
+/// sampling, e.g. `WithPdf::new(Exp::new(1.0), |x: f64| (-x).exp())`.
+pub struct WithPdf<S, P> {
+    sample: S,
+    pdf: P,
+}
+
+impl<S, P> WithPdf<S, P> {
+    /// Creates a new distribution from a sampler and a density.
+    ///
+    /// It is the caller's responsibility that `pdf` is the probability
+    /// density of `sample`.
+    pub fn new(sample: S, pdf: P) -> Self {
+        WithPdf { sample, pdf }
+    }
+}
+
+impl<S, P, X> Sample<X> for WithPdf<S, P>
+where
+    S: Sample<X>,
+{
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> X {
+        self.sample.sample(rng)
+    }
+}
+
+impl<S, P, X> IndependentSample<X> for WithPdf<S, P>
+where
+    S: IndependentSample<X>,
+{
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> X {
+        self.sample.ind_sample(rng)
+    }
+}
+
+impl<S, P, X, D> Pdf<X> for WithPdf<S, P>
+where
+    P: Fn(X) -> D,
+{
+    type Density = D;
+
+    fn pdf(&self, x: X) -> D {
+        (self.pdf)(x)
+    }
+}
+
+
+/// Struct for Monte-Carlo integration with importance sampling.
+///
+/// Instead of sampling `x` uniformly, this samples `x` from a
+/// distribution with density `p(x)` and yields `f(x)/p(x)`. The mean
+/// of these values is an unbiased estimate of the integral of `f` over
+/// the support of `p`. If `p` is similar in shape to `f`, the variance
+/// is much smaller than with uniform sampling.
+///
+/// For the general use case, use the function `integrate_weighted()`.
+pub struct ImportanceIntegrate<F, D, X> {
+    func: F,
+    dist: D,
+    _dummy: ::std::marker::PhantomData<X>,
+}
+
+impl<F, D, X> ImportanceIntegrate<F, D, X> {
+    /// Creates a new object that integrates `f` by sampling from
+    /// `dist`.
+    pub fn new(f: F, dist: D) -> Self {
+        ImportanceIntegrate {
+            func: f,
+            dist,
+            _dummy: Default::default(),
+        }
+    }
+}
+
+impl<F, D, X, Y> Sample<<Y as ops::Div<D::Density>>::Output> for ImportanceIntegrate<F, D, X>
+where
+    F: FnMut(X) -> Y,
+    D: Sample<X> + Pdf<X>,
+    X: Copy,
+    Y: ops::Div<D::Density>,
+{
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> <Y as ops::Div<D::Density>>::Output {
+        let x = self.dist.sample(rng);
+        (self.func)(x) / self.dist.pdf(x)
+    }
+}
+
+impl<F, D, X, Y> IndependentSample<<Y as ops::Div<D::Density>>::Output>
+    for ImportanceIntegrate<F, D, X>
+where
+    F: Fn(X) -> Y,
+    D: IndependentSample<X> + Pdf<X>,
+    X: Copy,
+    Y: ops::Div<D::Density>,
+{
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> <Y as ops::Div<D::Density>>::Output {
+        let x = self.dist.ind_sample(rng);
+        (self.func)(x) / self.dist.pdf(x)
+    }
+}
+
+
+/// Integrates a function `f(x)` using importance sampling.
+///
+/// `x` is drawn `sample_size` times from `pdf_sampler`, which must know
+/// its own probability density `p(x)`, and `f(x)/p(x)` is averaged.
+/// The integral is taken over the support of `p`; `p` must not vanish
+/// anywhere `f` does not. Use `WithPdf` to combine an arbitrary
+/// distribution with its density.
+///
+/// The returned `Statistics` object is used like that of
+/// `integrate()`.
+pub fn integrate_weighted<F, D, X, Y, R>(
+    f: F,
+    pdf_sampler: D,
+    sample_size: usize,
+    rng: &mut R,
+) -> Statistics<<Y as ops::Div<D::Density>>::Output>
+where
+    F: FnMut(X) -> Y,
+    D: Sample<X> + Pdf<X>,
+    X: Copy,
+    Y: ops::Div<D::Density>,
+    <Y as ops::Div<D::Density>>::Output: Stat,
+    R: Rng,
+{
+    ImportanceIntegrate::new(f, pdf_sampler)
+        .into_sample_iter(rng)
+        .take(sample_size)
+        .collect()
+}
+
+
+/// Calculates the expectation value `E[f(X)]` of a random variable `X`.
+///
+/// `x` is drawn `sample_size` times from `sampler` and `f(x)` is
+/// averaged. This is integration of `f` over the probability measure of
+/// `sampler`: no density needs to be known, and the integral is
+/// automatically normalized. With the identity as `f`, this gives the
+/// mean of a distribution.
+///
+/// The returned `Statistics` object is used like that of
+/// `integrate()`.
+pub fn expectation<F, D, X, Y, R>(
+    mut f: F,
+    sampler: D,
+    sample_size: usize,
+    rng: &mut R,
+) -> Statistics<Y>
+where
+    F: FnMut(X) -> Y,
+    D: IndependentSample<X>,
+    Y: Stat,
+    R: Rng,
+{
+    (0..sample_size)
+        .map(|_| f(sampler.ind_sample(rng)))
+        .collect()
+}
+
+
+/// How `integrate_stratified()` distributes samples among the strata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Allocation {
+    /// Each stratum gets the same number of samples.
+    Uniform,
+    /// Each stratum first gets `pilot` samples. The remaining samples
+    /// are distributed proportionally to the standard deviation
+    /// estimated in each stratum (Neyman allocation). This minimizes
+    /// the variance of the result.
+    Neyman { pilot: usize },
+}
+
+
+/// The result of `integrate_stratified()`.
+#[derive(Debug, Clone, Default)]
+pub struct Stratified {
+    strata: Vec<Statistics<f64>>,
+}
+
+impl Stratified {
+    /// Returns the statistics of each stratum.
+    ///
+    /// The mean of each stratum is its contribution to the integral.
+    pub fn strata(&self) -> &[Statistics<f64>] {
+        &self.strata
+    }
+
+    /// Returns the total number of function evaluations.
+    pub fn sample_size(&self) -> usize {
+        self.strata.iter().map(|stats| stats.count() as usize).sum()
+    }
+
+    /// Returns the integration result.
+    ///
+    /// This is the sum of the contributions of all strata. Strata
+    /// without samples contribute zero.
+    pub fn value(&self) -> f64 {
+        self.strata
+            .iter()
+            .filter(|stats| stats.count() > 0)
+            .map(Statistics::mean)
+            .sum()
+    }
+
+    /// Returns the uncertainty of the integration result.
+    ///
+    /// The variance of the result is the sum of the variances of the
+    /// means of all strata. If any stratum has less than two samples,
+    /// `None` is returned.
+    pub fn error(&self) -> Option<f64> {
+        let mut variance = 0.0;
+        for stats in &self.strata {
+            variance += stats.error_of_mean()?.powi(2);
+        }
+        Some(variance.sqrt())
+    }
+}
+
+
+/// Integrates a function `f(x)` in a given `range` using stratified
+/// sampling.
+///
+/// The range is split into `strata` intervals of equal width, and each
+/// interval is integrated separately by uniform sampling. The
+/// `sample_size` function evaluations are distributed among the strata
+/// according to `allocation`. Because the fluctuations between strata
+/// no longer contribute to the variance, the error for smooth
+/// integrands is much smaller than with `integrate()`.
+///
+/// # Panics
+/// This panics if `strata` is zero or if `sample_size` is too small to
+/// give each stratum at least two samples (or `pilot` samples for
+/// Neyman allocation).
+pub fn integrate_stratified<F, R>(
+    mut f: F,
+    range: ops::Range<f64>,
+    strata: usize,
+    sample_size: usize,
+    allocation: Allocation,
+    rng: &mut R,
+) -> Stratified
+where
+    F: FnMut(f64) -> f64,
+    R: Rng,
+{
+    assert!(strata > 0, "no strata");
+    let width = (range.end - range.start) / strata as f64;
+    let mut result = Stratified { strata: vec![Statistics::new(); strata] };
+    let mut fill = |stratum: usize, n: usize, rng: &mut R, result: &mut Stratified| {
+        let low = range.start + width * stratum as f64;
+        for _ in 0..n {
+            let x = rng.gen_range(low, low + width);
+            result.strata[stratum].push(f(x) * width);
+        }
+    };
+    match allocation {
+        Allocation::Uniform => {
+            let n = sample_size / strata;
+            assert!(n >= 2, "too few samples per stratum");
+            for stratum in 0..strata {
+                // Distribute the remainder among the first strata.
+                let extra = if stratum < sample_size % strata { 1 } else { 0 };
+                fill(stratum, n + extra, rng, &mut result);
+            }
+        },
+        Allocation::Neyman { pilot } => {
+            assert!(pilot >= 2, "pilot run too small");
+            assert!(sample_size >= pilot * strata, "too few samples for pilot run");
+            for stratum in 0..strata {
+                fill(stratum, pilot, rng, &mut result);
+            }
+            let deviations: Vec<f64> = result.strata
+                .iter()
+                .map(|stats| stats.standard_deviation().unwrap_or(0.0))
+                .collect();
+            let total: f64 = deviations.iter().sum();
+            let remaining = sample_size - pilot * strata;
+            for (stratum, deviation) in deviations.into_iter().enumerate() {
+                let share = if total > 0.0 {
+                    deviation / total
+                } else {
+                    1.0 / strata as f64
+                };
+                let n = (share * remaining as f64).round() as usize;
+                fill(stratum, n, rng, &mut result);
+            }
+        },
+    }
+    result
+}
+
+
+/// The result of an integration, including convergence diagnostics.
+///
+/// All integrators of this module can report their results in this
+/// form via `IntoIntegrationResult`, which allows comparing them on an
+/// equal footing. The easiest way to get one is `timed()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct IntegrationResult<X = f64> {
+    /// The estimate of the integral.
+    pub value: X,
+    /// The uncertainty of the estimate, if it is known.
+    pub error: Option<X>,
+    /// The number of function evaluations.
+    pub n_evals: usize,
+    /// The time spent on the integration.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    pub wall_time: Second<f64>,
+    /// For iterative integrators, `chi²` per degree of freedom of the
+    /// combination of all iterations.
+    pub chi2: Option<f64>,
+}
+
+impl<X> fmt::Display for IntegrationResult<X>
+where
+    X: fmt::Display,
+{
+    /// Displays the result, its uncertainty and the diagnostics on one
+    /// line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:.5}", self.value)?;
+        if let Some(ref error) = self.error {
+            write!(f, " ± {:.5}", error)?;
+        }
+        write!(
+            f,
+            " ({} evaluations, {:.3})",
+            self.n_evals,
+            self.wall_time
+        )?;
+        if let Some(chi2) = self.chi2 {
+            write!(f, " χ²/dof: {:.2}", chi2)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// Trait of everything that can be turned into an `IntegrationResult`.
+pub trait IntoIntegrationResult {
+    /// The type of the integration result.
+    type Value;
+
+    /// Creates the result, given the time spent on the integration.
+    fn into_result(self, wall_time: Second<f64>) -> IntegrationResult<Self::Value>;
+}
+
+impl<X> IntoIntegrationResult for Statistics<X>
+where
+    X: Stat<StdDev = X>,
+{
+    type Value = X;
+
+    /// # Panics
+    /// This panics if the sample is empty, as its mean is undefined.
+    fn into_result(self, wall_time: Second<f64>) -> IntegrationResult<X> {
+        IntegrationResult {
+            value: self.mean(),
+            error: self.error_of_mean(),
+            n_evals: self.count() as usize,
+            wall_time,
+            chi2: None,
+        }
+    }
+}
+
+impl IntoIntegrationResult for Stratified {
+    type Value = f64;
+
+    fn into_result(self, wall_time: Second<f64>) -> IntegrationResult<f64> {
+        IntegrationResult {
+            value: self.value(),
+            error: self.error(),
+            n_evals: self.sample_size(),
+            wall_time,
+            chi2: None,
+        }
+    }
+}
+
+impl IntoIntegrationResult for RegionIntegral {
+    type Value = f64;
+
+    fn into_result(self, wall_time: Second<f64>) -> IntegrationResult<f64> {
+        self.stats.into_result(wall_time)
+    }
+}
+
+impl<F> IntoIntegrationResult for &vegas::Vegas<F> {
+    type Value = f64;
+
+    fn into_result(self, wall_time: Second<f64>) -> IntegrationResult<f64> {
+        IntegrationResult {
+            value: self.value(),
+            error: if self.iterations().is_empty() { None } else { Some(self.error()) },
+            n_evals: self.iterations().iter().map(|iteration| iteration.n_calls).sum(),
+            wall_time,
+            chi2: self.chi2_per_dof(),
+        }
+    }
+}
+
+
+/// Runs an integration and returns its result with diagnostics.
+///
+/// The wall time of `func` is measured and combined with its return
+/// value, e.g.
+/// `timed(|| integrate(f, 0.0..1.0, 1000, &mut rng))`.
+pub fn timed<F, T>(func: F) -> IntegrationResult<T::Value>
+where
+    F: FnOnce() -> T,
+    T: IntoIntegrationResult,
+{
+    let (output, wall_time) = time::measure_with(func);
+    output.into_result(wall_time)
+}
+
+
+/// The number of samples between two checks of
+/// `integrate_to_precision()`.
+const PRECISION_BATCH: usize = 1000;
+
+/// The maximum number of evaluations of `integrate_to_precision()`.
+const MAX_EVALUATIONS: usize = 100_000_000;
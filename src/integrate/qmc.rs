@@ -0,0 +1,238 @@
+use std::ops;
+
+use rand::Rng;
+
+
+/// The Halton low-discrepancy sequence.
+///
+/// The `d`-th coordinate of the `n`-th point is the radical inverse of
+/// `n` in the `d`-th prime base. The sequence is easy to compute for
+/// any number of dimensions, but its uniformity degrades noticeably
+/// above about ten dimensions. In that case, prefer `Sobol`.
+///
+/// The iterator yields points in the unit hypercube. The first point
+/// is that of index one, so that the origin is never returned.
+#[derive(Debug, Clone)]
+pub struct Halton {
+    bases: Vec<u64>,
+    index: u64,
+}
+
+impl Halton {
+    /// Creates a new sequence of points with `dims` dimensions.
+    ///
+    /// # Panics
+    /// This panics if `dims` is zero.
+    pub fn new(dims: usize) -> Self {
+        assert!(dims > 0, "no dimensions");
+        let mut bases = Vec::with_capacity(dims);
+        let mut candidate = 2;
+        while bases.len() < dims {
+            if bases.iter().take_while(|&&p| p * p <= candidate).all(|&p| candidate % p != 0) {
+                bases.push(candidate);
+            }
+            candidate += 1;
+        }
+        Halton { bases, index: 0 }
+    }
+
+    /// Returns the number of dimensions.
+    pub fn dims(&self) -> usize {
+        self.bases.len()
+    }
+}
+
+impl Iterator for Halton {
+    type Item = Vec<f64>;
+
+    fn next(&mut self) -> Option<Vec<f64>> {
+        self.index += 1;
+        let index = self.index;
+        let point = self.bases
+            .iter()
+            .map(|&base| {
+                let mut n = index;
+                let mut result = 0.0;
+                let mut scale = 1.0;
+                while n > 0 {
+                    scale /= base as f64;
+                    result += (n % base) as f64 * scale;
+                    n /= base;
+                }
+                result
+            })
+            .collect();
+        Some(point)
+    }
+}
+
+
+/// The Sobol low-discrepancy sequence.
+///
+/// This uses the direction numbers of Joe and Kuo (2008) and generates
+/// the points in Gray-code order. It supports up to `Sobol::MAX_DIMS`
+/// dimensions and `2³² - 1` points. The uniformity of the sequence is
+/// best if the number of points used is a power of two.
+///
+/// The iterator yields points in the unit hypercube. The first point
+/// is that of index one, so that the origin is never returned.
+#[derive(Debug, Clone)]
+pub struct Sobol {
+    /// For each dimension, the 32 direction numbers.
+    directions: Vec<[u32; 32]>,
+    state: Vec<u32>,
+    index: u32,
+}
+
+impl Sobol {
+    /// The maximum number of dimensions supported.
+    pub const MAX_DIMS: usize = 16;
+
+    /// Creates a new sequence of points with `dims` dimensions.
+    ///
+    /// # Panics
+    /// This panics if `dims` is zero or greater than `MAX_DIMS`.
+    pub fn new(dims: usize) -> Self {
+        assert!(dims > 0, "no dimensions");
+        assert!(dims <= Self::MAX_DIMS, "too many dimensions for Sobol sequence");
+        let mut directions = Vec::with_capacity(dims);
+        // The first dimension is the van der Corput sequence in base 2.
+        let mut first = [0; 32];
+        for (k, v) in first.iter_mut().enumerate() {
+            *v = 1 << (31 - k);
+        }
+        directions.push(first);
+        for &(a, initial) in &SOBOL_PARAMETERS[..dims - 1] {
+            let s = initial.len();
+            let mut v = [0u32; 32];
+            for k in 0..32 {
+                v[k] = if k < s {
+                    initial[k] << (31 - k)
+                } else {
+                    let mut value = v[k - s] ^ (v[k - s] >> s);
+                    for i in 1..s {
+                        if (a >> (s - 1 - i)) & 1 == 1 {
+                            value ^= v[k - i];
+                        }
+                    }
+                    value
+                };
+            }
+            directions.push(v);
+        }
+        Sobol {
+            directions,
+            state: vec![0; dims],
+            index: 0,
+        }
+    }
+
+    /// Creates a new sequence with a random digital shift.
+    ///
+    /// Each coordinate of each point is XOR-ed with a random number
+    /// that is fixed per dimension. This keeps the low discrepancy of
+    /// the sequence, but makes the integration result a random
+    /// variable. Repeating the integration with several randomized
+    /// sequences thus gives an estimate of its error.
+    ///
+    /// # Panics
+    /// This panics under the same conditions as `new()`.
+    pub fn randomized<R: Rng>(dims: usize, rng: &mut R) -> Self {
+        let mut sobol = Self::new(dims);
+        for state in &mut sobol.state {
+            *state = rng.gen();
+        }
+        sobol
+    }
+
+    /// Returns the number of dimensions.
+    pub fn dims(&self) -> usize {
+        self.state.len()
+    }
+}
+
+impl Iterator for Sobol {
+    type Item = Vec<f64>;
+
+    fn next(&mut self) -> Option<Vec<f64>> {
+        if self.index == u32::MAX {
+            return None;
+        }
+        // The point with the next index differs from the current one in
+        // the direction number of the lowest zero bit of the index.
+        let bit = (!self.index).trailing_zeros() as usize;
+        self.index += 1;
+        let point = self.state
+            .iter_mut()
+            .zip(&self.directions)
+            .map(|(state, v)| {
+                *state ^= v[bit];
+                f64::from(*state) / 4_294_967_296.0
+            })
+            .collect();
+        Some(point)
+    }
+}
+
+
+/// Integrates a multi-dimensional function `f(x)` using quasi-Monte
+/// Carlo.
+///
+/// The first `sample_size` points of `sequence`, e.g. `Sobol` or
+/// `Halton`, are scaled to the given `ranges` and the mean of `f` over
+/// them is multiplied by the volume. For smooth integrands, the error
+/// decreases almost like `1/N` instead of `1/√N` as for `integrate()`.
+///
+/// Because the points are not random, there is no error estimate. To
+/// get one, integrate several times with `Sobol::randomized()` and
+/// collect the results in a `Statistics` object.
+///
+/// # Panics
+/// This panics if `sequence` yields points whose dimension differs
+/// from the number of `ranges`, or if it runs out of points.
+pub fn integrate_qmc<F, S>(
+    mut f: F,
+    ranges: &[ops::Range<f64>],
+    sample_size: usize,
+    sequence: S,
+) -> f64
+where
+    F: FnMut(&[f64]) -> f64,
+    S: IntoIterator<Item = Vec<f64>>,
+{
+    let volume: f64 = ranges.iter().map(|range| range.end - range.start).product();
+    let mut points = sequence.into_iter();
+    let mut sum = 0.0;
+    for _ in 0..sample_size {
+        let mut x = points.next().expect("sequence exhausted");
+        assert_eq!(x.len(), ranges.len(), "dimension mismatch");
+        for (coordinate, range) in x.iter_mut().zip(ranges) {
+            *coordinate = range.start + *coordinate * (range.end - range.start);
+        }
+        sum += f(&x);
+    }
+    sum / sample_size as f64 * volume
+}
+
+
+/// The parameters of the Sobol sequence for dimensions 2 and higher:
+/// the coefficients `a` of the primitive polynomial and the initial
+/// direction numbers `m`. The degree of the polynomial is the number of
+/// direction numbers.
+const SOBOL_PARAMETERS: [(u32, &[u32]); 15] = [
+    (0, &[1]),
+    (1, &[1, 3]),
+    (1, &[1, 3, 1]),
+    (2, &[1, 1, 1]),
+    (1, &[1, 1, 3, 3]),
+    (4, &[1, 3, 5, 13]),
+    (2, &[1, 1, 5, 5, 17]),
+    (4, &[1, 1, 5, 5, 5]),
+    (7, &[1, 1, 7, 11, 19]),
+    (11, &[1, 1, 5, 1, 1]),
+    (13, &[1, 1, 1, 3, 11]),
+    (14, &[1, 3, 5, 5, 31]),
+    (1, &[1, 3, 3, 9, 7, 49]),
+    (13, &[1, 1, 1, 15, 21, 21]),
+    (16, &[1, 3, 1, 13, 27, 49]),
+];
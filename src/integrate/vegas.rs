@@ -0,0 +1,279 @@
+use std::ops;
+
+use rand::Rng;
+
+
+/// The result of a single iteration of `Vegas`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VegasIteration {
+    /// The estimate of the integral.
+    pub value: f64,
+    /// The uncertainty of the estimate.
+    pub error: f64,
+    /// The number of function evaluations.
+    pub n_calls: usize,
+}
+
+
+/// Adaptive Monte-Carlo integrator of multi-dimensional functions
+/// using the VEGAS algorithm.
+///
+/// VEGAS samples points from a separable importance grid: each axis is
+/// divided into bins of varying width, and each bin is chosen with
+/// equal probability. After each iteration, the bins are resized so
+/// that they become narrow where the integrand is large. Thus, the
+/// sampling density gradually approaches the shape of the integrand.
+///
+/// The estimates of all iterations are combined as a weighted mean.
+/// The `chi2_per_dof()` of this combination should be close to one;
+/// much larger values indicate that the grid has not converged yet
+/// and early iterations should be discarded with `clear_results()`.
+///
+/// # Example
+/// ```ignore
+/// let mut vegas = Vegas::new(|x: &[f64]| (-x[0] * x[1]).exp(), vec![0.0..1.0, 0.0..1.0]);
+/// vegas.integrate(5, 10_000, &mut rng); // Warm-up.
+/// vegas.clear_results();
+/// vegas.integrate(10, 100_000, &mut rng);
+/// println!("{} ± {}", vegas.value(), vegas.error());
+/// ```
+pub struct Vegas<F> {
+    func: F,
+    ranges: Vec<ops::Range<f64>>,
+    /// For each axis, the bin edges in the unit interval.
+    grid: Vec<Vec<f64>>,
+    alpha: f64,
+    iterations: Vec<VegasIteration>,
+}
+
+impl<F> Vegas<F>
+where
+    F: FnMut(&[f64]) -> f64,
+{
+    /// Creates a new integrator of `f` over the given ranges.
+    ///
+    /// The number of ranges is the dimension of the integral. The grid
+    /// starts out uniform with 50 bins per axis.
+    ///
+    /// # Panics
+    /// This panics if `ranges` is empty.
+    pub fn new(f: F, ranges: Vec<ops::Range<f64>>) -> Self {
+        assert!(!ranges.is_empty(), "no integration ranges");
+        let mut vegas = Vegas {
+            func: f,
+            grid: Vec::new(),
+            ranges,
+            alpha: DEFAULT_ALPHA,
+            iterations: Vec::new(),
+        };
+        vegas.set_bins(DEFAULT_BINS);
+        vegas
+    }
+
+    /// Resets the grid to `nbins` uniform bins per axis.
+    ///
+    /// # Panics
+    /// This panics if `nbins` is zero.
+    pub fn set_bins(&mut self, nbins: usize) {
+        assert!(nbins > 0, "no bins");
+        let uniform: Vec<f64> = (0..nbins + 1).map(|i| i as f64 / nbins as f64).collect();
+        self.grid = vec![uniform; self.ranges.len()];
+    }
+
+    /// Sets the damping parameter of the grid refinement.
+    ///
+    /// Larger values adapt the grid faster, but may make it unstable.
+    /// The default is 1.5; zero disables the refinement.
+    pub fn set_alpha(&mut self, alpha: f64) {
+        self.alpha = alpha;
+    }
+
+    /// Performs one iteration with `n_calls` function evaluations.
+    ///
+    /// The result is stored and afterwards, the grid is refined.
+    ///
+    /// # Panics
+    /// This panics if `n_calls` is less than two.
+    pub fn iterate<R: Rng>(&mut self, n_calls: usize, rng: &mut R) -> VegasIteration {
+        assert!(n_calls >= 2, "too few calls");
+        let ndim = self.ranges.len();
+        let nbins = self.grid[0].len() - 1;
+        let mut x = vec![0.0; ndim];
+        let mut bins = vec![0; ndim];
+        // For each axis and bin, the sum of squared weighted values.
+        let mut importance = vec![vec![0.0; nbins]; ndim];
+        let mut sum = 0.0;
+        let mut sum_of_squares = 0.0;
+        for _ in 0..n_calls {
+            let mut jacobian = 1.0;
+            let axes = self.grid.iter().zip(&self.ranges);
+            for ((coordinate, bin), (edges, range)) in x.iter_mut().zip(bins.iter_mut()).zip(axes) {
+                let position = rng.gen::<f64>() * nbins as f64;
+                *bin = (position as usize).min(nbins - 1);
+                let width = edges[*bin + 1] - edges[*bin];
+                let y = edges[*bin] + (position - *bin as f64) * width;
+                *coordinate = range.start + y * (range.end - range.start);
+                jacobian *= width * nbins as f64 * (range.end - range.start);
+            }
+            let value = (self.func)(&x) * jacobian;
+            sum += value;
+            sum_of_squares += value * value;
+            for (axis, &bin) in importance.iter_mut().zip(&bins) {
+                axis[bin] += value * value;
+            }
+        }
+        let n = n_calls as f64;
+        let mean = sum / n;
+        let variance = ((sum_of_squares / n - mean * mean) / (n - 1.0)).max(0.0);
+        let iteration = VegasIteration {
+            value: mean,
+            error: variance.sqrt(),
+            n_calls,
+        };
+        self.iterations.push(iteration);
+        if self.alpha > 0.0 {
+            for (edges, importance) in self.grid.iter_mut().zip(importance) {
+                refine(edges, importance, self.alpha);
+            }
+        }
+        iteration
+    }
+
+    /// Performs `n_iterations` iterations with `n_calls` function
+    /// evaluations each.
+    ///
+    /// Returns the combined estimate over all iterations so far.
+    pub fn integrate<R: Rng>(&mut self, n_iterations: usize, n_calls: usize, rng: &mut R) -> f64 {
+        for _ in 0..n_iterations {
+            self.iterate(n_calls, rng);
+        }
+        self.value()
+    }
+
+    /// Forgets the results of all previous iterations, but keeps the
+    /// refined grid.
+    pub fn clear_results(&mut self) {
+        self.iterations.clear();
+    }
+}
+
+impl<F> Vegas<F> {
+    /// Returns the results of all iterations since the last call to
+    /// `clear_results()`.
+    pub fn iterations(&self) -> &[VegasIteration] {
+        &self.iterations
+    }
+
+    /// Returns the combined estimate of the integral.
+    ///
+    /// This is the mean of all iterations, weighted by their inverse
+    /// variance. Without any iterations, this is zero.
+    pub fn value(&self) -> f64 {
+        let (sum, weights) = self.weighted_sums();
+        if weights > 0.0 { sum / weights } else { 0.0 }
+    }
+
+    /// Returns the uncertainty of the combined estimate.
+    pub fn error(&self) -> f64 {
+        let (_, weights) = self.weighted_sums();
+        if weights > 0.0 { weights.recip().sqrt() } else { 0.0 }
+    }
+
+    /// Returns `chi²` per degree of freedom of the combined estimate.
+    ///
+    /// This tests whether the iterations are consistent with each
+    /// other. With less than two iterations, `None` is returned.
+    pub fn chi2_per_dof(&self) -> Option<f64> {
+        if self.iterations.len() < 2 {
+            return None;
+        }
+        let value = self.value();
+        let chi2: f64 = self.iterations
+            .iter()
+            .filter(|iteration| iteration.error > 0.0)
+            .map(|iteration| ((iteration.value - value) / iteration.error).powi(2))
+            .sum();
+        Some(chi2 / (self.iterations.len() - 1) as f64)
+    }
+
+    /// Private method that returns the sum of `value/error²` and the
+    /// sum of `1/error²` over all iterations.
+    ///
+    /// Iterations with zero error (e.g. of a constant integrand) get
+    /// a very large weight instead of an infinite one.
+    fn weighted_sums(&self) -> (f64, f64) {
+        self.iterations.iter().fold((0.0, 0.0), |(sum, weights), iteration| {
+            let floor = (f64::EPSILON * iteration.value).powi(2);
+            let floor = floor.max(f64::MIN_POSITIVE);
+            let weight = 1.0 / iteration.error.powi(2).max(floor);
+            (sum + weight * iteration.value, weights + weight)
+        })
+    }
+}
+
+
+/// Private function that refines the bin edges of one axis.
+///
+/// `importance` contains the sum of squared function values in each
+/// bin. It is smoothed, compressed with the damping parameter `alpha`,
+/// and then the edges are moved so that each new bin contains the same
+/// amount of importance.
+fn refine(edges: &mut Vec<f64>, importance: Vec<f64>, alpha: f64) {
+    let nbins = importance.len();
+    if nbins < 2 {
+        return;
+    }
+    // Smooth each bin with its neighbors.
+    let mut smoothed = vec![0.0; nbins];
+    smoothed[0] = (importance[0] + importance[1]) / 2.0;
+    smoothed[nbins - 1] = (importance[nbins - 2] + importance[nbins - 1]) / 2.0;
+    for (value, window) in smoothed[1..nbins - 1].iter_mut().zip(importance.windows(3)) {
+        *value = window.iter().sum::<f64>() / 3.0;
+    }
+    let total: f64 = smoothed.iter().sum();
+    if total <= 0.0 {
+        return;
+    }
+    let weights: Vec<f64> = smoothed
+        .into_iter()
+        .map(|d| {
+            let r = d / total;
+            if r <= 0.0 || r >= 1.0 {
+                r
+            } else {
+                ((r - 1.0) / r.ln()).powf(alpha)
+            }
+        })
+        .collect();
+    let per_bin = weights.iter().sum::<f64>() / nbins as f64;
+    if per_bin <= 0.0 {
+        return;
+    }
+    let mut new_edges = Vec::with_capacity(nbins + 1);
+    new_edges.push(0.0);
+    let mut accumulated = 0.0;
+    let mut old_bin = 0;
+    for _ in 1..nbins {
+        let target = per_bin * new_edges.len() as f64;
+        while accumulated + weights[old_bin] < target && old_bin < nbins - 1 {
+            accumulated += weights[old_bin];
+            old_bin += 1;
+        }
+        let fraction = if weights[old_bin] > 0.0 {
+            ((target - accumulated) / weights[old_bin]).min(1.0)
+        } else {
+            0.0
+        };
+        let (low, high) = (edges[old_bin], edges[old_bin + 1]);
+        new_edges.push(low + fraction * (high - low));
+    }
+    new_edges.push(1.0);
+    *edges = new_edges;
+}
+
+
+/// The default number of bins per axis.
+const DEFAULT_BINS: usize = 50;
+
+/// The default damping parameter of the grid refinement.
+const DEFAULT_ALPHA: f64 = 1.5;
@@ -1,22 +1,78 @@
 extern crate rand;
 extern crate csv;
+extern crate toml;
 extern crate serde;
+extern crate serde_json;
+extern crate gnuplot;
+#[macro_use]
+extern crate serde_derive;
 extern crate dimensioned;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(unix)]
+extern crate libc;
+#[cfg(feature = "log")]
+#[macro_use]
+extern crate log;
+#[cfg(feature = "python")]
+extern crate pyo3;
+// The pyo3 macros refer to `::core`, which 2015 crates must declare.
+#[cfg(feature = "python")]
+extern crate core;
+
+// This must come first so that its macros are visible in all modules.
+#[macro_use]
+pub mod logging;
 
 pub mod mc;
+pub mod rng;
 pub mod time;
+pub mod error;
 pub mod sample;
+pub mod element;
+pub mod formats;
 pub mod function;
 pub mod contains;
+pub mod constants;
 pub mod integrate;
 pub mod histogram;
+pub mod benchmarks;
 pub mod statistics;
+pub mod convergence;
 pub mod crosssection;
+pub mod fluorescence;
+
+mod serde_name;
+#[cfg(feature = "serde")]
+mod serde_si;
+// The code generated by `#[pymethods]` trips this lint.
+#[cfg(feature = "python")]
+#[allow(clippy::useless_conversion)]
+pub mod python;
 
+pub use rng::{SplitRng, StreamRng};
+pub use error::Error;
 pub use contains::Contains;
 pub use function::Function;
-pub use histogram::Histogram;
-pub use integrate::{integrate, Integrate};
-pub use sample::{IntoSampleIter, SampleIter};
+pub use element::ElementData;
+pub use histogram::{Float, Histogram, Histogram2D};
+pub use fluorescence::FluorescenceData;
+pub use integrate::{expectation, integrate, integrate_region, integrate_stratified,
+                    integrate_to_precision, integrate_weighted, Integrate};
+pub use integrate::{timed, IntegrationResult, IntoIntegrationResult};
+pub use integrate::vegas::Vegas;
+pub use integrate::qmc::{integrate_qmc, Halton, Sobol};
+pub use sample::{DiscreteSampler, IntoSampleIter, Metropolis, Rejection, SampleIter,
+                 WeightedSampleIter};
+pub use sample::ziggurat::Ziggurat;
+pub use sample::adapters::{Map, Mixture, Truncate};
+pub use sample::empirical::EmpiricalDistribution;
+pub use sample::piecewise::{PiecewiseConstant, PiecewiseLinear};
+#[cfg(feature = "rayon")]
+pub use sample::par_sample_iter;
 pub use statistics::{Stat, Statistics, print_stats_and_time};
-pub use crosssection::{CoherentCrossSection, IncoherentCrossSection, RejectionSampler};
+pub use crosssection::{CdfSampler, CoherentCrossSection, ComptonProfile, EnvelopeSampler,
+                       FormFactorFit, IncoherentCrossSection, KahnSampler,
+                       KleinNishinaCrossSection, MaxCache, PairProductionCrossSection,
+                       RayleighSampler, RejectionSampler, SamplerBank,
+                       ThomsonCrossSection};
@@ -0,0 +1,165 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+
+/// Private macro that logs a message at the info level.
+///
+/// If the `log` feature is disabled, this compiles to nothing, but the
+/// arguments are still type-checked.
+#[cfg(feature = "log")]
+macro_rules! log_info {
+    ($($arg:tt)*) => { info!($($arg)*) };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! log_info {
+    ($($arg:tt)*) => { if false { let _ = format_args!($($arg)*); } };
+}
+
+
+/// Private macro that logs a message at the debug level.
+///
+/// See `log_info!` for details.
+#[cfg(feature = "log")]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { debug!($($arg)*) };
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { if false { let _ = format_args!($($arg)*); } };
+}
+
+
+/// Counter of trials and successes of a random process.
+///
+/// This is used to monitor the acceptance rates of rejection samplers
+/// and the outcomes of variance-reduction techniques. The counters are
+/// global and shared by all threads. If the `log` feature is disabled,
+/// nothing is counted, so that the instrumentation costs nothing.
+pub struct AcceptanceCounter {
+    name: &'static str,
+    trials: AtomicUsize,
+    accepted: AtomicUsize,
+}
+
+impl AcceptanceCounter {
+    /// Creates a new counter with the given name.
+    pub const fn new(name: &'static str) -> Self {
+        AcceptanceCounter {
+            name,
+            trials: AtomicUsize::new(0),
+            accepted: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the name of the counter.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Counts one trial and returns whether it was accepted.
+    ///
+    /// This is meant to wrap the acceptance condition of a rejection
+    /// loop, e.g. `if COUNTER.count(u < f(x)) { return x; }`.
+    #[inline]
+    pub fn count(&self, accepted: bool) -> bool {
+        if cfg!(feature = "log") {
+            self.trials.fetch_add(1, Ordering::Relaxed);
+            if accepted {
+                self.accepted.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        accepted
+    }
+
+    /// Returns the number of trials counted so far.
+    pub fn trials(&self) -> usize {
+        self.trials.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of accepted trials counted so far.
+    pub fn accepted(&self) -> usize {
+        self.accepted.load(Ordering::Relaxed)
+    }
+
+    /// Returns the fraction of accepted trials.
+    ///
+    /// If no trial has been counted, this returns `None`.
+    pub fn rate(&self) -> Option<f64> {
+        match self.trials() {
+            0 => None,
+            trials => Some(self.accepted() as f64 / trials as f64),
+        }
+    }
+
+    /// Sets both counts back to zero.
+    pub fn reset(&self) {
+        self.trials.store(0, Ordering::Relaxed);
+        self.accepted.store(0, Ordering::Relaxed);
+    }
+}
+
+
+/// Logs the statistics of all rejection samplers and variance-reduction
+/// techniques and resets them.
+///
+/// The runners in `mc` call this at the end of every run. Counters
+/// without any trials are skipped. This does nothing if the `log`
+/// feature is disabled.
+pub fn log_statistics() {
+    for counter in COUNTERS {
+        if let Some(rate) = counter.rate() {
+            log_info!(
+                "{}: {} of {} accepted ({:.2}%)",
+                counter.name(),
+                counter.accepted(),
+                counter.trials(),
+                100.0 * rate
+            );
+        }
+        counter.reset();
+    }
+}
+
+
+/// Counter of Kahn's method for the Klein–Nishina cross-section.
+pub static KLEIN_NISHINA: AcceptanceCounter = AcceptanceCounter::new("Klein-Nishina sampler");
+
+/// Counter of the Doppler-broadened Compton scattering.
+pub static DOPPLER: AcceptanceCounter = AcceptanceCounter::new("Doppler broadening");
+
+/// Counter of `EnvelopeSampler`.
+pub static ENVELOPE: AcceptanceCounter = AcceptanceCounter::new("envelope sampler");
+
+/// Counter of `RayleighSampler`.
+pub static RAYLEIGH: AcceptanceCounter = AcceptanceCounter::new("Rayleigh sampler");
+
+/// Counter of `KahnSampler`.
+pub static KAHN: AcceptanceCounter = AcceptanceCounter::new("incoherent scattering sampler");
+
+/// Counter of the generic `Rejection` sampler.
+pub static REJECTION: AcceptanceCounter = AcceptanceCounter::new("rejection sampler");
+
+/// Counter of the energy-transfer samplers of electrons.
+pub static ELECTRON: AcceptanceCounter = AcceptanceCounter::new("electron energy sampler");
+
+/// Counter of Russian roulette; accepted trials are survivors.
+pub static ROULETTE: AcceptanceCounter = AcceptanceCounter::new("Russian roulette");
+
+/// Counter of particle splitting; each trial is a particle entering a
+/// more important cell, which is accepted if it is actually split.
+pub static SPLITTING: AcceptanceCounter = AcceptanceCounter::new("splitting");
+
+
+/// All counters reported by `log_statistics()`.
+static COUNTERS: &[&AcceptanceCounter] = &[
+    &KLEIN_NISHINA,
+    &DOPPLER,
+    &ENVELOPE,
+    &RAYLEIGH,
+    &KAHN,
+    &REJECTION,
+    &ELECTRON,
+    &ROULETTE,
+    &SPLITTING,
+];
@@ -0,0 +1,122 @@
+use rand::Rng;
+
+use dimensioned::si::*;
+
+use statistics::Statistics;
+use super::experiment::{Experiment, simulate_history_with};
+
+
+/// The result of `compute_buildup()` for a single absorber thickness.
+///
+/// All transmissions are given as the fraction of source photons that
+/// reach the detector.
+#[derive(Debug, Clone)]
+pub struct BuildupPoint {
+    thickness: Meter<f64>,
+    narrow_beam: Statistics<f64>,
+    broad_beam: Statistics<f64>,
+}
+
+impl BuildupPoint {
+    /// Returns the absorber thickness of this point.
+    pub fn thickness(&self) -> Meter<f64> {
+        self.thickness
+    }
+
+    /// Returns the narrow-beam transmission.
+    ///
+    /// In the narrow-beam geometry, only *uncollided* photons are
+    /// counted, i.e. photons that reach the detector without having
+    /// been scattered. Plotted against the thickness, this gives the
+    /// attenuation curve.
+    pub fn narrow_beam(&self) -> &Statistics<f64> {
+        &self.narrow_beam
+    }
+
+    /// Returns the broad-beam transmission.
+    ///
+    /// In the broad-beam geometry, all photons that reach the detector
+    /// are counted, including those that have been scattered.
+    pub fn broad_beam(&self) -> &Statistics<f64> {
+        &self.broad_beam
+    }
+
+    /// Returns the buildup factor and its uncertainty.
+    ///
+    /// The buildup factor is the ratio of broad-beam and narrow-beam
+    /// transmission. The uncertainty is calculated by adding the
+    /// relative errors of both transmissions in quadrature. Because
+    /// both are positively correlated, this slightly overestimates the
+    /// uncertainty.
+    ///
+    /// If no uncollided photon has been detected or there is too
+    /// little data to calculate an uncertainty, `None` is returned.
+    pub fn buildup_factor(&self) -> Option<(f64, f64)> {
+        let narrow_error = self.narrow_beam.error_of_mean()?;
+        let broad_error = self.broad_beam.error_of_mean()?;
+        let narrow = self.narrow_beam.mean();
+        let broad = self.broad_beam.mean();
+        if narrow == 0.0 {
+            return None;
+        }
+        let narrow_error = narrow_error / narrow;
+        let broad_error = broad_error / broad;
+        let factor = broad / narrow;
+        let error = factor * (narrow_error * narrow_error + broad_error * broad_error).sqrt();
+        Some((factor, error))
+    }
+}
+
+
+/// Calculates attenuation curves and buildup factors.
+///
+/// For each of the given `thicknesses`, this calls `make_experiment`
+/// to create an experiment with an absorber of this thickness. Each
+/// experiment is simulated in `n_batches` batches of
+/// `histories_per_batch` histories each. The narrow-beam and the
+/// broad-beam transmission are measured in the same run by counting
+/// uncollided and all detected photons respectively.
+///
+/// Histories that fail with a `TransportError` count as lost.
+///
+/// To get the relative attenuation `I/I_0`, include a thickness of
+/// zero and divide by its narrow-beam transmission.
+pub fn compute_buildup<E, F, R>(
+    mut make_experiment: F,
+    thicknesses: &[Meter<f64>],
+    n_batches: usize,
+    histories_per_batch: usize,
+    rng: &mut R,
+) -> Vec<BuildupPoint>
+where
+    E: Experiment,
+    F: FnMut(Meter<f64>) -> E,
+    R: Rng,
+{
+    let mut points = Vec::with_capacity(thicknesses.len());
+    for &thickness in thicknesses {
+        let exp = make_experiment(thickness);
+        let mut narrow_beam = Statistics::new();
+        let mut broad_beam = Statistics::new();
+        for _ in 0..n_batches {
+            let mut uncollided = 0.0;
+            let mut detected = 0.0;
+            for _ in 0..histories_per_batch {
+                let _ = simulate_history_with(&exp, rng, &mut (), |photon| {
+                    detected += photon.weight();
+                    if photon.num_scatters() == 0 {
+                        uncollided += photon.weight();
+                    }
+                });
+            }
+            narrow_beam.push(uncollided / histories_per_batch as f64);
+            broad_beam.push(detected / histories_per_batch as f64);
+        }
+        points.push(BuildupPoint {
+            thickness,
+            narrow_beam,
+            broad_beam,
+        });
+    }
+    points
+}
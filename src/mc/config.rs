@@ -0,0 +1,534 @@
+use std::io;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use toml;
+use serde_json;
+
+use rand::Rng;
+use rand::distributions::IndependentSample;
+
+use dimensioned::si::*;
+use dimensioned::{Dimensionless, Recip};
+use dimensioned::f64prefixes::*;
+
+use error;
+use element;
+use contains::Contains;
+use function::Function;
+use fluorescence::FluorescenceData;
+use crosssection::{CoherentCrossSection, IncoherentCrossSection, MaxCache};
+use super::tracks::Outline;
+use super::geometry::Point;
+use super::particle::Photon;
+use super::source::{Source, SimpleSource, EastPointingSource};
+use super::experiment::{Experiment, Material, FreePath, Event, Cutoff};
+
+
+/// Declarative description of an experiment.
+///
+/// This type can be read from TOML or JSON files. All lengths are
+/// given in centimeters, all energies in kiloelectronvolts. A minimal
+/// TOML description looks like this:
+///
+/// ```toml
+/// x_start = 0.5
+///
+/// [source]
+/// kind = "east"
+/// position = [0.0, 0.0]
+/// energy = 661.7
+///
+/// [absorber]
+/// mean_free_paths = "data/MFWL.dat"
+/// form_factor = "data/AFF.dat"
+/// scattering_function = "data/ISF.dat"
+///
+/// [[regions]]
+/// material = "detector"
+/// x = [11.5, 12.5]
+/// y = [-2.0, 2.0]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    /// The photon source of the experiment.
+    pub source: SourceConfig,
+    /// The X-coordinate at which the experiment begins.
+    pub x_start: f64,
+    /// The regions that make up the setup.
+    ///
+    /// If regions overlap, the first one takes precedence.
+    pub regions: Vec<RegionConfig>,
+    /// The material outside of all regions.
+    #[serde(default = "default_background", with = "::serde_name")]
+    pub background: Material,
+    /// The fixed step length of photons in air.
+    #[serde(default = "default_air_step")]
+    pub air_step: f64,
+    /// The data files describing the absorber material.
+    pub absorber: AbsorberConfig,
+    /// Photons below this energy are discarded.
+    #[serde(default)]
+    pub cutoff: Option<f64>,
+}
+
+/// Declarative description of a photon source.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SourceConfig {
+    /// The kind of the source.
+    pub kind: SourceKind,
+    /// The location of the source.
+    pub position: (f64, f64),
+    /// The energy of the emitted photons.
+    pub energy: f64,
+}
+
+/// The kinds of sources that can be described in a `Config`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    /// An isotropic point source, see `SimpleSource`.
+    Isotropic,
+    /// A point source emitting to the right, see `EastPointingSource`.
+    East,
+}
+
+/// Declarative description of a rectangular region of the setup.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegionConfig {
+    /// The material that fills this region.
+    #[serde(with = "::serde_name")]
+    pub material: Material,
+    /// The lower and upper X-coordinate of the region.
+    pub x: (f64, f64),
+    /// The lower and upper Y-coordinate of the region.
+    pub y: (f64, f64),
+}
+
+/// Paths to the data files describing the absorber material.
+///
+/// The files have the same format as those in the `data` directory.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AbsorberConfig {
+    /// Total and partial mean free paths, see `data/MFWL.dat`.
+    pub mean_free_paths: PathBuf,
+    /// The atomic form factor, see `data/AFF.dat`.
+    pub form_factor: PathBuf,
+    /// The incoherent scattering function, see `data/ISF.dat`.
+    pub scattering_function: PathBuf,
+    /// The Compton profile, see `ComptonProfile::new()`.
+    ///
+    /// If given, the energy of incoherently scattered photons is
+    /// Doppler-broadened.
+    #[serde(default)]
+    pub compton_profile: Option<PathBuf>,
+    /// Whether to sample angle and energy of incoherently scattered
+    /// photons jointly, see `IncoherentCrossSection::gen_scatter()`.
+    ///
+    /// This only has an effect if a Compton profile is given.
+    #[serde(default)]
+    pub joint_compton_sampling: bool,
+    /// The symbol of the element whose built-in K-shell data is used
+    /// to emit fluorescence X-rays, see `FluorescenceData`.
+    ///
+    /// If not given, the photo-effect is treated as pure absorption.
+    #[serde(default)]
+    pub fluorescence: Option<String>,
+}
+
+fn default_background() -> Material {
+    Material::Air
+}
+
+fn default_air_step() -> f64 {
+    0.1
+}
+
+impl Config {
+    /// Reads a configuration from a file.
+    ///
+    /// Files ending in `.json` are parsed as JSON, all others as TOML.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be read or parsed.
+    pub fn from_file<P>(path: P) -> Result<Self, ConfigError>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        let is_json = path.extension().is_some_and(|ext| ext == "json");
+        if is_json {
+            Ok(serde_json::from_str(&contents)?)
+        } else {
+            Ok(toml::from_str(&contents)?)
+        }
+    }
+
+    /// Sets a numeric parameter given by its dotted path.
+    ///
+    /// The path follows the structure of the configuration file, with
+    /// indices for regions and coordinate pairs, e.g. `source.energy`,
+    /// `source.position.1`, `cutoff` or `regions.0.x.1`. This is used
+    /// to drive parameter scans, see `scan()`.
+    ///
+    /// # Errors
+    /// This fails if the path does not name a numeric parameter.
+    pub fn set_parameter(&mut self, path: &str, value: f64) -> Result<(), error::Error> {
+        let parts = path.split('.').collect::<Vec<_>>();
+        let field = match parts.as_slice() {
+            ["x_start"] => &mut self.x_start,
+            ["air_step"] => &mut self.air_step,
+            ["cutoff"] => {
+                self.cutoff = Some(value);
+                return Ok(());
+            },
+            ["source", "energy"] => &mut self.source.energy,
+            ["source", "position", index] => pair_element(&mut self.source.position, index)?,
+            ["regions", region, axis, index] => {
+                let region = region
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|region| self.regions.get_mut(region))
+                    .ok_or_else(|| error::Error::domain(format!("no such region: {}", path)))?;
+                match *axis {
+                    "x" => pair_element(&mut region.x, index)?,
+                    "y" => pair_element(&mut region.y, index)?,
+                    _ => return Err(error::Error::domain(format!("no such axis: {}", path))),
+                }
+            },
+            _ => return Err(error::Error::domain(format!("no such parameter: {}", path))),
+        };
+        *field = value;
+        Ok(())
+    }
+
+    /// Returns the paths of all data files that the configuration
+    /// refers to.
+    ///
+    /// This is used to record their checksums, see `RunManifest`.
+    pub fn data_files(&self) -> Vec<&Path> {
+        let absorber = &self.absorber;
+        let mut files = vec![
+            absorber.mean_free_paths.as_path(),
+            absorber.form_factor.as_path(),
+            absorber.scattering_function.as_path(),
+        ];
+        files.extend(absorber.compton_profile.as_deref());
+        files
+    }
+}
+
+
+/// Private function that returns the element of a coordinate pair
+/// named by `index`.
+fn pair_element<'a>(pair: &'a mut (f64, f64), index: &str) -> Result<&'a mut f64, error::Error> {
+    match index {
+        "0" => Ok(&mut pair.0),
+        "1" => Ok(&mut pair.1),
+        _ => Err(error::Error::domain(format!("index out of range: {}", index))),
+    }
+}
+
+
+/// The error type returned when loading a `ConfigExperiment`.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The configuration file could not be read.
+    Io(io::Error),
+    /// The configuration file is not valid TOML.
+    Toml(toml::de::Error),
+    /// The configuration file is not valid JSON.
+    Json(serde_json::Error),
+    /// A data file could not be read or is invalid.
+    Data(error::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Io(ref err) => write!(f, "could not read configuration: {}", err),
+            ConfigError::Toml(ref err) => write!(f, "invalid TOML: {}", err),
+            ConfigError::Json(ref err) => write!(f, "invalid JSON: {}", err),
+            ConfigError::Data(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(err: toml::de::Error) -> Self {
+        ConfigError::Toml(err)
+    }
+}
+
+impl From<serde_json::Error> for ConfigError {
+    fn from(err: serde_json::Error) -> Self {
+        ConfigError::Json(err)
+    }
+}
+
+impl From<error::Error> for ConfigError {
+    fn from(err: error::Error) -> Self {
+        ConfigError::Data(err)
+    }
+}
+
+
+/// The photon source of a `ConfigExperiment`.
+pub enum ConfigSource {
+    Isotropic(SimpleSource),
+    East(EastPointingSource),
+}
+
+impl Source for ConfigSource {
+    fn emit_photon<R: Rng>(&self, rng: &mut R) -> Photon {
+        match *self {
+            ConfigSource::Isotropic(ref source) => source.emit_photon(rng),
+            ConfigSource::East(ref source) => source.emit_photon(rng),
+        }
+    }
+}
+
+
+/// Private type that describes a region in SI units.
+struct Region {
+    material: Material,
+    x: (Meter<f64>, Meter<f64>),
+    y: (Meter<f64>, Meter<f64>),
+}
+
+
+/// An experiment that is described entirely by a `Config`.
+///
+/// The experiment consists of rectangular regions of air, absorber and
+/// detector material. Photons travel through air in fixed steps and
+/// are absorbed by the detector immediately. The absorber is described
+/// by tabulated mean free paths, an atomic form factor and an
+/// incoherent scattering function.
+pub struct ConfigExperiment {
+    source: ConfigSource,
+    x_start: Meter<f64>,
+    regions: Vec<Region>,
+    background: Material,
+    air_step: Meter<f64>,
+    cutoff: Option<Joule<f64>>,
+    coherent_xsection: CoherentCrossSection,
+    incoherent_xsection: IncoherentCrossSection,
+    coherent_max: MaxCache,
+    incoherent_max: MaxCache,
+    joint_compton_sampling: bool,
+    fluorescence: Option<FluorescenceData>,
+    mfp_tot: Function<Joule<f64>, Meter<f64>>,
+    mfp_coh: Function<Joule<f64>, Meter<f64>>,
+    mfp_inc: Function<Joule<f64>, Meter<f64>>,
+    mfp_pho: Function<Joule<f64>, Meter<f64>>,
+}
+
+impl ConfigExperiment {
+    /// Reads a configuration file and creates the experiment from it.
+    ///
+    /// # Errors
+    /// This fails if the configuration or any data file cannot be
+    /// read.
+    pub fn from_file<P>(path: P) -> Result<Self, ConfigError>
+    where
+        P: AsRef<Path>,
+    {
+        Self::new(&Config::from_file(path)?)
+    }
+
+    /// Creates the experiment described by `config`.
+    ///
+    /// # Errors
+    /// This fails if any data file cannot be read.
+    pub fn new(config: &Config) -> Result<Self, ConfigError> {
+        log_info!("setting up experiment: {:?}", config);
+        let cm = CENTI * M;
+        let kev = KILO * EV;
+
+        let location = Point::new(config.source.position.0 * cm, config.source.position.1 * cm);
+        let energy = config.source.energy * kev;
+        let source = match config.source.kind {
+            SourceKind::Isotropic => ConfigSource::Isotropic(SimpleSource::new(location, energy)),
+            SourceKind::East => ConfigSource::East(EastPointingSource::new(location, energy)),
+        };
+        let regions = config
+            .regions
+            .iter()
+            .map(|region| {
+                Region {
+                    material: region.material,
+                    x: (region.x.0 * cm, region.x.1 * cm),
+                    y: (region.y.0 * cm, region.y.1 * cm),
+                }
+            })
+            .collect();
+
+        let absorber = &config.absorber;
+        let mut mean_free_paths = Function::<f64>::multiple_from_file(&absorber.mean_free_paths)?
+            .into_iter()
+            .map(|mfp| mfp.scale(kev, cm));
+        let mut next_mfp = || {
+            mean_free_paths
+                .next()
+                .ok_or_else(|| error::Error::domain("too few mean free paths"))
+        };
+        let mut incoherent_xsection = IncoherentCrossSection::new(&absorber.scattering_function)?;
+        if let Some(ref compton_profile) = absorber.compton_profile {
+            incoherent_xsection = incoherent_xsection.with_compton_profile(compton_profile)?;
+        }
+        let fluorescence = match absorber.fluorescence {
+            Some(ref symbol) => {
+                let data = element::atomic_number(symbol).and_then(FluorescenceData::for_element);
+                let data = data.ok_or_else(|| {
+                    error::Error::domain("no fluorescence data for absorber")
+                })?;
+                Some(data)
+            },
+            None => None,
+        };
+        Ok(ConfigExperiment {
+            source,
+            x_start: config.x_start * cm,
+            regions,
+            background: config.background,
+            air_step: config.air_step * cm,
+            cutoff: config.cutoff.map(|cutoff| cutoff * kev),
+            coherent_xsection: CoherentCrossSection::new(&absorber.form_factor)?,
+            incoherent_xsection,
+            coherent_max: MaxCache::new(100, 1.0 * kev, 1.001 * energy),
+            incoherent_max: MaxCache::new(100, 1.0 * kev, 1.001 * energy),
+            joint_compton_sampling: absorber.joint_compton_sampling,
+            fluorescence,
+            mfp_tot: next_mfp()?,
+            mfp_coh: next_mfp()?,
+            mfp_inc: next_mfp()?,
+            mfp_pho: next_mfp()?,
+        })
+    }
+
+    /// Randomly chooses an absorber process, weighted by its
+    /// macroscopic cross-section.
+    fn choose_absorber_process<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Event {
+        let w_coherent = *(self.mfp_coh.call(energy).recip() * M).value();
+        let w_incoherent = *(self.mfp_inc.call(energy).recip() * M).value();
+        let w_photo = *(self.mfp_pho.call(energy).recip() * M).value();
+        let choice = rng.gen_range(0.0, w_coherent + w_incoherent + w_photo);
+        if choice < w_coherent {
+            Event::CoherentScatter
+        } else if choice < w_coherent + w_incoherent {
+            Event::IncoherentScatter
+        } else if self.fluorescence.is_some() {
+            Event::Fluorescence
+        } else {
+            Event::Absorbed
+        }
+    }
+
+    /// Returns the outlines of all regions of the setup.
+    ///
+    /// This is meant to be drawn together with recorded tracks, see
+    /// `write_tracks()`.
+    pub fn outline(&self) -> Vec<Outline> {
+        self.regions
+            .iter()
+            .map(|region| Outline::rectangle(region.material, region.x, region.y))
+            .collect()
+    }
+}
+
+impl Experiment for ConfigExperiment {
+    type Source = ConfigSource;
+
+    fn source(&self) -> &Self::Source {
+        &self.source
+    }
+
+    fn x_start(&self) -> Meter<f64> {
+        self.x_start
+    }
+
+    fn get_material(&self, location: &Point) -> Material {
+        let (x, y) = location.to_tuple();
+        self.regions
+            .iter()
+            .find(|region| region.x.contains(x) && region.y.contains(y))
+            .map_or(self.background, |region| region.material)
+    }
+
+    fn get_mean_free_path(&self, material: Material, energy: Joule<f64>) -> FreePath<f64> {
+        match material {
+            Material::Detector => FreePath::Fix(0.0 * M),
+            Material::Air => FreePath::Fix(self.air_step),
+            Material::Absorber => FreePath::Exp(self.mfp_tot.call(energy)),
+        }
+    }
+
+    fn energy_cutoff(&self) -> Option<Cutoff<f64>> {
+        self.cutoff.map(Cutoff::Discard)
+    }
+
+    fn gen_event<R: Rng>(&self, material: Material, energy: Joule<f64>, rng: &mut R) -> Event {
+        match material {
+            Material::Detector => Event::Absorbed,
+            Material::Air => Event::Nothing,
+            Material::Absorber => self.choose_absorber_process(energy, rng),
+        }
+    }
+
+    fn gen_coherent_scatter<R: Rng>(
+        &self,
+        _: Material,
+        energy: Joule<f64>,
+        rng: &mut R,
+    ) -> Unitless<f64> {
+        let sampler = self.coherent_max.sampler(&self.coherent_xsection, energy);
+        let mu = sampler.ind_sample(rng);
+        let mut angle = mu.value().acos();
+        if rng.gen::<bool>() {
+            angle *= -1.0;
+        }
+        Unitless::new(angle)
+    }
+
+    fn gen_incoherent_scatter<R: Rng>(
+        &self,
+        _: Material,
+        energy: Joule<f64>,
+        rng: &mut R,
+    ) -> (Unitless<f64>, Joule<f64>) {
+        let (mu, new_energy) = if self.joint_compton_sampling {
+            self.incoherent_xsection.gen_scatter(energy, rng)
+        } else {
+            let sampler = self.incoherent_max.sampler(&self.incoherent_xsection, energy);
+            let mu = sampler.ind_sample(rng);
+            (mu, self.incoherent_xsection.gen_scattered_energy(energy, mu, rng))
+        };
+        let mut angle = mu.value().acos();
+        if rng.gen::<bool>() {
+            angle *= -1.0;
+        }
+        (Unitless::new(angle), new_energy)
+    }
+
+    fn gen_fluorescence<R: Rng>(
+        &self,
+        _: Material,
+        energy: Joule<f64>,
+        rng: &mut R,
+    ) -> Option<Joule<f64>> {
+        self.fluorescence.as_ref().and_then(|data| data.gen_xray(energy, rng))
+    }
+}
@@ -0,0 +1,292 @@
+use rand::Rng;
+use rand::distributions::{Exp, IndependentSample};
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+
+use histogram::Histogram;
+
+
+/// A photon registered by the detector at a certain time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedEvent {
+    /// The time at which the photon has been registered.
+    pub time: Second<f64>,
+    /// The energy deposited by the photon.
+    pub energy: Joule<f64>,
+}
+
+impl TimedEvent {
+    /// Creates a new event.
+    pub fn new(time: Second<f64>, energy: Joule<f64>) -> Self {
+        TimedEvent { time, energy }
+    }
+}
+
+
+/// Assigns random arrival times to a sequence of detected energies.
+///
+/// The simulation itself does not know about the activity of the
+/// source. This function assumes that photons arrive at the detector
+/// as a Poisson process with the given mean `rate`, starting at time
+/// zero. The returned events are sorted by time.
+pub fn poisson_arrivals<I, R>(energies: I, rate: Hertz<f64>, rng: &mut R) -> Vec<TimedEvent>
+where
+    I: IntoIterator<Item = Joule<f64>>,
+    R: Rng,
+{
+    let distribution = Exp::new(*(rate * S).value());
+    let mut time = 0.0 * S;
+    energies
+        .into_iter()
+        .map(|energy| {
+            time += distribution.ind_sample(rng) * S;
+            TimedEvent::new(time, energy)
+        })
+        .collect()
+}
+
+
+/// The two standard models of detector dead time.
+///
+/// After registering an event, a detector is unable to register
+/// further events for a certain time `tau`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeadTime {
+    /// Events during the dead time are lost without any effect.
+    NonParalyzable(Second<f64>),
+    /// Events during the dead time are lost and extend the dead time.
+    Paralyzable(Second<f64>),
+}
+
+impl DeadTime {
+    /// Returns the length of the dead time.
+    pub fn tau(&self) -> Second<f64> {
+        match *self {
+            DeadTime::NonParalyzable(tau) | DeadTime::Paralyzable(tau) => tau,
+        }
+    }
+
+    /// Removes all events that are lost due to dead time.
+    ///
+    /// `events` must be sorted by time.
+    pub fn apply(&self, events: &[TimedEvent]) -> Vec<TimedEvent> {
+        let tau = self.tau();
+        let mut result = Vec::with_capacity(events.len());
+        let mut dead_until = None;
+        for event in events {
+            let is_dead = dead_until.is_some_and(|until| event.time < until);
+            if !is_dead {
+                result.push(*event);
+                dead_until = Some(event.time + tau);
+            } else if let DeadTime::Paralyzable(_) = *self {
+                dead_until = Some(event.time + tau);
+            }
+        }
+        result
+    }
+
+    /// Returns the count rate measured at a given true count rate.
+    ///
+    /// This is `n / (1 + n tau)` for the non-paralyzable and
+    /// `n exp(-n tau)` for the paralyzable model.
+    pub fn measured_rate(&self, true_rate: Hertz<f64>) -> Hertz<f64> {
+        let n_tau = *(true_rate * self.tau()).value();
+        match *self {
+            DeadTime::NonParalyzable(_) => true_rate / (1.0 + n_tau),
+            DeadTime::Paralyzable(_) => true_rate * (-n_tau).exp(),
+        }
+    }
+
+    /// Returns the true count rate for a given measured count rate.
+    ///
+    /// For the paralyzable model, two true rates lead to the same
+    /// measured rate; this returns the lower one. If the measured rate
+    /// is negative, NaN, or higher than the maximum measurable rate of
+    /// the model, `None` is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate mcgen;
+    /// extern crate dimensioned;
+    ///
+    /// use mcgen::mc::DeadTime;
+    /// use dimensioned::si::*;
+    ///
+    /// let dead_time = DeadTime::Paralyzable(1e-6 * S);
+    /// for &true_rate in &[0.0 * HZ, 2e5 * HZ, 1e6 * HZ] {
+    ///     let measured_rate = dead_time.measured_rate(true_rate);
+    ///     let estimate = dead_time.true_rate(measured_rate).unwrap();
+    ///     assert!(((estimate - true_rate) / HZ).abs() < 1.0);
+    /// }
+    /// assert_eq!(dead_time.true_rate(4e5 * HZ), None);
+    /// ```
+    pub fn true_rate(&self, measured_rate: Hertz<f64>) -> Option<Hertz<f64>> {
+        let m_tau = *(measured_rate * self.tau()).value();
+        if m_tau.is_nan() || m_tau < 0.0 {
+            return None;
+        } else if m_tau == 0.0 {
+            return Some(measured_rate);
+        }
+        match *self {
+            DeadTime::NonParalyzable(_) => {
+                if m_tau < 1.0 {
+                    Some(measured_rate / (1.0 - m_tau))
+                } else {
+                    None
+                }
+            },
+            DeadTime::Paralyzable(_) => {
+                // The maximum measured rate is 1/(e tau) at n = 1/tau.
+                if m_tau > (-1.0f64).exp() {
+                    return None;
+                }
+                let x = solve_lower_branch(m_tau);
+                Some(measured_rate * (x / m_tau))
+            },
+        }
+    }
+}
+
+
+/// Private function that solves `x exp(-x) = y` for `0 <= x <= 1`.
+///
+/// `y` must lie between zero and `1/e`. Because the left-hand side
+/// rises monotonically on this interval, the root is bracketed by its
+/// ends. This uses Newton's method, but falls back to bisection
+/// whenever a step would leave the bracket, e.g. near `x = 1`, where
+/// the derivative vanishes.
+fn solve_lower_branch(y: f64) -> f64 {
+    let (mut low, mut high) = (0.0, 1.0);
+    let mut x = y;
+    for _ in 0..100 {
+        let f = x * (-x).exp() - y;
+        if f < 0.0 {
+            low = x;
+        } else {
+            high = x;
+        }
+        let df = (1.0 - x) * (-x).exp();
+        let newton = x - f / df;
+        let next = if newton > low && newton < high {
+            newton
+        } else {
+            0.5 * (low + high)
+        };
+        if (next - x).abs() < 1e-12 {
+            return next;
+        }
+        x = next;
+    }
+    x
+}
+
+
+/// Merges events that are closer in time than `resolving_time`.
+///
+/// If two photons arrive at the detector within the resolving time of
+/// the pulse-processing electronics, their pulses pile up and they are
+/// registered as a single event with the sum of their energies. The
+/// merged event keeps the time of its first photon.
+///
+/// `events` must be sorted by time.
+pub fn pile_up(events: &[TimedEvent], resolving_time: Second<f64>) -> Vec<TimedEvent> {
+    let mut result: Vec<TimedEvent> = Vec::with_capacity(events.len());
+    for event in events {
+        if let Some(last) = result.last_mut() {
+            if event.time - last.time < resolving_time {
+                last.energy += event.energy;
+                continue;
+            }
+        }
+        result.push(*event);
+    }
+    result
+}
+
+
+/// Post-processing of detected events by the detector electronics.
+///
+/// This applies pile-up first and dead time afterwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PulseProcessor {
+    dead_time: DeadTime,
+    resolving_time: Second<f64>,
+}
+
+impl PulseProcessor {
+    /// Creates a new processor with the given dead-time model and
+    /// pile-up resolving time.
+    pub fn new(dead_time: DeadTime, resolving_time: Second<f64>) -> Self {
+        PulseProcessor {
+            dead_time,
+            resolving_time,
+        }
+    }
+
+    /// Processes the events of a measurement of the given duration.
+    ///
+    /// `events` must be sorted by time.
+    pub fn process(&self, events: &[TimedEvent], measurement_time: Second<f64>) -> ProcessedEvents {
+        let piled_up = pile_up(events, self.resolving_time);
+        ProcessedEvents {
+            events: self.dead_time.apply(&piled_up),
+            input_count: events.len(),
+            measurement_time,
+        }
+    }
+}
+
+
+/// The result of `PulseProcessor::process()`.
+#[derive(Debug, Clone)]
+pub struct ProcessedEvents {
+    events: Vec<TimedEvent>,
+    input_count: usize,
+    measurement_time: Second<f64>,
+}
+
+impl ProcessedEvents {
+    /// Returns the events that have been registered.
+    pub fn events(&self) -> &[TimedEvent] {
+        &self.events
+    }
+
+    /// Returns the number of photons that arrived at the detector.
+    pub fn input_count(&self) -> usize {
+        self.input_count
+    }
+
+    /// Returns the fraction of arrived photons that have been
+    /// registered.
+    ///
+    /// If no photon has arrived, this returns `None`.
+    pub fn throughput(&self) -> Option<f64> {
+        if self.input_count > 0 {
+            Some(self.events.len() as f64 / self.input_count as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the true count rate of photons arriving at the detector.
+    pub fn input_rate(&self) -> Hertz<f64> {
+        self.input_count as f64 / self.measurement_time
+    }
+
+    /// Returns the measured count rate.
+    pub fn count_rate(&self) -> Hertz<f64> {
+        self.events.len() as f64 / self.measurement_time
+    }
+
+    /// Fills the energies of all registered events into a histogram.
+    ///
+    /// The histogram's X-axis is measured in multiples of `unit`, e.g.
+    /// `KILO * EV`.
+    pub fn fill(&self, histogram: &mut Histogram, unit: Joule<f64>) {
+        for event in &self.events {
+            histogram.fill(*(event.energy / unit).value());
+        }
+    }
+}
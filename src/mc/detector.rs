@@ -0,0 +1,91 @@
+use rand::Rng;
+use rand::distributions::{Normal, IndependentSample};
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+
+use function::Function;
+
+
+/// The ratio between the FWHM and the standard deviation of a normal
+/// distribution, `2 * sqrt(2 * ln(2))`.
+const FWHM_PER_SIGMA: f64 = 2.3548200450309493;
+
+
+/// The common trait of all detector response models.
+///
+/// A real detector does not measure the energy deposited in it
+/// exactly. A response model turns the deposited energy into the
+/// measured one. It should be applied before filling the energy into
+/// a histogram.
+pub trait DetectorResponse {
+    /// Returns the energy measured for a given deposited energy.
+    ///
+    /// This uses `rng` as a source of randomness.
+    fn measure<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Joule<f64>;
+}
+
+
+/// A detector with perfect energy resolution.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IdealResponse;
+
+impl DetectorResponse for IdealResponse {
+    /// Returns the deposited energy unchanged.
+    fn measure<R: Rng>(&self, energy: Joule<f64>, _: &mut R) -> Joule<f64> {
+        energy
+    }
+}
+
+
+/// A detector with Gaussian energy resolution.
+///
+/// The measured energy follows a normal distribution around the
+/// deposited energy. The full width at half maximum (FWHM) of this
+/// distribution depends on the deposited energy and is given by a
+/// `Function`. Typical values are about 7% at 662 keV for NaI and
+/// about 0.2% for HPGe detectors.
+#[derive(Debug, Clone)]
+pub struct GaussianResponse {
+    fwhm: Function<Joule<f64>, Joule<f64>>,
+}
+
+impl GaussianResponse {
+    /// Creates a new response model with the given FWHM curve.
+    pub fn new(fwhm: Function<Joule<f64>, Joule<f64>>) -> Self {
+        GaussianResponse { fwhm }
+    }
+
+    /// Returns the FWHM at the given deposited energy.
+    ///
+    /// # Panics
+    /// This panics if `energy` lies outside of the domain of the FWHM
+    /// curve.
+    pub fn fwhm(&self, energy: Joule<f64>) -> Joule<f64> {
+        self.fwhm.call(energy)
+    }
+}
+
+impl DetectorResponse for GaussianResponse {
+    /// Smears the deposited energy with a normal distribution.
+    ///
+    /// The distribution is truncated at zero: negative energies are
+    /// rejected and sampled anew. Since the distribution is centered
+    /// on the non-negative deposited energy, at most half of all
+    /// samples are rejected.
+    ///
+    /// # Panics
+    /// This panics if `energy` lies outside of the domain of the FWHM
+    /// curve.
+    fn measure<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Joule<f64> {
+        let sigma = self.fwhm(energy) / FWHM_PER_SIGMA / J;
+        let distribution = Normal::new(0.0, *sigma.value());
+        loop {
+            let measured = energy + distribution.ind_sample(rng) * J;
+            // Also return NaN to avoid an endless loop.
+            if measured >= 0.0 * J || (measured / J).value().is_nan() {
+                return measured;
+            }
+        }
+    }
+}
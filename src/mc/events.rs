@@ -0,0 +1,313 @@
+use std::io::{self, Write};
+use std::fs::File;
+use std::path::Path;
+
+use csv;
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+use dimensioned::f64prefixes::*;
+
+use error::Error;
+use super::tally::Tally;
+use super::geometry::Point;
+use super::particle::Photon;
+use super::experiment::{Material, Event};
+
+
+/// The record of a single detected photon.
+///
+/// This is written by `EventWriter` and read by `read_events()`. So
+/// that the records can be analyzed by other programs, all quantities
+/// are plain numbers in the units given by the field names.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DetectedEvent {
+    /// The zero-based number of the history in which the photon was
+    /// detected.
+    pub history: u64,
+    /// The energy of the photon.
+    pub energy_kev: f64,
+    /// The X-coordinate at which the photon was detected.
+    pub x_cm: f64,
+    /// The Y-coordinate at which the photon was detected.
+    pub y_cm: f64,
+    /// The Z-coordinate at which the photon was detected.
+    pub z_cm: f64,
+    /// The X-component of the photon's direction.
+    pub dx: f64,
+    /// The Y-component of the photon's direction.
+    pub dy: f64,
+    /// The Z-component of the photon's direction.
+    pub dz: f64,
+    /// The time of flight since the photon was emitted.
+    pub time_ns: f64,
+    /// The statistical weight of the photon.
+    pub weight: f64,
+    /// The number of times the photon has been scattered.
+    pub scatters: u32,
+}
+
+impl DetectedEvent {
+    /// Creates the record of `photon`, detected in the given history.
+    pub fn new(history: u64, photon: &Photon) -> Self {
+        let cm = CENTI * M;
+        let (x, y, z) = photon.location().to_triple();
+        let direction = photon.direction();
+        DetectedEvent {
+            history,
+            energy_kev: *(photon.energy() / (KILO * EV)).value(),
+            x_cm: *(x / cm).value(),
+            y_cm: *(y / cm).value(),
+            z_cm: *(z / cm).value(),
+            dx: *direction.dx().value(),
+            dy: *direction.dy().value(),
+            dz: *direction.dz().value(),
+            time_ns: *(photon.time() / (NANO * S)).value(),
+            weight: photon.weight(),
+            scatters: photon.num_scatters(),
+        }
+    }
+}
+
+
+/// Tally that writes a `DetectedEvent` for every detected photon.
+///
+/// The events are written as CSV with a header line. This allows
+/// re-binning the results or running custom analyses without
+/// rerunning the simulation. Because `Tally` methods cannot fail, the
+/// first write error is kept and returned by `finish()`; no further
+/// events are written after it.
+pub struct EventWriter<W: Write> {
+    writer: csv::Writer<W>,
+    /// The number of the current history plus one.
+    histories: u64,
+    written: u64,
+    error: Option<Error>,
+}
+
+impl<W: Write> EventWriter<W> {
+    /// Creates a writer that writes events to `writer`.
+    pub fn new(writer: W) -> Self {
+        EventWriter {
+            writer: csv::Writer::from_writer(writer),
+            histories: 0,
+            written: 0,
+            error: None,
+        }
+    }
+
+    /// Returns the number of events written so far.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Flushes all events and returns the underlying writer.
+    ///
+    /// # Errors
+    /// This fails if any event could not be written.
+    pub fn finish(mut self) -> Result<W, Error> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.writer
+            .into_inner()
+            .map_err(|err| Error::Io(io::Error::other(err.to_string())))
+    }
+}
+
+impl EventWriter<File> {
+    /// Creates a writer that writes events to the file at `path`.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be created.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(EventWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> Tally for EventWriter<W> {
+    fn begin_history(&mut self) {
+        self.histories += 1;
+    }
+
+    fn detect(&mut self, photon: &Photon) {
+        if self.error.is_some() {
+            return;
+        }
+        let history = self.histories.saturating_sub(1);
+        match self.writer.serialize(DetectedEvent::new(history, photon)) {
+            Ok(()) => self.written += 1,
+            Err(err) => self.error = Some(err.into()),
+        }
+    }
+}
+
+
+/// The record of a single interaction or energy deposit.
+///
+/// This is written by `InteractionWriter` and read by
+/// `read_interactions()`. Like `DetectedEvent`, all quantities are
+/// plain numbers in the units given by the field names.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InteractionEvent {
+    /// The zero-based number of the history in which the interaction
+    /// occurred.
+    pub history: u64,
+    /// The type of the interaction, or `None` for an energy deposit.
+    #[serde(with = "::serde_name::option")]
+    pub event: Option<Event>,
+    /// The material in which the interaction occurred.
+    #[serde(with = "::serde_name")]
+    pub material: Material,
+    /// The X-coordinate of the interaction.
+    pub x_cm: f64,
+    /// The Y-coordinate of the interaction.
+    pub y_cm: f64,
+    /// The Z-coordinate of the interaction.
+    pub z_cm: f64,
+    /// The energy of the photon before the interaction, or the
+    /// deposited energy for an energy deposit.
+    pub energy_kev: f64,
+    /// The statistical weight of the photon.
+    ///
+    /// Deposited energies have already been multiplied by the weight of
+    /// the depositing particle, so their weight is always one.
+    pub weight: f64,
+}
+
+impl InteractionEvent {
+    /// Creates the record of `photon` undergoing `event`.
+    pub fn interaction(history: u64, material: Material, photon: &Photon, event: Event) -> Self {
+        let mut record = Self::deposit(history, material, photon.location(), photon.energy());
+        record.event = Some(event);
+        record.weight = photon.weight();
+        record
+    }
+
+    /// Creates the record of `energy` being deposited at `location`.
+    pub fn deposit(history: u64, material: Material, location: &Point, energy: Joule<f64>) -> Self {
+        let cm = CENTI * M;
+        let (x, y, z) = location.to_triple();
+        InteractionEvent {
+            history,
+            event: None,
+            material,
+            x_cm: *(x / cm).value(),
+            y_cm: *(y / cm).value(),
+            z_cm: *(z / cm).value(),
+            energy_kev: *(energy / (KILO * EV)).value(),
+            weight: 1.0,
+        }
+    }
+}
+
+
+/// Tally that writes an `InteractionEvent` for every interaction and
+/// every energy deposit.
+///
+/// The records are written as CSV with a header line, in the order in
+/// which they occur. Summing up the deposits in the detector per
+/// history gives the pulse-height spectrum; the interactions are
+/// mostly useful for debugging. Errors are handled like in
+/// `EventWriter`.
+pub struct InteractionWriter<W: Write> {
+    writer: csv::Writer<W>,
+    /// The number of the current history plus one.
+    histories: u64,
+    written: u64,
+    error: Option<Error>,
+}
+
+impl<W: Write> InteractionWriter<W> {
+    /// Creates a writer that writes records to `writer`.
+    pub fn new(writer: W) -> Self {
+        InteractionWriter {
+            writer: csv::Writer::from_writer(writer),
+            histories: 0,
+            written: 0,
+            error: None,
+        }
+    }
+
+    /// Returns the number of records written so far.
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Flushes all records and returns the underlying writer.
+    ///
+    /// # Errors
+    /// This fails if any record could not be written.
+    pub fn finish(mut self) -> Result<W, Error> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.writer
+            .into_inner()
+            .map_err(|err| Error::Io(io::Error::other(err.to_string())))
+    }
+
+    /// Private function that writes a single record.
+    fn write(&mut self, record: InteractionEvent) {
+        if self.error.is_some() {
+            return;
+        }
+        match self.writer.serialize(record) {
+            Ok(()) => self.written += 1,
+            Err(err) => self.error = Some(err.into()),
+        }
+    }
+
+    /// Private function that returns the number of the current history.
+    fn history(&self) -> u64 {
+        self.histories.saturating_sub(1)
+    }
+}
+
+impl InteractionWriter<File> {
+    /// Creates a writer that writes records to the file at `path`.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be created.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        Ok(InteractionWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> Tally for InteractionWriter<W> {
+    fn begin_history(&mut self) {
+        self.histories += 1;
+    }
+
+    fn deposit(&mut self, material: Material, location: &Point, energy: Joule<f64>, weight: f64) {
+        let history = self.history();
+        self.write(InteractionEvent::deposit(history, material, location, energy * weight));
+    }
+
+    fn interact(&mut self, material: Material, photon: &Photon, event: Event) {
+        let history = self.history();
+        self.write(InteractionEvent::interaction(history, material, photon, event));
+    }
+}
+
+
+/// Reads the events written by an `EventWriter` from a file.
+///
+/// # Errors
+/// This fails if the file cannot be read or any record is malformed.
+pub fn read_events<P: AsRef<Path>>(path: P) -> Result<Vec<DetectedEvent>, Error> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let events = reader.deserialize().collect::<Result<_, _>>()?;
+    Ok(events)
+}
+
+
+/// Reads the records written by an `InteractionWriter` from a file.
+///
+/// # Errors
+/// This fails if the file cannot be read or any record is malformed.
+pub fn read_interactions<P: AsRef<Path>>(path: P) -> Result<Vec<InteractionEvent>, Error> {
+    let mut reader = csv::Reader::from_path(path)?;
+    let records = reader.deserialize().collect::<Result<_, _>>()?;
+    Ok(records)
+}
@@ -1,20 +1,34 @@
+use std::fmt;
+use std::error::Error;
+use std::str::FromStr;
+
 use rand::{Rng, thread_rng};
 
 use rand::distributions::{self, IndependentSample};
 
 use dimensioned::si::*;
 use dimensioned::Dimensionless;
+use dimensioned::f64prefixes::*;
+
+use logging;
+use crosssection::PairProductionCrossSection;
 
-use super::Point;
+use super::{Point, Direction, Geometry};
 use super::source::Source;
-use super::particle::Photon;
+use super::tally::Tally;
+use super::material::StoppingPower;
+use super::importance::ImportanceMap;
+use super::particle::{Photon, ChargedParticle, Species};
+use super::runner::RunReport;
 
 
 /// The type of all materials that can exist at a given point.
 ///
 /// This type is used by `Experiment` to describe the experimental
 /// setup.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Material {
     /// A non-interactive material.
     Air,
@@ -24,6 +38,31 @@ pub enum Material {
     Detector,
 }
 
+impl fmt::Display for Material {
+    /// Writes the lowercase name of the material, e.g. `absorber`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Material::Air => "air",
+            Material::Absorber => "absorber",
+            Material::Detector => "detector",
+        })
+    }
+}
+
+impl FromStr for Material {
+    type Err = ::error::Error;
+
+    /// Parses the name written by `Display`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "air" => Ok(Material::Air),
+            "absorber" => Ok(Material::Absorber),
+            "detector" => Ok(Material::Detector),
+            _ => Err(::error::Error::domain(format!("unknown material: {:?}", s))),
+        }
+    }
+}
+
 
 /// The type returned by `Experiment::get_mean_free_path()`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -36,11 +75,45 @@ pub enum FreePath<T> {
 }
 
 
+/// The type returned by `Experiment::exponential_transform()`.
+///
+/// The exponential transform is a variance-reduction technique for
+/// deep-penetration problems. Free paths are stretched for photons
+/// moving towards `direction` and shortened for photons moving away
+/// from it. The total cross-section `Sigma` is replaced by
+/// `Sigma* = Sigma (1 - p mu)`, where `p` is the `strength` and `mu`
+/// the cosine of the angle between the photon's direction and
+/// `direction`. The photon's weight is corrected so that the result
+/// stays unbiased.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExponentialTransform {
+    /// The preferred direction, usually pointing to the detector.
+    pub direction: Direction,
+    /// The strength `p` of the transform, at least zero and less than
+    /// one.
+    pub strength: f64,
+}
+
+
+/// The type returned by `Experiment::energy_cutoff()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cutoff<T> {
+    /// Photons below this energy are simply discarded.
+    Discard(Joule<T>),
+    /// Photons below this energy deposit their remaining energy where
+    /// they are. If this happens inside the detector, the photon counts
+    /// as detected.
+    Deposit(Joule<T>),
+}
+
+
 /// The type of all possible outcomes of an interaction.
 ///
 /// This type is used by `Experiment::gen_event` to find out which
 /// cross-section to use.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Event {
     /// No iteraction occurred.
     Nothing,
@@ -50,6 +123,59 @@ pub enum Event {
     IncoherentScatter,
     /// The photon was absorbed.
     Absorbed,
+    /// The photon was absorbed and a fluorescence X-ray was emitted.
+    Fluorescence,
+    /// The photon was converted into an electron–positron pair.
+    PairProduction,
+}
+
+impl fmt::Display for Event {
+    /// Writes the snake-case name of the event, e.g. `coherent_scatter`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Event::Nothing => "nothing",
+            Event::CoherentScatter => "coherent_scatter",
+            Event::IncoherentScatter => "incoherent_scatter",
+            Event::Absorbed => "absorbed",
+            Event::Fluorescence => "fluorescence",
+            Event::PairProduction => "pair_production",
+        })
+    }
+}
+
+impl FromStr for Event {
+    type Err = ::error::Error;
+
+    /// Parses the name written by `Display`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nothing" => Ok(Event::Nothing),
+            "coherent_scatter" => Ok(Event::CoherentScatter),
+            "incoherent_scatter" => Ok(Event::IncoherentScatter),
+            "absorbed" => Ok(Event::Absorbed),
+            "fluorescence" => Ok(Event::Fluorescence),
+            "pair_production" => Ok(Event::PairProduction),
+            _ => Err(::error::Error::domain(format!("unknown event: {:?}", s))),
+        }
+    }
+}
+
+
+/// Private type of the particles that wait on the stack of a history.
+///
+/// Interactions push secondary particles onto the stack. They are
+/// transported once the current particle has been tracked to its end.
+#[derive(Debug, Clone)]
+enum Secondary {
+    /// A photon, transported by `propagate()`.
+    Photon(Photon),
+    /// A copy split off a photon by the importance map.
+    Copy(Photon),
+    /// A charged particle, transported by `transport_charged()`.
+    ///
+    /// Positrons carry the energy of each of their annihilation
+    /// photons, see `annihilate()`.
+    Charged(ChargedParticle, Option<Joule<f64>>),
 }
 
 
@@ -65,6 +191,34 @@ enum ParticleStatus {
 }
 
 
+/// The error type returned when the transport of a particle fails.
+///
+/// These errors are usually caused by an `Experiment` returning
+/// degenerate values, e.g. a free path of zero or a NaN energy. They
+/// only invalidate the history in which they occur.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TransportError {
+    /// The free path was zero, negative or NaN, or the mean free path
+    /// was not positive and finite.
+    InvalidStep(#[cfg_attr(feature = "serde", serde(with = "::serde_si"))] Meter<f64>),
+    /// A scattering or secondary energy was negative, NaN, or larger
+    /// than the energy of the incoming photon.
+    InvalidEnergy(#[cfg_attr(feature = "serde", serde(with = "::serde_si"))] Joule<f64>),
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TransportError::InvalidStep(step) => write!(f, "invalid free path: {}", step),
+            TransportError::InvalidEnergy(energy) => write!(f, "invalid energy: {}", energy),
+        }
+    }
+}
+
+impl Error for TransportError {}
+
+
 /// The trait of all types that describe an experiment.
 ///
 /// This trait provides an interface through which the function
@@ -73,6 +227,12 @@ enum ParticleStatus {
 ///
 /// This trait is not particularly general and has several arbitrary
 /// restrictions to keep it simple.
+///
+/// By default, experiments are two-dimensional: All photons move in
+/// the plane `z = 0` and are scattered within this plane. Experiments
+/// that override `is_3d()` are simulated in full 3D-space instead. To
+/// simulate an existing two-dimensional experiment in 3D, wrap it in
+/// `Spatial`.
 pub trait Experiment {
     type Source: Source;
 
@@ -91,13 +251,51 @@ pub trait Experiment {
     /// move away from the experiment as early as possible.
     fn x_start(&self) -> Meter<f64>;
 
+    /// Returns `true` if the experiment should be simulated in 3D.
+    ///
+    /// In 3D, the scattering angles returned by `gen_coherent_scatter`
+    /// and `gen_incoherent_scatter` are interpreted as polar angles and
+    /// the azimuthal angle is chosen uniformly at random. Secondary
+    /// photons are emitted isotropically into 3D-space. The source
+    /// should then emit photons in 3D as well, e.g. `SphericalSource`.
+    ///
+    /// The result must not change during a run. If it is `false`, the
+    /// source must emit photons in the plane `z = 0`; transport then
+    /// keeps all particles in this plane, so `get_material()` is only
+    /// queried there. If it is `true`, `get_material()` must describe
+    /// the setup at all points of 3D-space.
+    ///
+    /// By default, this returns `false`.
+    fn is_3d(&self) -> bool {
+        false
+    }
+
+    /// Returns a declarative description of the setup, if any.
+    ///
+    /// If this returns a geometry, `get_material()` need not be
+    /// implemented. By default, this returns `None`.
+    fn geometry(&self) -> Option<&Geometry> {
+        None
+    }
+
     /// Describes the setup of the experiment.
     ///
     /// This function must be able to determine the material of the
     /// object that takes up space at any given location. It thus
     /// serves a complete description of what the experimental setup
     /// is.
-    fn get_material(&self, location: &Point) -> Material;
+    ///
+    /// By default, this looks up the material in `geometry()`.
+    ///
+    /// # Panics
+    /// The default implementation panics if `geometry()` returns
+    /// `None`. Experiments must implement at least one of both
+    /// functions.
+    fn get_material(&self, location: &Point) -> Material {
+        self.geometry()
+            .expect("experiment has neither geometry() nor get_material()")
+            .get_material(location)
+    }
 
     /// Returns the mean free path associated with at material.
     ///
@@ -106,6 +304,70 @@ pub trait Experiment {
     /// exponential distribution.
     fn get_mean_free_path(&self, material: Material, energy: Joule<f64>) -> FreePath<f64>;
 
+    /// Returns the energy below which photons are no longer tracked.
+    ///
+    /// Photons of very low energy tend to undergo long chains of
+    /// Compton scatterings. These chains take up a lot of runtime, but
+    /// hardly contribute to the spectrum. By default, there is no
+    /// cutoff.
+    fn energy_cutoff(&self) -> Option<Cutoff<f64>> {
+        None
+    }
+
+    /// Returns the parameters of the exponential transform.
+    ///
+    /// See `ExponentialTransform` for details. This only affects
+    /// exponentially distributed free paths. By default, free paths are
+    /// not biased.
+    fn exponential_transform(&self) -> Option<ExponentialTransform> {
+        None
+    }
+
+    /// Returns the importance map used for splitting and Russian
+    /// roulette.
+    ///
+    /// See `ImportanceMap` for details. By default, there is no map and
+    /// the simulation is analog.
+    fn importance_map(&self) -> Option<&ImportanceMap> {
+        None
+    }
+
+    /// Returns the stopping power of a material for electrons and
+    /// positrons.
+    ///
+    /// If this returns a value for the material in which a photon is
+    /// absorbed by the photo-effect or scattered incoherently, the
+    /// electron set free by the interaction is tracked, see
+    /// `ChargedParticle`. Otherwise, the electron deposits its energy
+    /// where it is created. The same happens if a tracked particle
+    /// enters a material without stopping power.
+    ///
+    /// By default, no material has a stopping power and charged
+    /// particles are never tracked.
+    fn stopping_power(&self, _: Material) -> Option<&StoppingPower> {
+        None
+    }
+
+    /// Returns the energy below which electrons and positrons are no
+    /// longer tracked.
+    ///
+    /// Particles below this energy deposit their remaining kinetic
+    /// energy where they are. By default, this is 10 keV.
+    fn electron_cutoff(&self) -> Joule<f64> {
+        10.0 * KILO * EV
+    }
+
+    /// Returns the probability that a photon absorbed by the detector
+    /// material is actually registered.
+    ///
+    /// This allows modelling the intrinsic efficiency of a detector
+    /// (e.g. via a `Function` of the energy) without simulating the
+    /// transport inside of it. By default, every absorbed photon is
+    /// detected.
+    fn detection_efficiency(&self, _: Joule<f64>) -> Unitless<f64> {
+        Unitless::new(1.0)
+    }
+
     /// Decides whether a collision occurs at a certain point.
     ///
     /// This function should randomly decide what kind of interaction
@@ -140,6 +402,229 @@ pub trait Experiment {
         energy: Joule<f64>,
         rng: &mut R,
     ) -> (Unitless<f64>, Joule<f64>);
+
+    /// Returns the energy of a fluorescence X-ray.
+    ///
+    /// If the decision has been made that a fluorescence event shall
+    /// take place, the photon is absorbed and the excited atom may
+    /// emit a characteristic X-ray (e.g. from the K-shell) into a
+    /// random direction. This function is called to determine the
+    /// X-ray's energy. If it returns `None`, no X-ray is emitted and
+    /// the event is treated like an absorption.
+    ///
+    /// By default, no X-ray is ever emitted.
+    fn gen_fluorescence<R: Rng>(
+        &self,
+        _: Material,
+        _: Joule<f64>,
+        _: &mut R,
+    ) -> Option<Joule<f64>> {
+        None
+    }
+
+    /// Returns the energy of an annihilation photon after pair
+    /// production.
+    ///
+    /// If the decision has been made that a pair-production event
+    /// shall take place, the photon is replaced by an electron and a
+    /// positron, see `gen_pair_split()`. Both are transported like
+    /// other charged particles, see `stopping_power()`. Once the
+    /// positron has come to rest, it annihilates into two photons,
+    /// which are emitted back to back in a random direction and
+    /// tracked further. This function is called to determine the
+    /// energy of each of them; the remainder of the rest energy of the
+    /// pair is deposited locally. If it returns `None`, the photon is
+    /// absorbed without creating a pair.
+    ///
+    /// By default, the annihilation photon has an energy of
+    /// `m_e c²` (511 keV).
+    fn gen_pair_production<R: Rng>(
+        &self,
+        _: Material,
+        _: Joule<f64>,
+        _: &mut R,
+    ) -> Option<Joule<f64>> {
+        Some(PairProductionCrossSection::annihilation_energy())
+    }
+
+    /// Returns the kinetic energies of the electron and the positron
+    /// created by pair production, in this order.
+    ///
+    /// Both particles start into the direction of the photon. Their
+    /// kinetic energies must add up to at most the photon's energy
+    /// minus the threshold `2 m_e c²`; any remainder is deposited
+    /// locally.
+    ///
+    /// By default, the energy is split according to
+    /// `PairProductionCrossSection::gen_energy_split()`.
+    fn gen_pair_split<R: Rng>(
+        &self,
+        _: Material,
+        energy: Joule<f64>,
+        rng: &mut R,
+    ) -> (Joule<f64>, Joule<f64>) {
+        // The split does not depend on the atomic number.
+        PairProductionCrossSection::new(1.0).gen_energy_split(energy, rng)
+    }
+
+    /// Simulates `n_histories` histories of the experiment.
+    ///
+    /// This is the simplest way to run an experiment. The run is
+    /// reproducible: the same `seed` always gives the same result. Use
+    /// `RunReport::spectrum()` to get the energy spectrum with
+    /// uncertainties. For progress reports or tallies, use a `Runner`
+    /// instead.
+    fn run(&self, n_histories: usize, seed: u64) -> RunReport
+    where
+        Self: Sized,
+    {
+        RunReport::simulate(self, n_histories, seed)
+    }
+
+    /// Simulates the experiment until the fraction of detected photons
+    /// is known with the relative error `precision`.
+    ///
+    /// Like `run()`, the same `seed` always gives the same result. See
+    /// `Runner::run_streams_until()` for details.
+    fn run_until(&self, precision: f64, seed: u64) -> RunReport
+    where
+        Self: Sized,
+    {
+        RunReport::simulate_until(self, precision, seed)
+    }
+}
+
+
+/// An experiment that is simulated in 3D-space.
+///
+/// This wraps another experiment, usually a two-dimensional one, and
+/// forwards all methods to it, except that `is_3d()` returns `true`.
+/// The wrapped experiment's `get_material()` must thus be valid at all
+/// points of 3D-space, e.g. by describing a setup that is uniform
+/// along the Z-axis. For realistic solid angles, its source should
+/// emit photons into 3D-space as well, see `SphericalSource`.
+#[derive(Debug, Clone)]
+pub struct Spatial<E> {
+    experiment: E,
+}
+
+impl<E: Experiment> Spatial<E> {
+    /// Wraps `experiment` so that it is simulated in 3D.
+    pub fn new(experiment: E) -> Self {
+        Spatial { experiment }
+    }
+
+    /// Returns the wrapped experiment.
+    pub fn experiment(&self) -> &E {
+        &self.experiment
+    }
+
+    /// Consumes `self` and returns the wrapped experiment.
+    pub fn into_inner(self) -> E {
+        self.experiment
+    }
+}
+
+impl<E: Experiment> Experiment for Spatial<E> {
+    type Source = E::Source;
+
+    fn source(&self) -> &Self::Source {
+        self.experiment.source()
+    }
+
+    fn x_start(&self) -> Meter<f64> {
+        self.experiment.x_start()
+    }
+
+    /// Always returns `true`.
+    fn is_3d(&self) -> bool {
+        true
+    }
+
+    fn geometry(&self) -> Option<&Geometry> {
+        self.experiment.geometry()
+    }
+
+    fn get_material(&self, location: &Point) -> Material {
+        self.experiment.get_material(location)
+    }
+
+    fn get_mean_free_path(&self, material: Material, energy: Joule<f64>) -> FreePath<f64> {
+        self.experiment.get_mean_free_path(material, energy)
+    }
+
+    fn energy_cutoff(&self) -> Option<Cutoff<f64>> {
+        self.experiment.energy_cutoff()
+    }
+
+    fn exponential_transform(&self) -> Option<ExponentialTransform> {
+        self.experiment.exponential_transform()
+    }
+
+    fn importance_map(&self) -> Option<&ImportanceMap> {
+        self.experiment.importance_map()
+    }
+
+    fn stopping_power(&self, material: Material) -> Option<&StoppingPower> {
+        self.experiment.stopping_power(material)
+    }
+
+    fn electron_cutoff(&self) -> Joule<f64> {
+        self.experiment.electron_cutoff()
+    }
+
+    fn detection_efficiency(&self, energy: Joule<f64>) -> Unitless<f64> {
+        self.experiment.detection_efficiency(energy)
+    }
+
+    fn gen_event<R: Rng>(&self, material: Material, energy: Joule<f64>, rng: &mut R) -> Event {
+        self.experiment.gen_event(material, energy, rng)
+    }
+
+    fn gen_coherent_scatter<R: Rng>(
+        &self,
+        material: Material,
+        energy: Joule<f64>,
+        rng: &mut R,
+    ) -> Unitless<f64> {
+        self.experiment.gen_coherent_scatter(material, energy, rng)
+    }
+
+    fn gen_incoherent_scatter<R: Rng>(
+        &self,
+        material: Material,
+        energy: Joule<f64>,
+        rng: &mut R,
+    ) -> (Unitless<f64>, Joule<f64>) {
+        self.experiment.gen_incoherent_scatter(material, energy, rng)
+    }
+
+    fn gen_fluorescence<R: Rng>(
+        &self,
+        material: Material,
+        energy: Joule<f64>,
+        rng: &mut R,
+    ) -> Option<Joule<f64>> {
+        self.experiment.gen_fluorescence(material, energy, rng)
+    }
+
+    fn gen_pair_production<R: Rng>(
+        &self,
+        material: Material,
+        energy: Joule<f64>,
+        rng: &mut R,
+    ) -> Option<Joule<f64>> {
+        self.experiment.gen_pair_production(material, energy, rng)
+    }
+
+    fn gen_pair_split<R: Rng>(
+        &self,
+        material: Material,
+        energy: Joule<f64>,
+        rng: &mut R,
+    ) -> (Joule<f64>, Joule<f64>) {
+        self.experiment.gen_pair_split(material, energy, rng)
+    }
 }
 
 
@@ -148,35 +633,298 @@ pub trait Experiment {
 /// This creates a photon at the experiment's source and simulates its
 /// path through the experiment. If the photon is lost on its way, the
 /// procedure is repeated from the start. This process is repeated
-/// until eventually a photon is detected.
+/// until eventually a photon is detected. Histories that fail with a
+/// `TransportError` are treated like lost photons.
+///
+/// This uses the thread-local random-number generator, so the result
+/// cannot be reproduced. Use `simulate_particle_with_rng()` with a
+/// seeded generator for that.
 pub fn simulate_particle<E>(exp: &E) -> Photon
 where
     E: Experiment,
 {
-    let source = exp.source();
-    let mut rng = thread_rng();
-    loop {
-        // Get a photon.
-        let mut photon = source.emit_photon(&mut rng);
+    simulate_particle_with_rng(exp, &mut thread_rng())
+}
+
 
-        // Make sure it's headed towards the experiment.
-        if photon.go_to_x(exp.x_start()).is_err() {
-            continue;
+/// Like `simulate_particle()`, but uses `rng` as a source of
+/// randomness.
+///
+/// With a seeded generator, e.g. from `StreamRng`, the same photon is
+/// simulated every time.
+pub fn simulate_particle_with_rng<E, R>(exp: &E, rng: &mut R) -> Photon
+where
+    E: Experiment,
+    R: Rng,
+{
+    simulate_particle_with_tally(exp, rng, &mut ())
+}
+
+
+/// Like `simulate_particle_with_rng()`, but reports everything that
+/// happens to `tally`.
+///
+/// This includes the histories in which the photon is lost, so `tally`
+/// sees one `begin_history()` and `end_history()` per attempt. Use
+/// this with a custom `Tally` to collect arbitrary per-event data, e.g.
+/// every interaction via `Tally::interact()`.
+pub fn simulate_particle_with_tally<E, R, T>(exp: &E, rng: &mut R, tally: &mut T) -> Photon
+where
+    E: Experiment,
+    R: Rng,
+    T: Tally,
+{
+    loop {
+        if let Ok(Some(photon)) = simulate_history(exp, rng, tally) {
+            return photon;
         }
+    }
+}
+
 
-        // Propagate it until it hits the detector or gets lost. If it
-        // gets detected, the function is done. Otherwise, we have to
-        // break the inner loop and continue the outer loop.
-        let mut result;
+/// Simulates the history of a single photon in an experiment.
+///
+/// This creates a photon at the experiment's source and simulates its
+/// path through the experiment. Unlike `simulate_particle()`, this
+/// function gives up as soon as the photon is lost. If the photon
+/// reaches the detector, it is returned, otherwise, the result is
+/// `None`.
+///
+/// If the experiment has an importance map or the photon creates
+/// secondary photons, more than one photon may be detected. In this
+/// case, only the first one is returned; use `simulate_primary()` to
+/// get all of them.
+///
+/// All interactions, energy depositions and detections are reported to
+/// `tally`. Pass `&mut ()` if no tally is needed.
+///
+/// # Errors
+/// This fails if the transport of any photon fails. The history is
+/// aborted, but `tally` keeps everything reported to it up to this
+/// point.
+pub fn simulate_history<E, R, T>(
+    exp: &E,
+    rng: &mut R,
+    tally: &mut T,
+) -> Result<Option<Photon>, TransportError>
+where
+    E: Experiment,
+    R: Rng,
+    T: Tally,
+{
+    let mut first = None;
+    simulate_history_with(exp, rng, tally, |photon| if first.is_none() {
+        first = Some(photon);
+    })?;
+    Ok(first)
+}
+
+
+/// Like `simulate_history()`, but returns all detected photons.
+///
+/// Besides the primary photon, these may be secondary photons, e.g.
+/// fluorescence X-rays and annihilation photons, and photons split off
+/// by an importance map. They are returned in the order in which they
+/// have been detected.
+///
+/// # Errors
+/// This fails under the same conditions as `simulate_history()`.
+pub fn simulate_primary<E, R, T>(
+    exp: &E,
+    rng: &mut R,
+    tally: &mut T,
+) -> Result<Vec<Photon>, TransportError>
+where
+    E: Experiment,
+    R: Rng,
+    T: Tally,
+{
+    let mut detected = Vec::new();
+    simulate_history_with(exp, rng, tally, |photon| detected.push(photon))?;
+    Ok(detected)
+}
+
+
+/// Like `simulate_history()`, but passes every detected photon to
+/// `on_detect`.
+///
+/// The return value is the number of detected photons.
+///
+/// # Errors
+/// This fails under the same conditions as `simulate_history()`.
+pub fn simulate_history_with<E, R, T, F>(
+    exp: &E,
+    rng: &mut R,
+    tally: &mut T,
+    mut on_detect: F,
+) -> Result<usize, TransportError>
+where
+    E: Experiment,
+    R: Rng,
+    T: Tally,
+    F: FnMut(Photon),
+{
+    tally.begin_history();
+    let detected = simulate_history_inner(exp, rng, tally, &mut on_detect);
+    tally.end_history();
+    detected
+}
+
+
+/// Private function that implements `simulate_history_with()`.
+///
+/// This is split off so that `Tally::end_history()` is called no
+/// matter where the history ends.
+fn simulate_history_inner<E, R, T, F>(
+    exp: &E,
+    rng: &mut R,
+    tally: &mut T,
+    on_detect: &mut F,
+) -> Result<usize, TransportError>
+where
+    E: Experiment,
+    R: Rng,
+    T: Tally,
+    F: FnMut(Photon),
+{
+    // Get a photon and make sure it's headed towards the experiment.
+    let mut photon = exp.source().emit_photon(rng);
+    tally.start(&photon);
+    if photon.go_to_x(exp.x_start()).is_err() {
+        return Ok(0);
+    }
+    tally.step(&photon);
+    // Secondary particles wait on the stack until the current particle
+    // has been tracked to its end. They inherit the importance of their
+    // parent's cell. Photons split off by the importance map wait until
+    // the stack is empty, so that everything that happened before the
+    // splitting is shared by all copies.
+    let importance = exp.importance_map()
+        .and_then(|map| map.importance(photon.location()));
+    let mut stack = vec![(Secondary::Photon(photon), importance)];
+    let mut copies = Vec::new();
+    let mut secondaries = Vec::new();
+    let mut detected = 0;
+    let mut is_primary = true;
+    while let Some((particle, mut importance)) = stack.pop().or_else(|| copies.pop()) {
+        let mut photon = match particle {
+            Secondary::Photon(photon) => {
+                // The primary photon has been announced right after
+                // emission.
+                if !is_primary {
+                    tally.start(&photon);
+                }
+                photon
+            },
+            Secondary::Copy(photon) => {
+                tally.start_copy(&photon);
+                photon
+            },
+            Secondary::Charged(particle, annihilation) => {
+                let stopped = transport_charged(exp, particle, tally)?;
+                if let (Some(positron), Some(energy)) = (stopped, annihilation) {
+                    annihilate(exp, &positron, energy, rng, tally, &mut secondaries);
+                }
+                stack.extend(secondaries.drain(..).map(|secondary| (secondary, importance)));
+                continue;
+            },
+        };
+        is_primary = false;
+        // Propagate it until it hits the detector or gets lost.
         loop {
-            result = propagate(exp, &mut photon, &mut rng);
-            match result {
-                ParticleStatus::Propagating => {},
-                ParticleStatus::Detected => return photon,
+            let status = propagate(exp, &mut photon, rng, tally, &mut secondaries)?;
+            stack.extend(secondaries.drain(..).map(|secondary| (secondary, importance)));
+            match status {
+                ParticleStatus::Propagating => {
+                    if !apply_importance(exp, &mut photon, &mut importance, rng, tally, &mut copies) {
+                        break;
+                    }
+                },
+                ParticleStatus::Detected => {
+                    tally.detect(&photon);
+                    on_detect(photon);
+                    detected += 1;
+                    break;
+                },
                 ParticleStatus::Lost => break,
             }
         }
     }
+    Ok(detected)
+}
+
+
+/// Private function that splits or roulettes a particle according to
+/// the experiment's importance map.
+///
+/// `importance` is the importance of the cell in which the particle
+/// has been before its last step; it is updated to the importance of
+/// its current cell. Splitting and roulette are reported to `tally`.
+///
+/// If the particle is split into several copies, all of them are pushed
+/// onto `copies` and the particle itself stops. This, like the particle
+/// being killed by Russian roulette, makes the function return `false`.
+fn apply_importance<E, R, T>(
+    exp: &E,
+    photon: &mut Photon,
+    importance: &mut Option<f64>,
+    rng: &mut R,
+    tally: &mut T,
+    copies: &mut Vec<(Secondary, Option<f64>)>,
+) -> bool
+where
+    E: Experiment,
+    R: Rng,
+    T: Tally,
+{
+    let new = match exp.importance_map().and_then(|map| map.importance(photon.location())) {
+        Some(new) => new,
+        None => return true,
+    };
+    let old = match importance.take() {
+        Some(old) => old,
+        None => {
+            *importance = Some(new);
+            return true;
+        },
+    };
+    *importance = Some(new);
+    if new <= 0.0 {
+        tally.split(photon, 0);
+        return false;
+    }
+    // A particle that starts in a cell of importance zero has never
+    // entered it. It keeps its weight when leaving the cell.
+    if old <= 0.0 {
+        return true;
+    }
+    let ratio = new / old;
+    if ratio > 1.0 {
+        // Split into `ratio` copies on average.
+        let n_copies = (ratio + rng.gen::<f64>()).floor() as usize;
+        let weight = photon.weight() / ratio;
+        photon.set_weight(weight);
+        tally.split(photon, n_copies);
+        if !logging::SPLITTING.count(n_copies > 1) {
+            return true;
+        }
+        for _ in 0..n_copies {
+            copies.push((Secondary::Copy(photon.clone()), Some(new)));
+        }
+        false
+    } else if ratio < 1.0 {
+        if logging::ROULETTE.count(rng.gen::<f64>() < ratio) {
+            let weight = photon.weight() / ratio;
+            photon.set_weight(weight);
+            tally.split(photon, 1);
+            true
+        } else {
+            tally.split(photon, 0);
+            false
+        }
+    } else {
+        true
+    }
 }
 
 
@@ -188,48 +936,410 @@ where
 /// unhindered.
 ///
 /// The return value reports the result of the particle's interaction.
-fn propagate<E, R>(exp: &E, photon: &mut Photon, rng: &mut R) -> ParticleStatus
+/// Any energy deposited in the medium is reported to `tally`. Any
+/// secondary particles that need to be tracked are pushed onto
+/// `secondaries`.
+///
+/// # Errors
+/// This fails if the experiment returns a degenerate free path or
+/// energy.
+fn propagate<E, R, T>(
+    exp: &E,
+    photon: &mut Photon,
+    rng: &mut R,
+    tally: &mut T,
+    secondaries: &mut Vec<Secondary>,
+) -> Result<ParticleStatus, TransportError>
 where
     E: Experiment,
     R: Rng,
+    T: Tally,
 {
-    // Move the particle. If it leaves the experiment, stop.
+    // Terminate the particle if its energy is too low.
     let material = exp.get_material(photon.location());
+    match exp.energy_cutoff() {
+        Some(Cutoff::Discard(limit)) if photon.energy() < limit => {
+            return Ok(ParticleStatus::Lost);
+        },
+        Some(Cutoff::Deposit(limit)) if photon.energy() < limit => {
+            return Ok(absorb(exp, material, photon, rng, tally));
+        },
+        _ => {},
+    }
+
+    // Move the particle. If it leaves the experiment, stop.
     let scale = match exp.get_mean_free_path(material, photon.energy()) {
         FreePath::Fix(scale) => scale,
         FreePath::Exp(mean) => {
-            let lambda = M / mean;
-            let distribution = distributions::Exp::new(*lambda.value());
-            distribution.ind_sample(rng) * M
+            if !mean.value().is_finite() || mean <= 0.0 * M {
+                return Err(TransportError::InvalidStep(mean));
+            }
+            match exp.exponential_transform() {
+                Some(transform) => stretched_path(&transform, mean, photon, rng)?,
+                None => {
+                    let lambda = M / mean;
+                    let distribution = distributions::Exp::new(*lambda.value());
+                    distribution.ind_sample(rng) * M
+                },
+            }
         },
     };
-    photon.step(scale).expect("`scale` cannot be negative");
+    photon
+        .step(scale)
+        .map_err(|_| TransportError::InvalidStep(scale))?;
+    tally.step(photon);
     if photon.location().x() < exp.x_start() {
-        return ParticleStatus::Lost;
+        return Ok(ParticleStatus::Lost);
     }
 
     // Find the next interaction at the new location.
     let material = exp.get_material(photon.location());
     let event = exp.gen_event(material, photon.energy(), rng);
+    if event != Event::Nothing {
+        tally.interact(material, photon, event);
+    }
 
-    match event {
+    let status = match event {
         Event::Nothing => ParticleStatus::Propagating,
         Event::Absorbed => {
-            match material {
-                Material::Detector => ParticleStatus::Detected,
-                _ => ParticleStatus::Lost,
-            }
+            let energy = photon.energy();
+            emit_photoelectron(exp, material, photon, energy, rng, tally, secondaries);
+            detect(exp, material, photon, rng)
         },
         Event::CoherentScatter => {
             let angle = exp.gen_coherent_scatter(material, photon.energy(), rng);
-            photon.direction_mut().rotate(angle);
+            scatter(exp, photon, angle, rng);
             ParticleStatus::Propagating
         },
         Event::IncoherentScatter => {
             let (angle, energy) = exp.gen_incoherent_scatter(material, photon.energy(), rng);
-            photon.direction_mut().rotate(angle);
+            check_energy(energy, photon.energy())?;
+            let incoming = photon.clone();
+            scatter(exp, photon, angle, rng);
             photon.set_energy(energy);
+            let direction = recoil_direction(&incoming, photon);
+            let recoil = incoming.energy() - energy;
+            let electron =
+                ChargedParticle::secondary(Species::Electron, &incoming, direction, recoil);
+            secondaries.push(Secondary::Charged(electron, None));
             ParticleStatus::Propagating
         },
+        Event::Fluorescence => {
+            match exp.gen_fluorescence(material, photon.energy(), rng) {
+                Some(energy) => {
+                    check_energy(energy, photon.energy())?;
+                    // The photo-electron carries the rest of the energy.
+                    let rest = photon.energy() - energy;
+                    emit_photoelectron(exp, material, photon, rest, rng, tally, secondaries);
+                    let mut xray = photon.clone();
+                    xray.reemit(random_direction(exp, rng), energy);
+                    secondaries.push(Secondary::Photon(xray));
+                    // The photon has been replaced by the X-ray.
+                    ParticleStatus::Lost
+                },
+                None => absorb(exp, material, photon, rng, tally),
+            }
+        },
+        Event::PairProduction => {
+            match exp.gen_pair_production(material, photon.energy(), rng) {
+                Some(energy) => {
+                    let threshold = PairProductionCrossSection::threshold();
+                    if photon.energy() <= threshold {
+                        return Err(TransportError::InvalidEnergy(photon.energy()));
+                    }
+                    check_energy(2.0 * energy, threshold)?;
+                    let available = photon.energy() - threshold;
+                    let (electron, positron) = exp.gen_pair_split(material, photon.energy(), rng);
+                    check_energy(electron, available)?;
+                    check_energy(positron, available)?;
+                    // Allow for rounding errors when splitting the energy.
+                    let rest = available - electron - positron;
+                    if rest < -ENERGY_TOLERANCE * available {
+                        return Err(TransportError::InvalidEnergy(electron + positron));
+                    } else if rest > 0.0 * J {
+                        tally.deposit(material, photon.location(), rest, photon.weight());
+                    }
+                    let direction = photon.direction();
+                    let new = |species, energy| {
+                        ChargedParticle::secondary(species, photon, direction.clone(), energy)
+                    };
+                    let electron = new(Species::Electron, electron);
+                    let positron = new(Species::Positron, positron);
+                    secondaries.push(Secondary::Charged(electron, None));
+                    secondaries.push(Secondary::Charged(positron, Some(energy)));
+                    // The photon has been replaced by the pair.
+                    ParticleStatus::Lost
+                },
+                None => absorb(exp, material, photon, rng, tally),
+            }
+        },
+    };
+    Ok(status)
+}
+
+
+/// Private function that samples a free path with the exponential
+/// transform.
+///
+/// This also multiplies the photon's weight by the ratio of the analog
+/// and the biased probability density of the sampled path.
+///
+/// # Errors
+/// This fails if the biased cross-section is not positive, i.e. if the
+/// strength of the transform is one or more.
+fn stretched_path<R: Rng>(
+    transform: &ExponentialTransform,
+    mean: Meter<f64>,
+    photon: &mut Photon,
+    rng: &mut R,
+) -> Result<Meter<f64>, TransportError> {
+    let sigma = *(M / mean).value();
+    let mu = *photon.direction().dot(&transform.direction);
+    let biased_sigma = sigma * (1.0 - transform.strength * mu);
+    if biased_sigma.is_nan() || biased_sigma <= 0.0 {
+        return Err(TransportError::InvalidStep(M / biased_sigma));
+    }
+    let path = distributions::Exp::new(biased_sigma).ind_sample(rng);
+    let weight = photon.weight() * sigma / biased_sigma * (-(sigma - biased_sigma) * path).exp();
+    photon.set_weight(weight);
+    Ok(path * M)
+}
+
+
+/// Private function that checks an energy returned by the experiment.
+///
+/// # Errors
+/// This fails if `energy` is negative, NaN, or larger than `incoming`,
+/// the energy of the photon before the interaction.
+fn check_energy(energy: Joule<f64>, incoming: Joule<f64>) -> Result<(), TransportError> {
+    if energy >= 0.0 * J && energy <= incoming {
+        Ok(())
+    } else {
+        Err(TransportError::InvalidEnergy(energy))
     }
 }
+
+
+/// Private function that scatters a particle by a given angle.
+///
+/// In 2D, this simply rotates the particle's direction. In 3D, `angle`
+/// is the polar angle and the azimuthal angle is chosen at random.
+fn scatter<E, R>(exp: &E, photon: &mut Photon, angle: Unitless<f64>, rng: &mut R)
+where
+    E: Experiment,
+    R: Rng,
+{
+    if exp.is_3d() {
+        let phi = rng.gen_range(0.0, 2.0 * ::std::f64::consts::PI);
+        photon.scatter_3d(Unitless::new(angle.abs()), Unitless::new(phi));
+    } else {
+        photon.scatter(angle);
+    }
+}
+
+
+/// Private function that returns a random direction for a secondary
+/// particle.
+fn random_direction<E, R>(exp: &E, rng: &mut R) -> Direction
+where
+    E: Experiment,
+    R: Rng,
+{
+    if exp.is_3d() {
+        Direction::random_3d(rng)
+    } else {
+        rng.gen()
+    }
+}
+
+
+/// Private function that sets free the electron of a photo-absorption.
+///
+/// If the electron is tracked, see `transport_charged()`, it is emitted
+/// isotropically with the given `energy` and pushed onto `secondaries`.
+/// Otherwise, it deposits its energy where it is created. In this case,
+/// no random number is drawn, so that experiments without charged
+/// particles leave the random-number stream untouched.
+fn emit_photoelectron<E, R, T>(
+    exp: &E,
+    material: Material,
+    photon: &Photon,
+    energy: Joule<f64>,
+    rng: &mut R,
+    tally: &mut T,
+    secondaries: &mut Vec<Secondary>,
+) where
+    E: Experiment,
+    R: Rng,
+    T: Tally,
+{
+    let is_tracked = energy > exp.electron_cutoff()
+        && exp.stopping_power(material).and_then(|table| table.at(energy)).is_some();
+    if is_tracked {
+        let direction = random_direction(exp, rng);
+        let electron = ChargedParticle::secondary(Species::Electron, photon, direction, energy);
+        secondaries.push(Secondary::Charged(electron, None));
+    } else {
+        tally.deposit(material, photon.location(), energy, photon.weight());
+    }
+}
+
+
+/// Private function that returns the direction of the electron that
+/// recoils from an incoherent scattering.
+///
+/// `incoming` is the photon before and `outgoing` after the scattering.
+/// The direction follows from the conservation of momentum; binding
+/// effects are neglected.
+fn recoil_direction(incoming: &Photon, outgoing: &Photon) -> Direction {
+    let momentum = |photon: &Photon| {
+        let energy = *(photon.energy() / J).value();
+        let direction = photon.direction();
+        (energy * *direction.dx(), energy * *direction.dy(), energy * *direction.dz())
+    };
+    let (px, py, pz) = momentum(incoming);
+    let (qx, qy, qz) = momentum(outgoing);
+    Direction::new_3d(Unitless::new(px - qx), Unitless::new(py - qy), Unitless::new(pz - qz))
+}
+
+
+/// Private function that tracks a charged particle until it stops.
+///
+/// The particle moves in a straight line. In each step, it loses the
+/// fraction `MAX_ENERGY_LOSS` of its kinetic energy; the step length
+/// follows from the stopping power of the current material. The lost
+/// energy is deposited in the middle of the step.
+///
+/// Once the particle's energy falls below the cutoff, or if it is in a
+/// material without stopping power, it deposits its remaining energy
+/// where it is and is returned. This is also the case for particles
+/// created with an energy below the cutoff. Particles in a material
+/// with zero stopping power, or that move out of the experiment to the
+/// left, are lost and `None` is returned.
+///
+/// # Errors
+/// This fails if the stopping power is negative or NaN.
+fn transport_charged<E, T>(
+    exp: &E,
+    mut particle: ChargedParticle,
+    tally: &mut T,
+) -> Result<Option<ChargedParticle>, TransportError>
+where
+    E: Experiment,
+    T: Tally,
+{
+    loop {
+        let material = exp.get_material(particle.location());
+        let energy = particle.energy();
+        let stopping_power = exp.stopping_power(material).and_then(|table| table.at(energy));
+        let stopping_power = match stopping_power {
+            Some(stopping_power) if energy > exp.electron_cutoff() => stopping_power,
+            _ => {
+                tally.deposit(material, particle.location(), energy, particle.weight());
+                break;
+            },
+        };
+        if stopping_power == 0.0 * N {
+            return Ok(None);
+        }
+        let loss = MAX_ENERGY_LOSS * energy;
+        let step = loss / stopping_power;
+        particle
+            .step(0.5 * step)
+            .map_err(|_| TransportError::InvalidStep(step))?;
+        tally.deposit(material, particle.location(), loss, particle.weight());
+        particle
+            .step(0.5 * step)
+            .map_err(|_| TransportError::InvalidStep(step))?;
+        particle.set_energy(energy - loss);
+        if particle.location().x() < exp.x_start() {
+            return Ok(None);
+        }
+    }
+    Ok(Some(particle))
+}
+
+
+/// Private function that lets a positron annihilate at rest.
+///
+/// Two annihilation photons with the given `energy` are emitted back to
+/// back in a random direction and pushed onto `secondaries`. The
+/// remainder of the rest energy of the pair is deposited where the
+/// positron is.
+fn annihilate<E, R, T>(
+    exp: &E,
+    positron: &ChargedParticle,
+    energy: Joule<f64>,
+    rng: &mut R,
+    tally: &mut T,
+    secondaries: &mut Vec<Secondary>,
+) where
+    E: Experiment,
+    R: Rng,
+    T: Tally,
+{
+    let material = exp.get_material(positron.location());
+    let deposit = PairProductionCrossSection::threshold() - 2.0 * energy;
+    tally.deposit(material, positron.location(), deposit, positron.weight());
+    let direction = random_direction(exp, rng);
+    for direction in [direction.reversed(), direction] {
+        secondaries.push(Secondary::Photon(positron.emit(direction, energy)));
+    }
+}
+
+
+/// Private function that decides the fate of an absorbed particle.
+///
+/// The particle deposits all of its energy at its current location.
+/// Then, its fate is decided by `detect()`.
+fn absorb<E, R, T>(
+    exp: &E,
+    material: Material,
+    photon: &Photon,
+    rng: &mut R,
+    tally: &mut T,
+) -> ParticleStatus
+where
+    E: Experiment,
+    R: Rng,
+    T: Tally,
+{
+    tally.deposit(material, photon.location(), photon.energy(), photon.weight());
+    detect(exp, material, photon, rng)
+}
+
+
+/// Private function that decides whether an absorbed particle is
+/// detected.
+///
+/// Particles absorbed outside of the detector are lost. Particles
+/// absorbed by the detector are detected with a probability given by
+/// `Experiment::detection_efficiency()`. If it is one or more, no
+/// random number is drawn, so that perfect detectors leave the
+/// random-number stream untouched.
+fn detect<E, R>(exp: &E, material: Material, photon: &Photon, rng: &mut R) -> ParticleStatus
+where
+    E: Experiment,
+    R: Rng,
+{
+    match material {
+        Material::Detector => {
+            let efficiency = *exp.detection_efficiency(photon.energy()).value();
+            if efficiency >= 1.0 || rng.gen::<f64>() < efficiency {
+                ParticleStatus::Detected
+            } else {
+                ParticleStatus::Lost
+            }
+        },
+        _ => ParticleStatus::Lost,
+    }
+}
+
+
+/// The fraction of its kinetic energy that a charged particle loses in
+/// each step, see `transport_charged()`.
+const MAX_ENERGY_LOSS: f64 = 0.1;
+
+/// The relative tolerance for rounding errors when an experiment splits
+/// the energy of a photon between several particles.
+const ENERGY_TOLERANCE: f64 = 1e-9;
@@ -1,18 +1,36 @@
 use rand::{Rand, Rng};
 
 use dimensioned::si::*;
+use dimensioned::Dimensionless;
 
+use contains::Contains;
+use super::experiment::Material;
 
-/// Type that describes a location in 2D-space.
+
+/// Type that describes a location in 3D-space.
+///
+/// Two-dimensional experiments simply use the plane `z = 0`. Points
+/// created via `Point::new()` lie in this plane.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Point {
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
     x: Meter<f64>,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
     y: Meter<f64>,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    z: Meter<f64>,
 }
 
 impl Point {
+    /// Creates a new point in the plane `z = 0`.
     pub fn new(x: Meter<f64>, y: Meter<f64>) -> Self {
-        Point { x, y }
+        Point::new_3d(x, y, 0.0 * M)
+    }
+
+    /// Creates a new point in 3D-space.
+    pub fn new_3d(x: Meter<f64>, y: Meter<f64>, z: Meter<f64>) -> Self {
+        Point { x, y, z }
     }
 
     /// Returns the X-coordinate of the point.
@@ -25,6 +43,11 @@ impl Point {
         self.y
     }
 
+    /// Returns the Z-coordinate of the point.
+    pub fn z(&self) -> Meter<f64> {
+        self.z
+    }
+
     /// Sets the X-coordinate of the point to a new value.
     pub fn set_x(&mut self, x: Meter<f64>) {
         self.x = x;
@@ -35,6 +58,11 @@ impl Point {
         self.y = y;
     }
 
+    /// Sets the Z-coordinate of the point to a new value.
+    pub fn set_z(&mut self, z: Meter<f64>) {
+        self.z = z;
+    }
+
     /// Moves the point a certain distance in a given direction.
     ///
     /// # Example
@@ -46,20 +74,26 @@ impl Point {
     /// use mcgen::mc::geometry::*;
     /// use dimensioned::si::*;
     ///
-    /// point = Point::new(1.0 * M, 1.0 * M);
-    /// point.step(Direction::from_angle(0.0), 3.0 * M);
+    /// let mut point = Point::new(1.0 * M, 1.0 * M);
+    /// point.step(&Direction::from_angle(Unitless::new(0.0)), 3.0 * M);
     ///
     /// assert_eq!(point.to_tuple(), (4.0 * M, 1.0 * M));
     /// ```
     pub fn step(&mut self, d: &Direction, length: Meter<f64>) {
         self.x += d.dx() * length;
         self.y += d.dy() * length;
+        self.z += d.dz() * length;
     }
 
-    /// Returns the coordinates of this point as a tuple.
+    /// Returns the X- and Y-coordinate of this point as a tuple.
     pub fn to_tuple(&self) -> (Meter<f64>, Meter<f64>) {
         (self.x, self.y)
     }
+
+    /// Returns all three coordinates of this point as a tuple.
+    pub fn to_triple(&self) -> (Meter<f64>, Meter<f64>, Meter<f64>) {
+        (self.x, self.y, self.z)
+    }
 }
 
 impl From<Point> for (Meter<f64>, Meter<f64>) {
@@ -75,14 +109,21 @@ impl From<(Meter<f64>, Meter<f64>)> for Point {
 }
 
 
-/// Type that describes a direction in 2D-space.
+/// Type that describes a direction in 3D-space.
 ///
 /// `Direction`s are similar to `Point`s, but they are normalized to
-/// a length of `1` and don't carry a physical unit.
+/// a length of `1` and don't carry a physical unit. Directions created
+/// via `Direction::new()` or `Direction::from_angle()` lie in the
+/// plane `z = 0`.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Direction {
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
     dx: Unitless<f64>,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
     dy: Unitless<f64>,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    dz: Unitless<f64>,
 }
 
 impl Direction {
@@ -92,11 +133,35 @@ impl Direction {
     /// Y-coordinate of a 2D vector describing the desired direction.
     /// The returned direction is formed by normalizing the length of
     /// the vector `(dx, dy)`.
-    pub fn new(mut dx: Unitless<f64>, mut dy: Unitless<f64>) -> Self {
-        let len = (dx * dx + dy * dy).sqrt();
+    pub fn new(dx: Unitless<f64>, dy: Unitless<f64>) -> Self {
+        Direction::new_3d(dx, dy, Unitless::new(0.0))
+    }
+
+    /// Creates a new direction from the given 3D vector.
+    ///
+    /// The returned direction is formed by normalizing the length of
+    /// the vector `(dx, dy, dz)`.
+    pub fn new_3d(mut dx: Unitless<f64>, mut dy: Unitless<f64>, mut dz: Unitless<f64>) -> Self {
+        let len = (dx * dx + dy * dy + dz * dz).sqrt();
         dx /= len;
         dy /= len;
-        Direction { dx, dy }
+        dz /= len;
+        Direction { dx, dy, dz }
+    }
+
+    /// Generates a vector pointing in a random direction in 3D-space.
+    ///
+    /// The directions are distributed uniformly over the unit sphere.
+    /// For random directions in the plane `z = 0`, use `rng.gen()`.
+    pub fn random_3d<R: Rng>(rng: &mut R) -> Self {
+        let dz = rng.gen_range(-1.0f64, 1.0f64);
+        let phi = rng.gen_range(0.0, 2.0 * ::std::f64::consts::PI);
+        let rho = (1.0 - dz * dz).sqrt();
+        Direction {
+            dx: Unitless::new(rho * phi.cos()),
+            dy: Unitless::new(rho * phi.sin()),
+            dz: Unitless::new(dz),
+        }
     }
 
     /// Creates a new direction from a given angle.
@@ -107,6 +172,7 @@ impl Direction {
         Direction {
             dx: Unitless::new(angle.cos()),
             dy: Unitless::new(angle.sin()),
+            dz: Unitless::new(0.0),
         }
     }
 
@@ -120,15 +186,61 @@ impl Direction {
         self.dy
     }
 
-    /// Rotates the direction by a given angle.
+    /// Returns the Z-component of the vector describing the direction.
+    pub fn dz(&self) -> Unitless<f64> {
+        self.dz
+    }
+
+    /// Returns the cosine of the angle between two directions.
     ///
-    /// A positive angle rotates the direction counter-clockwise.
+    /// This is the scalar product of both direction vectors.
+    pub fn dot(&self, other: &Direction) -> Unitless<f64> {
+        self.dx * other.dx + self.dy * other.dy + self.dz * other.dz
+    }
+
+    /// Returns the opposite direction.
+    pub fn reversed(&self) -> Self {
+        Direction {
+            dx: -self.dx,
+            dy: -self.dy,
+            dz: -self.dz,
+        }
+    }
+
+    /// Rotates the direction by a given angle around the Z-axis.
+    ///
+    /// A positive angle rotates the direction counter-clockwise. This
+    /// is the 2D way of scattering a direction.
     pub fn rotate(&mut self, angle: Unitless<f64>) {
         let dx = self.dx * angle.cos() - self.dy * angle.sin();
         let dy = self.dx * angle.sin() + self.dy * angle.cos();
         self.dx = dx;
         self.dy = dy;
     }
+
+    /// Deflects the direction by a polar and an azimuthal angle.
+    ///
+    /// `theta` is the angle between the old and the new direction.
+    /// `phi` is the angle around the old direction. For directions in
+    /// the plane `z = 0`, `phi = π/2` is equivalent to `rotate(theta)`
+    /// and `phi = -π/2` to `rotate(-theta)`.
+    pub fn deflect(&mut self, theta: Unitless<f64>, phi: Unitless<f64>) {
+        let (sin_theta, cos_theta) = (theta.sin(), theta.cos());
+        let (sin_phi, cos_phi) = (phi.sin(), phi.cos());
+        let (ux, uy, uz) = (*self.dx, *self.dy, *self.dz);
+        let rho = (1.0 - uz * uz).sqrt();
+        let (dx, dy, dz) = if rho > 1e-10 {
+            (
+                ux * cos_theta + sin_theta * (ux * uz * cos_phi - uy * sin_phi) / rho,
+                uy * cos_theta + sin_theta * (uy * uz * cos_phi + ux * sin_phi) / rho,
+                uz * cos_theta - sin_theta * rho * cos_phi,
+            )
+        } else {
+            // The old direction is parallel to the Z-axis.
+            (sin_theta * cos_phi, sin_theta * sin_phi, uz.signum() * cos_theta)
+        };
+        *self = Direction::new_3d(Unitless::new(dx), Unitless::new(dy), Unitless::new(dz));
+    }
 }
 
 impl Rand for Direction {
@@ -142,3 +254,896 @@ impl Rand for Direction {
         Direction::new(Unitless::new(dx), Unitless::new(dy))
     }
 }
+
+
+/// Type that describes a regular, rectangular grid in 2D-space.
+///
+/// The mesh covers the area between two X- and two Y-coordinates.
+/// It is divided into `nx` times `ny` cells of equal size. Cells are
+/// numbered row by row, i.e. the cell in column `ix` and row `iy` has
+/// the index `iy * nx + ix`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Mesh {
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    x_range: (Meter<f64>, Meter<f64>),
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    y_range: (Meter<f64>, Meter<f64>),
+    nx: usize,
+    ny: usize,
+}
+
+impl Mesh {
+    /// Creates a new mesh with `nx` columns between the X-coordinates
+    /// `x_range` and `ny` rows between the Y-coordinates `y_range`.
+    pub fn new(
+        nx: usize,
+        x_range: (Meter<f64>, Meter<f64>),
+        ny: usize,
+        y_range: (Meter<f64>, Meter<f64>),
+    ) -> Self {
+        Mesh {
+            x_range,
+            y_range,
+            nx,
+            ny,
+        }
+    }
+
+    /// Returns the number of columns and rows of the mesh.
+    pub fn shape(&self) -> (usize, usize) {
+        (self.nx, self.ny)
+    }
+
+    /// Returns the total number of cells of the mesh.
+    pub fn num_cells(&self) -> usize {
+        self.nx * self.ny
+    }
+
+    /// Returns the index of the cell in which `point` lies.
+    ///
+    /// If `point` lies outside of the mesh, `None` is returned.
+    pub fn find_cell(&self, point: &Point) -> Option<usize> {
+        let ix = Self::find_index(point.x(), self.x_range, self.nx)?;
+        let iy = Self::find_index(point.y(), self.y_range, self.ny)?;
+        Some(iy * self.nx + ix)
+    }
+
+    /// Returns the center of the cell with the given index.
+    ///
+    /// # Panics
+    /// This panics if `index` is not less than `self.num_cells()`.
+    pub fn cell_center(&self, index: usize) -> Point {
+        assert!(index < self.num_cells(), "cell index out of range");
+        let (ix, iy) = (index % self.nx, index / self.nx);
+        let (x_low, x_high) = self.x_range;
+        let (y_low, y_high) = self.y_range;
+        let x = x_low + (x_high - x_low) * ((ix as f64 + 0.5) / self.nx as f64);
+        let y = y_low + (y_high - y_low) * ((iy as f64 + 0.5) / self.ny as f64);
+        Point::new(x, y)
+    }
+
+    /// Finds the index of `value` in a range divided into `n` parts.
+    fn find_index(value: Meter<f64>, (low, high): (Meter<f64>, Meter<f64>), n: usize) -> Option<usize> {
+        if value < low || value > high {
+            return None;
+        }
+        let fraction = (value - low) / (high - low);
+        let index = (*fraction.value() * n as f64) as usize;
+        // The upper edge belongs to the last cell.
+        Some(index.min(n - 1))
+    }
+}
+
+
+/// The common trait of all geometric shapes.
+///
+/// Shapes describe the parts of a setup, so that `get_material()` can
+/// be composed from them instead of comparing coordinates by hand.
+/// Two-dimensional shapes describe prisms that extend infinitely in
+/// Z-direction; in 2D experiments, they behave like the respective
+/// planar figure.
+///
+/// # Examples
+///
+/// ```
+/// extern crate mcgen;
+/// extern crate dimensioned;
+///
+/// use mcgen::mc::geometry::*;
+/// use dimensioned::si::*;
+///
+/// let circle = Circle::new(Point::new(0.0 * M, 0.0 * M), 1.0 * M);
+/// let start = Point::new(-3.0 * M, 0.0 * M);
+/// let east = Direction::from_angle(Unitless::new(0.0));
+///
+/// assert!(!circle.contains(&start));
+/// assert_eq!(circle.distance_to_boundary(&start, &east), Some(2.0 * M));
+/// ```
+pub trait Shape {
+    /// Returns `true` if `point` lies inside of the shape.
+    ///
+    /// Points on the boundary count as inside.
+    fn contains(&self, point: &Point) -> bool;
+
+    /// Returns the distance from `point` to the next point where a
+    /// straight line in `direction` crosses the shape's boundary.
+    ///
+    /// Only crossings at a strictly positive distance are considered,
+    /// so a point on the boundary gets the distance to the next one.
+    /// If the line never crosses the boundary, `None` is returned.
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>>;
+
+    /// Combines this shape with `other` into their union.
+    ///
+    /// The result contains all points that lie in either shape.
+    fn union<S: Shape>(self, other: S) -> Union<Self, S>
+    where
+        Self: Sized,
+    {
+        Union::new(self, other)
+    }
+
+    /// Combines this shape with `other` into their intersection.
+    ///
+    /// The result contains all points that lie in both shapes.
+    fn intersect<S: Shape>(self, other: S) -> Intersection<Self, S>
+    where
+        Self: Sized,
+    {
+        Intersection::new(self, other)
+    }
+
+    /// Cuts `other` out of this shape.
+    ///
+    /// The result contains all points that lie in this shape, but not
+    /// in `other`. Points on the boundary of `other` count as inside of
+    /// the result, so that the result is closed like all other shapes.
+    ///
+    /// # Examples
+    ///
+    /// A collimator with a slit:
+    ///
+    /// ```
+    /// extern crate mcgen;
+    /// extern crate dimensioned;
+    ///
+    /// use mcgen::mc::geometry::*;
+    /// use dimensioned::si::*;
+    ///
+    /// let block = Box2D::new((0.0 * M, 1.0 * M), (-1.0 * M, 1.0 * M));
+    /// let slit = Box2D::new((0.0 * M, 1.0 * M), (-0.1 * M, 0.1 * M));
+    /// let collimator = block.subtract(slit);
+    ///
+    /// assert!(collimator.contains(&Point::new(0.5 * M, 0.5 * M)));
+    /// assert!(!collimator.contains(&Point::new(0.5 * M, 0.0 * M)));
+    /// ```
+    fn subtract<S: Shape>(self, other: S) -> Difference<Self, S>
+    where
+        Self: Sized,
+    {
+        Difference::new(self, other)
+    }
+}
+
+impl<S: Shape + ?Sized> Shape for &S {
+    fn contains(&self, point: &Point) -> bool {
+        (**self).contains(point)
+    }
+
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        (**self).distance_to_boundary(point, direction)
+    }
+}
+
+impl<S: Shape + ?Sized> Shape for Box<S> {
+    fn contains(&self, point: &Point) -> bool {
+        (**self).contains(point)
+    }
+
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        (**self).distance_to_boundary(point, direction)
+    }
+}
+
+
+/// A rectangle between two X- and two Y-coordinates.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Box2D {
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    x: (Meter<f64>, Meter<f64>),
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    y: (Meter<f64>, Meter<f64>),
+}
+
+impl Box2D {
+    /// Creates a new rectangle between the given lower and upper X-
+    /// and Y-coordinates.
+    pub fn new(x: (Meter<f64>, Meter<f64>), y: (Meter<f64>, Meter<f64>)) -> Self {
+        Box2D { x, y }
+    }
+
+    /// Returns the lower and upper X-coordinate of the rectangle.
+    pub fn x(&self) -> (Meter<f64>, Meter<f64>) {
+        self.x
+    }
+
+    /// Returns the lower and upper Y-coordinate of the rectangle.
+    pub fn y(&self) -> (Meter<f64>, Meter<f64>) {
+        self.y
+    }
+}
+
+impl Shape for Box2D {
+    fn contains(&self, point: &Point) -> bool {
+        self.x.contains(point.x()) && self.y.contains(point.y())
+    }
+
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        let x = slab(point.x(), direction.dx(), self.x);
+        let y = slab(point.y(), direction.dy(), self.y);
+        first_crossing(&[overlap(x, y)])
+    }
+}
+
+
+/// A disk with a given center and radius.
+///
+/// In 3D, this is an infinitely long cylinder parallel to the Z-axis;
+/// see `Cylinder` for a finite one.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Circle {
+    center: Point,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    radius: Meter<f64>,
+}
+
+impl Circle {
+    /// Creates a new disk around the X- and Y-coordinate of `center`.
+    pub fn new(center: Point, radius: Meter<f64>) -> Self {
+        Circle { center, radius }
+    }
+
+    /// Returns the center of the disk.
+    pub fn center(&self) -> &Point {
+        &self.center
+    }
+
+    /// Returns the radius of the disk.
+    pub fn radius(&self) -> Meter<f64> {
+        self.radius
+    }
+}
+
+impl Shape for Circle {
+    fn contains(&self, point: &Point) -> bool {
+        distance_xy(&self.center, point) <= self.radius
+    }
+
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        first_crossing(&[circle(&self.center, self.radius, point, direction)])
+    }
+}
+
+
+/// A ring between two concentric circles.
+///
+/// In 3D, this is an infinitely long tube parallel to the Z-axis.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Annulus {
+    center: Point,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    inner: Meter<f64>,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    outer: Meter<f64>,
+}
+
+impl Annulus {
+    /// Creates a new ring with the given inner and outer radius.
+    ///
+    /// # Panics
+    /// This panics if `inner` is greater than `outer`.
+    pub fn new(center: Point, inner: Meter<f64>, outer: Meter<f64>) -> Self {
+        assert!(inner <= outer, "inner radius greater than outer radius");
+        Annulus { center, inner, outer }
+    }
+
+    /// Returns the center of the ring.
+    pub fn center(&self) -> &Point {
+        &self.center
+    }
+
+    /// Returns the inner and outer radius of the ring.
+    pub fn radii(&self) -> (Meter<f64>, Meter<f64>) {
+        (self.inner, self.outer)
+    }
+}
+
+impl Shape for Annulus {
+    fn contains(&self, point: &Point) -> bool {
+        (self.inner, self.outer).contains(distance_xy(&self.center, point))
+    }
+
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        first_crossing(&[
+            circle(&self.center, self.inner, point, direction),
+            circle(&self.center, self.outer, point, direction),
+        ])
+    }
+}
+
+
+/// A cuboid between two X-, two Y- and two Z-coordinates.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Box3D {
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    x: (Meter<f64>, Meter<f64>),
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    y: (Meter<f64>, Meter<f64>),
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    z: (Meter<f64>, Meter<f64>),
+}
+
+impl Box3D {
+    /// Creates a new cuboid between the given lower and upper
+    /// coordinates.
+    pub fn new(
+        x: (Meter<f64>, Meter<f64>),
+        y: (Meter<f64>, Meter<f64>),
+        z: (Meter<f64>, Meter<f64>),
+    ) -> Self {
+        Box3D { x, y, z }
+    }
+
+    /// Returns the lower and upper X-coordinate of the cuboid.
+    pub fn x(&self) -> (Meter<f64>, Meter<f64>) {
+        self.x
+    }
+
+    /// Returns the lower and upper Y-coordinate of the cuboid.
+    pub fn y(&self) -> (Meter<f64>, Meter<f64>) {
+        self.y
+    }
+
+    /// Returns the lower and upper Z-coordinate of the cuboid.
+    pub fn z(&self) -> (Meter<f64>, Meter<f64>) {
+        self.z
+    }
+}
+
+impl Shape for Box3D {
+    fn contains(&self, point: &Point) -> bool {
+        self.x.contains(point.x()) && self.y.contains(point.y()) && self.z.contains(point.z())
+    }
+
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        let x = slab(point.x(), direction.dx(), self.x);
+        let y = slab(point.y(), direction.dy(), self.y);
+        let z = slab(point.z(), direction.dz(), self.z);
+        first_crossing(&[overlap(overlap(x, y), z)])
+    }
+}
+
+
+/// A ball with a given center and radius.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Sphere {
+    center: Point,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    radius: Meter<f64>,
+}
+
+impl Sphere {
+    /// Creates a new ball around `center`.
+    pub fn new(center: Point, radius: Meter<f64>) -> Self {
+        Sphere { center, radius }
+    }
+
+    /// Returns the center of the ball.
+    pub fn center(&self) -> &Point {
+        &self.center
+    }
+
+    /// Returns the radius of the ball.
+    pub fn radius(&self) -> Meter<f64> {
+        self.radius
+    }
+}
+
+impl Shape for Sphere {
+    fn contains(&self, point: &Point) -> bool {
+        distance(&self.center, point) <= self.radius
+    }
+
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        first_crossing(&[sphere(&self.center, self.radius, point, direction)])
+    }
+}
+
+
+/// A hollow ball between two concentric spheres.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SphericalShell {
+    center: Point,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    inner: Meter<f64>,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    outer: Meter<f64>,
+}
+
+impl SphericalShell {
+    /// Creates a new shell with the given inner and outer radius.
+    ///
+    /// # Panics
+    /// This panics if `inner` is greater than `outer`.
+    pub fn new(center: Point, inner: Meter<f64>, outer: Meter<f64>) -> Self {
+        assert!(inner <= outer, "inner radius greater than outer radius");
+        SphericalShell { center, inner, outer }
+    }
+
+    /// Returns the center of the shell.
+    pub fn center(&self) -> &Point {
+        &self.center
+    }
+
+    /// Returns the inner and outer radius of the shell.
+    pub fn radii(&self) -> (Meter<f64>, Meter<f64>) {
+        (self.inner, self.outer)
+    }
+}
+
+impl Shape for SphericalShell {
+    fn contains(&self, point: &Point) -> bool {
+        (self.inner, self.outer).contains(distance(&self.center, point))
+    }
+
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        first_crossing(&[
+            sphere(&self.center, self.inner, point, direction),
+            sphere(&self.center, self.outer, point, direction),
+        ])
+    }
+}
+
+
+/// A cylinder parallel to the Z-axis between two Z-coordinates.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Cylinder {
+    base: Circle,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    z: (Meter<f64>, Meter<f64>),
+}
+
+impl Cylinder {
+    /// Creates a new cylinder by extruding `base` from the lower to the
+    /// upper Z-coordinate.
+    pub fn new(base: Circle, z: (Meter<f64>, Meter<f64>)) -> Self {
+        Cylinder { base, z }
+    }
+
+    /// Returns the cross-section of the cylinder.
+    pub fn base(&self) -> &Circle {
+        &self.base
+    }
+
+    /// Returns the lower and upper Z-coordinate of the cylinder.
+    pub fn z(&self) -> (Meter<f64>, Meter<f64>) {
+        self.z
+    }
+}
+
+impl Shape for Cylinder {
+    fn contains(&self, point: &Point) -> bool {
+        self.base.contains(point) && self.z.contains(point.z())
+    }
+
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        let side = circle(&self.base.center, self.base.radius, point, direction);
+        let caps = slab(point.z(), direction.dz(), self.z);
+        first_crossing(&[overlap(side, caps)])
+    }
+}
+
+
+/// The union of two shapes, see `Shape::union()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Union<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A: Shape, B: Shape> Union<A, B> {
+    /// Creates the union of `a` and `b`.
+    pub fn new(a: A, b: B) -> Self {
+        Union { a, b }
+    }
+}
+
+impl<A: Shape, B: Shape> Shape for Union<A, B> {
+    fn contains(&self, point: &Point) -> bool {
+        self.a.contains(point) || self.b.contains(point)
+    }
+
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        combined_crossing(self, &self.a, &self.b, point, direction)
+    }
+}
+
+
+/// The intersection of two shapes, see `Shape::intersect()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Intersection<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A: Shape, B: Shape> Intersection<A, B> {
+    /// Creates the intersection of `a` and `b`.
+    pub fn new(a: A, b: B) -> Self {
+        Intersection { a, b }
+    }
+}
+
+impl<A: Shape, B: Shape> Shape for Intersection<A, B> {
+    fn contains(&self, point: &Point) -> bool {
+        self.a.contains(point) && self.b.contains(point)
+    }
+
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        combined_crossing(self, &self.a, &self.b, point, direction)
+    }
+}
+
+
+/// The difference of two shapes, see `Shape::subtract()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Difference<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A: Shape, B: Shape> Difference<A, B> {
+    /// Creates the shape `a` with `b` cut out of it.
+    pub fn new(a: A, b: B) -> Self {
+        Difference { a, b }
+    }
+}
+
+impl<A: Shape, B: Shape> Shape for Difference<A, B> {
+    fn contains(&self, point: &Point) -> bool {
+        (self.a.contains(point) && !self.b.contains(point)) || on_boundary(&self.a, &self.b, point)
+    }
+
+    fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        combined_crossing(self, &self.a, &self.b, point, direction)
+    }
+}
+
+
+/// Declarative description of an experimental setup.
+///
+/// A geometry consists of an ordered list of parts, each a shape filled
+/// with a material, and a background material that fills the space
+/// outside of all parts. If parts overlap, the first one takes
+/// precedence. An `Experiment` may return it from
+/// `Experiment::geometry()` instead of implementing
+/// `Experiment::get_material()` by hand.
+///
+/// # Examples
+///
+/// ```
+/// extern crate mcgen;
+/// extern crate dimensioned;
+///
+/// use mcgen::mc::geometry::*;
+/// use mcgen::mc::experiment::Material;
+/// use dimensioned::si::*;
+///
+/// let block = Box2D::new((0.0 * M, 1.0 * M), (-1.0 * M, 1.0 * M));
+/// let slit = Box2D::new((0.0 * M, 1.0 * M), (-0.1 * M, 0.1 * M));
+/// let detector = Box2D::new((2.0 * M, 2.5 * M), (-1.0 * M, 1.0 * M));
+/// let geometry = Geometry::new(Material::Air)
+///     .with_part(block.subtract(slit), Material::Absorber)
+///     .with_part(detector, Material::Detector);
+///
+/// let start = Point::new(-1.0 * M, 0.0 * M);
+/// let east = Direction::from_angle(Unitless::new(0.0));
+/// assert_eq!(geometry.get_material(&start), Material::Air);
+/// assert_eq!(geometry.distance_to_boundary(&start, &east), Some(3.0 * M));
+/// ```
+pub struct Geometry {
+    parts: Vec<(Box<dyn Shape + Send + Sync>, Material)>,
+    background: Material,
+}
+
+impl Geometry {
+    /// Creates a new geometry that is filled with `background`.
+    pub fn new(background: Material) -> Self {
+        Geometry {
+            parts: Vec::new(),
+            background,
+        }
+    }
+
+    /// Adds a part made of `material` to the geometry.
+    ///
+    /// The part takes precedence over all parts that are added after
+    /// it.
+    pub fn with_part<S>(mut self, shape: S, material: Material) -> Self
+    where
+        S: Shape + Send + Sync + 'static,
+    {
+        self.push(shape, material);
+        self
+    }
+
+    /// Like `with_part()`, but modifies the geometry in place.
+    pub fn push<S>(&mut self, shape: S, material: Material)
+    where
+        S: Shape + Send + Sync + 'static,
+    {
+        self.parts.push((Box::new(shape), material));
+    }
+
+    /// Returns the number of parts of the geometry.
+    pub fn num_parts(&self) -> usize {
+        self.parts.len()
+    }
+
+    /// Returns the material outside of all parts.
+    pub fn background(&self) -> Material {
+        self.background
+    }
+
+    /// Returns the material at the given location.
+    ///
+    /// This is the material of the first part that contains `location`
+    /// or, if there is none, the background material.
+    pub fn get_material(&self, location: &Point) -> Material {
+        self.parts
+            .iter()
+            .find(|&(shape, _)| shape.contains(location))
+            .map_or(self.background, |&(_, material)| material)
+    }
+
+    /// Returns the distance from `point` to the next place where a
+    /// straight line in `direction` enters a different material.
+    ///
+    /// Boundaries between parts of the same material are skipped. If
+    /// the material never changes, `None` is returned.
+    pub fn distance_to_boundary(&self, point: &Point, direction: &Direction) -> Option<Meter<f64>> {
+        let crossings = |point: &Point| {
+            self.parts
+                .iter()
+                .filter_map(|(shape, _)| shape.distance_to_boundary(point, direction))
+                .fold(None, |min, step| match min {
+                    Some(min) if min < step => Some(min),
+                    _ => Some(step),
+                })
+        };
+        next_change(point, direction, crossings, |point| self.get_material(point))
+    }
+}
+
+
+/// Private function that checks whether `point` lies in `a` and on the
+/// boundary of `b`.
+///
+/// A point is considered to be on the boundary if moving it by
+/// `BOUNDARY_TOLERANCE` along any axis takes it out of `b`.
+fn on_boundary<A: Shape, B: Shape>(a: &A, b: &B, point: &Point) -> bool {
+    if !a.contains(point) || !b.contains(point) {
+        return false;
+    }
+    let (x, y, z) = point.to_triple();
+    let d = BOUNDARY_TOLERANCE * M;
+    let neighbors = [
+        Point::new_3d(x - d, y, z),
+        Point::new_3d(x + d, y, z),
+        Point::new_3d(x, y - d, z),
+        Point::new_3d(x, y + d, z),
+        Point::new_3d(x, y, z - d),
+        Point::new_3d(x, y, z + d),
+    ];
+    neighbors.iter().any(|neighbor| !b.contains(neighbor))
+}
+
+
+/// Private function that finds the next boundary of a combination of
+/// the shapes `a` and `b`.
+///
+/// Every boundary of `combined` is a boundary of `a` or `b`, but not
+/// vice versa, see `next_change()`.
+fn combined_crossing<C, A, B>(
+    combined: &C,
+    a: &A,
+    b: &B,
+    point: &Point,
+    direction: &Direction,
+) -> Option<Meter<f64>>
+where
+    C: Shape,
+    A: Shape,
+    B: Shape,
+{
+    let crossings = |point: &Point| {
+        let next_a = a.distance_to_boundary(point, direction);
+        let next_b = b.distance_to_boundary(point, direction);
+        match (next_a, next_b) {
+            (Some(a), Some(b)) => Some(if a < b { a } else { b }),
+            (next, None) | (None, next) => next,
+        }
+    };
+    next_change(point, direction, crossings, |point| combined.contains(point))
+}
+
+
+/// Private function that walks along a straight line until `state`
+/// changes.
+///
+/// `next_crossing` returns the distance from a point to the next place
+/// where `state` may change. This walks from crossing to crossing
+/// until `state`, evaluated slightly beyond the crossing, differs from
+/// its value slightly beyond `point`. The result is the total distance
+/// walked, or `None` if there are no more crossings.
+fn next_change<T, F, G>(
+    point: &Point,
+    direction: &Direction,
+    mut next_crossing: F,
+    mut state: G,
+) -> Option<Meter<f64>>
+where
+    T: PartialEq,
+    F: FnMut(&Point) -> Option<Meter<f64>>,
+    G: FnMut(&Point) -> T,
+{
+    let mut beyond = |point: &Point| {
+        let mut point = point.clone();
+        point.step(direction, BOUNDARY_TOLERANCE * M);
+        state(&point)
+    };
+    let initial = beyond(point);
+    let mut current = point.clone();
+    let mut travelled = 0.0 * M;
+    loop {
+        let step = next_crossing(&current)?;
+        current.step(direction, step);
+        travelled += step;
+        if beyond(&current) != initial {
+            return Some(travelled);
+        }
+    }
+}
+
+
+/// Private type of the distances, in meters, at which a straight line
+/// enters and leaves a convex shape.
+///
+/// The distances may be negative or infinite. If the line misses the
+/// shape, this is `None`.
+type Interval = Option<(f64, f64)>;
+
+
+/// Private function that returns the smallest finite, strictly positive
+/// endpoint of the given intervals.
+fn first_crossing(intervals: &[Interval]) -> Option<Meter<f64>> {
+    intervals
+        .iter()
+        .filter_map(|&interval| interval)
+        .flat_map(|(enter, leave)| [enter, leave])
+        .filter(|&t| t > 0.0 && t.is_finite())
+        .fold(None, |min: Option<f64>, t| Some(min.map_or(t, |min| min.min(t))))
+        .map(|t| t * M)
+}
+
+
+/// Private function that intersects two intervals.
+fn overlap(a: Interval, b: Interval) -> Interval {
+    let ((a_enter, a_leave), (b_enter, b_leave)) = (a?, b?);
+    let (enter, leave) = (a_enter.max(b_enter), a_leave.min(b_leave));
+    if enter <= leave {
+        Some((enter, leave))
+    } else {
+        None
+    }
+}
+
+
+/// Private function that intersects a line with the slab between the
+/// coordinates `low` and `high` along a single axis.
+///
+/// `position` and `direction` are the respective components of the
+/// line's starting point and direction.
+fn slab(
+    position: Meter<f64>,
+    direction: Unitless<f64>,
+    (low, high): (Meter<f64>, Meter<f64>),
+) -> Interval {
+    let (position, direction) = (*(position / M).value(), *direction.value());
+    let (low, high) = (*(low / M).value(), *(high / M).value());
+    if direction == 0.0 {
+        if low <= position && position <= high {
+            Some((f64::NEG_INFINITY, f64::INFINITY))
+        } else {
+            None
+        }
+    } else {
+        let (t1, t2) = ((low - position) / direction, (high - position) / direction);
+        Some((t1.min(t2), t1.max(t2)))
+    }
+}
+
+
+/// Private function that intersects a line with an infinite cylinder
+/// parallel to the Z-axis.
+fn circle(center: &Point, radius: Meter<f64>, point: &Point, direction: &Direction) -> Interval {
+    let dx = *((point.x() - center.x()) / M).value();
+    let dy = *((point.y() - center.y()) / M).value();
+    let (ux, uy) = (*direction.dx().value(), *direction.dy().value());
+    let radius = *(radius / M).value();
+    let a = ux * ux + uy * uy;
+    if a == 0.0 {
+        // The line is parallel to the axis.
+        return if dx * dx + dy * dy <= radius * radius {
+            Some((f64::NEG_INFINITY, f64::INFINITY))
+        } else {
+            None
+        };
+    }
+    solve_quadratic(a, dx * ux + dy * uy, dx * dx + dy * dy - radius * radius)
+}
+
+
+/// Private function that intersects a line with a sphere.
+fn sphere(center: &Point, radius: Meter<f64>, point: &Point, direction: &Direction) -> Interval {
+    let dx = *((point.x() - center.x()) / M).value();
+    let dy = *((point.y() - center.y()) / M).value();
+    let dz = *((point.z() - center.z()) / M).value();
+    let (ux, uy, uz) = (*direction.dx().value(), *direction.dy().value(), *direction.dz().value());
+    let radius = *(radius / M).value();
+    let a = ux * ux + uy * uy + uz * uz;
+    solve_quadratic(a, dx * ux + dy * uy + dz * uz, dx * dx + dy * dy + dz * dz - radius * radius)
+}
+
+
+/// Private function that solves `a t² + 2 b t + c = 0` for `t`.
+///
+/// If there are no real solutions, `None` is returned.
+fn solve_quadratic(a: f64, b: f64, c: f64) -> Interval {
+    let discriminant = b * b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    Some(((-b - root) / a, (-b + root) / a))
+}
+
+
+/// Private function that returns the distance of two points in the
+/// XY-plane.
+fn distance_xy(a: &Point, b: &Point) -> Meter<f64> {
+    let dx = *((a.x() - b.x()) / M).value();
+    let dy = *((a.y() - b.y()) / M).value();
+    dx.hypot(dy) * M
+}
+
+
+/// Private function that returns the distance of two points.
+fn distance(a: &Point, b: &Point) -> Meter<f64> {
+    let dx = *((a.x() - b.x()) / M).value();
+    let dy = *((a.y() - b.y()) / M).value();
+    let dz = *((a.z() - b.z()) / M).value();
+    (dx * dx + dy * dy + dz * dz).sqrt() * M
+}
+
+
+/// The distance in meters by which points are moved to find out on
+/// which side of a boundary they lie, see `next_change()`.
+const BOUNDARY_TOLERANCE: f64 = 1e-9;
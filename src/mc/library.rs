@@ -0,0 +1,238 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+
+use toml;
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+
+use element;
+use error::Error;
+use super::config::ConfigError;
+use super::material::{ElementCrossSections, MaterialBuilder, MaterialData, StoppingPower};
+
+
+/// Declarative description of a material in a `MaterialLibrary`.
+///
+/// The composition is given either by the chemical formula or by the
+/// mass fractions of the elements, but not both. Elements are named by
+/// their symbols, e.g. `"Pb"`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MaterialDefinition {
+    /// The density in g/cm³.
+    pub density: f64,
+    /// The number of atoms of each element per molecule.
+    #[serde(default)]
+    pub formula: Option<BTreeMap<String, f64>>,
+    /// The mass fraction of each element.
+    ///
+    /// The fractions are normalized, so they need not add up to one.
+    #[serde(default)]
+    pub mass_fractions: Option<BTreeMap<String, f64>>,
+    /// The stopping power for electrons, see `StoppingPower::from_file()`.
+    #[serde(default)]
+    pub stopping_power: Option<PathBuf>,
+}
+
+
+/// A homogeneous material with its composition and interaction data.
+///
+/// In contrast to `Material`, which only tells apart the regions of an
+/// experiment, this describes what a region is made of. It is usually
+/// obtained from a `MaterialLibrary`.
+#[derive(Debug, Clone)]
+pub struct MaterialProperties {
+    name: String,
+    density: KilogramPerMeter3<f64>,
+    composition: Vec<(u32, f64)>,
+    data: MaterialData,
+    stopping_power: Option<StoppingPower>,
+}
+
+impl MaterialProperties {
+    /// Returns the name of the material.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the density of the material.
+    pub fn density(&self) -> KilogramPerMeter3<f64> {
+        self.density
+    }
+
+    /// Returns the atomic number and the mass fraction of each element.
+    ///
+    /// The elements are sorted by atomic number and the fractions add
+    /// up to one.
+    pub fn composition(&self) -> &[(u32, f64)] {
+        &self.composition
+    }
+
+    /// Returns the macroscopic interaction data of the material.
+    pub fn data(&self) -> &MaterialData {
+        &self.data
+    }
+
+    /// Returns the linear attenuation coefficient at the given energy.
+    ///
+    /// This is the total macroscopic cross-section, see
+    /// `MaterialData::total()`.
+    pub fn attenuation(&self, energy: Joule<f64>) -> PerMeter<f64> {
+        self.data.total(energy)
+    }
+
+    /// Returns the stopping power for electrons and positrons, if
+    /// known.
+    pub fn stopping_power(&self) -> Option<&StoppingPower> {
+        self.stopping_power.as_ref()
+    }
+}
+
+
+/// A collection of named materials.
+///
+/// The materials are read from a TOML file with one table per material,
+/// see `MaterialDefinition`. The cross-sections of the elements are read
+/// from a data directory as described for `ElementData`; only the file
+/// `XS.dat` is needed. For example:
+///
+/// ```toml
+/// [water]
+/// density = 1.0
+/// formula = { H = 2, O = 1 }
+/// stopping_power = "data/water_estar.dat"
+///
+/// [nai]
+/// density = 3.67
+/// mass_fractions = { Na = 0.153, I = 0.846, Tl = 0.001 }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MaterialLibrary {
+    materials: BTreeMap<String, MaterialProperties>,
+}
+
+impl MaterialLibrary {
+    /// Creates a new, empty library.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Reads a library from a TOML file.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be read or parsed, if a material
+    /// names an unknown element or gives both or none of `formula` and
+    /// `mass_fractions`, or if any data file cannot be read.
+    pub fn from_file<P, Q>(path: P, data_dir: Q) -> Result<Self, ConfigError>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        let definitions: BTreeMap<String, MaterialDefinition> = toml::from_str(&contents)?;
+        let mut elements = HashMap::new();
+        let mut library = Self::new();
+        for (name, definition) in definitions {
+            let material = load_material(name, &definition, data_dir.as_ref(), &mut elements)?;
+            library.insert(material);
+        }
+        Ok(library)
+    }
+
+    /// Adds a material to the library.
+    ///
+    /// If the library already contains a material of the same name, it
+    /// is replaced and returned.
+    pub fn insert(&mut self, material: MaterialProperties) -> Option<MaterialProperties> {
+        self.materials.insert(material.name.clone(), material)
+    }
+
+    /// Returns the material with the given name, if any.
+    pub fn get(&self, name: &str) -> Option<&MaterialProperties> {
+        self.materials.get(name)
+    }
+
+    /// Returns the names of all materials in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.materials.keys().map(String::as_str)
+    }
+
+    /// Returns the number of materials in the library.
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    /// Returns `true` if the library contains no materials.
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
+    }
+}
+
+
+/// Private function that builds a material from its definition.
+///
+/// The cross-sections of each element are read only once and then
+/// cached in `elements`.
+fn load_material(
+    name: String,
+    definition: &MaterialDefinition,
+    data_dir: &Path,
+    elements: &mut HashMap<u32, ElementCrossSections>,
+) -> Result<MaterialProperties, Error> {
+    let invalid = |message: &str| Error::domain(format!("material {}: {}", name, message));
+    let (ingredients, is_formula) = match (&definition.formula, &definition.mass_fractions) {
+        (Some(formula), None) => (formula, true),
+        (None, Some(fractions)) => (fractions, false),
+        _ => return Err(invalid("expected either formula or mass_fractions")),
+    };
+    if !definition.density.is_finite() || definition.density <= 0.0 {
+        return Err(invalid("density must be positive"));
+    }
+    let density = definition.density * 1000.0 * KG / M3;
+    let mut components = Vec::with_capacity(ingredients.len());
+    for (symbol, &amount) in ingredients {
+        let z = element::atomic_number(symbol)
+            .ok_or_else(|| invalid(&format!("unknown element {}", symbol)))?;
+        if let Entry::Vacant(entry) = elements.entry(z) {
+            let mass = element::atomic_mass(z).expect("known element without mass");
+            let path = data_dir.join(symbol).join("XS.dat");
+            entry.insert(ElementCrossSections::from_file(path, mass)?);
+        }
+        components.push((z, amount));
+    }
+    let mut builder = MaterialBuilder::new().density(density);
+    let formula = components.iter().map(|&(z, n)| (elements[&z].clone(), n)).collect();
+    if is_formula {
+        builder = builder.compound(formula, 1.0);
+    } else {
+        for (element, fraction) in formula {
+            builder = builder.element(element, fraction);
+        }
+    }
+    let data = builder.build()?;
+    // Convert the composition to normalized mass fractions.
+    let mut composition: Vec<(u32, f64)> = data
+        .components()
+        .iter()
+        .zip(&components)
+        .map(|(&(ref element, number_density), &(z, _))| {
+            (z, *(element.atomic_mass() * number_density / density).value())
+        })
+        .collect();
+    composition.sort_by_key(|&(z, _)| z);
+    let stopping_power = match definition.stopping_power {
+        Some(ref path) => Some(StoppingPower::from_file(path)?),
+        None => None,
+    };
+    Ok(MaterialProperties {
+        name,
+        density,
+        composition,
+        data,
+        stopping_power,
+    })
+}
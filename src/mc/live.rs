@@ -0,0 +1,184 @@
+use std::fs::{self, File};
+use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::io::{self, Write, BufWriter};
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+
+use error::Error;
+use time::to_seconds;
+use histogram::Histogram;
+use super::tally::Tally;
+use super::particle::Photon;
+
+
+/// Tally that periodically writes intermediate results to disk.
+///
+/// This allows watching a long simulation converge instead of waiting
+/// for its end. The energy of each detected photon is filled into a
+/// histogram. At most every `interval`, after a history has ended, the
+/// following files in the output directory are replaced:
+/// - `live_spectrum.tsv`: the spectrum of detected photons so far;
+/// - `live_convergence.tsv`: the efficiency and its relative error at
+///   every update so far, see `Progress::efficiency()`;
+/// - `live.gp`: a gnuplot script that plots both files and re-reads
+///   them every few seconds; run it inside the output directory with
+///   `gnuplot live.gp`.
+///
+/// The files are replaced atomically, so readers never see partial
+/// output. Because `Tally` methods cannot fail, the first write error
+/// is kept and returned by `finish()`; no further updates are written
+/// after it.
+pub struct LiveOutput {
+    dir: PathBuf,
+    histogram: Histogram,
+    unit: Joule<f64>,
+    interval: Second<f64>,
+    last_update: Instant,
+    histories: usize,
+    detected: usize,
+    convergence: Vec<(usize, f64, f64)>,
+    error: Option<Error>,
+}
+
+impl LiveOutput {
+    /// Creates a new tally writing to the directory `dir`.
+    ///
+    /// The directory is created if necessary. The histogram's X-axis is
+    /// measured in multiples of `unit`, e.g. `KILO * EV`.
+    ///
+    /// # Errors
+    /// This fails if the directory cannot be created or any file
+    /// cannot be written.
+    pub fn new<P>(
+        dir: P,
+        histogram: Histogram,
+        unit: Joule<f64>,
+        interval: Second<f64>,
+    ) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref().to_owned();
+        fs::create_dir_all(&dir)?;
+        replace_file(&dir.join("live.gp"), |file| file.write_all(GNUPLOT_SCRIPT.as_bytes()))?;
+        let mut live = LiveOutput {
+            dir,
+            histogram,
+            unit,
+            interval,
+            last_update: Instant::now(),
+            histories: 0,
+            detected: 0,
+            convergence: Vec::new(),
+            error: None,
+        };
+        live.update()?;
+        Ok(live)
+    }
+
+    /// Returns the histogram of detected energies.
+    pub fn histogram(&self) -> &Histogram {
+        &self.histogram
+    }
+
+    /// Writes the current state to disk, regardless of the interval.
+    ///
+    /// # Errors
+    /// This fails if any file cannot be written.
+    pub fn update(&mut self) -> Result<(), Error> {
+        self.last_update = Instant::now();
+        if self.histories > 0 {
+            let efficiency = self.detected as f64 / self.histories as f64;
+            let rel_error = if self.detected > 0 {
+                ((1.0 - efficiency) / self.detected as f64).sqrt()
+            } else {
+                1.0
+            };
+            self.convergence.push((self.histories, efficiency, rel_error));
+        }
+        let histogram = &self.histogram;
+        replace_file(&self.dir.join("live_spectrum.tsv"), |file| {
+            writeln!(file, "E\tcounts")?;
+            for (center, count) in histogram.bin_centers().zip(histogram.bin_contents()) {
+                writeln!(file, "{}\t{}", center, count)?;
+            }
+            Ok(())
+        })?;
+        let convergence = &self.convergence;
+        replace_file(&self.dir.join("live_convergence.tsv"), |file| {
+            writeln!(file, "histories\tefficiency\trel. error")?;
+            for &(histories, efficiency, rel_error) in convergence {
+                writeln!(file, "{}\t{:e}\t{:e}", histories, efficiency, rel_error)?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Writes the final state to disk and returns the histogram.
+    ///
+    /// # Errors
+    /// This fails if any update could not be written.
+    pub fn finish(mut self) -> Result<Histogram, Error> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        self.update()?;
+        Ok(self.histogram)
+    }
+}
+
+impl Tally for LiveOutput {
+    fn detect(&mut self, photon: &Photon) {
+        let energy = photon.energy() / self.unit;
+        self.histogram.fill_weighted(*energy.value(), photon.weight());
+        self.detected += 1;
+    }
+
+    fn end_history(&mut self) {
+        self.histories += 1;
+        if self.error.is_some() || to_seconds(self.last_update.elapsed()) < self.interval {
+            return;
+        }
+        if let Err(err) = self.update() {
+            self.error = Some(err);
+        }
+    }
+}
+
+
+/// Private function that atomically replaces the file at `path`.
+///
+/// The contents are written by `write` to a temporary file, which is
+/// then renamed to `path`.
+fn replace_file<F>(path: &Path, write: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+{
+    let temporary = path.with_extension("tmp");
+    {
+        let mut file = BufWriter::new(File::create(&temporary)?);
+        write(&mut file)?;
+        file.flush()?;
+    }
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+
+/// The gnuplot script written by `LiveOutput`.
+const GNUPLOT_SCRIPT: &str = r#"set multiplot layout 2,1
+set logscale y
+set xlabel "E"
+set ylabel "counts"
+plot "live_spectrum.tsv" using 1:2 with steps notitle
+unset logscale y
+set xlabel "histories"
+set ylabel "efficiency"
+plot "live_convergence.tsv" using 1:2:($2 * $3) with yerrorlines notitle
+unset multiplot
+pause 2
+reread
+"#;
@@ -0,0 +1,186 @@
+use std::env;
+use std::fs::File;
+use std::path::Path;
+use std::io::{Read, BufWriter};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json;
+#[cfg(unix)]
+use libc;
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+
+use error::Error;
+
+
+/// Everything needed to trace results back to the run that produced
+/// them.
+///
+/// A manifest records the seed, the version of this crate, checksums
+/// of the configuration and all data files, the wall time of the run,
+/// and the machine it ran on. It is attached to every `RunReport` and
+/// written next to the outputs of the binaries.
+///
+/// Checksums are 64-bit FNV-1a hashes of the file contents, written as
+/// hexadecimal numbers. They are meant to detect changed inputs, not
+/// tampering.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+    /// The seed of the run, if it was seeded.
+    pub seed: Option<u64>,
+    /// The version of this crate.
+    pub version: String,
+    /// The path and checksum of the configuration file, if any.
+    pub config: Option<FileChecksum>,
+    /// The paths and checksums of all data files.
+    pub data_files: Vec<FileChecksum>,
+    /// The start of the run in seconds since the Unix epoch.
+    pub started_at: u64,
+    /// The wall time of the run in seconds.
+    pub wall_time: f64,
+    /// The machine the run was executed on.
+    pub host: HostInfo,
+}
+
+/// The path and checksum of an input file, see `RunManifest`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileChecksum {
+    /// The path as given by the user.
+    pub path: String,
+    /// The FNV-1a hash of the file contents.
+    pub checksum: String,
+}
+
+/// Information about the machine a run was executed on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostInfo {
+    /// The host name, if it can be determined.
+    pub hostname: Option<String>,
+    /// The operating system, e.g. `linux`.
+    pub os: String,
+    /// The CPU architecture, e.g. `x86_64`.
+    pub arch: String,
+    /// The number of threads that can run in parallel.
+    pub threads: usize,
+}
+
+impl RunManifest {
+    /// Creates a manifest for a run that starts now.
+    ///
+    /// Configuration and data files have to be added separately.
+    pub fn new(seed: Option<u64>) -> Self {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        RunManifest {
+            seed,
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            config: None,
+            data_files: Vec::new(),
+            started_at,
+            wall_time: 0.0,
+            host: HostInfo::current(),
+        }
+    }
+
+    /// Records the configuration file of the run.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be read.
+    pub fn set_config<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        self.config = Some(FileChecksum::of_file(path)?);
+        Ok(())
+    }
+
+    /// Records a data file used by the run.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be read.
+    pub fn add_data_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        self.data_files.push(FileChecksum::of_file(path)?);
+        Ok(())
+    }
+
+    /// Records the wall time of the run.
+    pub fn set_wall_time(&mut self, wall_time: Second<f64>) {
+        self.wall_time = *(wall_time / S).value();
+    }
+
+    /// Writes the manifest as JSON to the file at `path`.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be written.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(file, self)
+            .map_err(|err| Error::domain(format!("could not write manifest: {}", err)))
+    }
+}
+
+impl FileChecksum {
+    /// Reads the file at `path` and calculates its checksum.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be read.
+    pub fn of_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let mut contents = Vec::new();
+        File::open(path)?.read_to_end(&mut contents)?;
+        Ok(FileChecksum {
+            path: path.to_string_lossy().into_owned(),
+            checksum: format!("{:016x}", fnv1a(&contents)),
+        })
+    }
+}
+
+impl HostInfo {
+    /// Returns information about the current machine.
+    pub fn current() -> Self {
+        HostInfo {
+            hostname: hostname(),
+            os: env::consts::OS.to_owned(),
+            arch: env::consts::ARCH.to_owned(),
+            threads: ::std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+        }
+    }
+}
+
+
+/// Private function that calculates the 64-bit FNV-1a hash of `bytes`.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+
+/// Private function that returns the host name of this machine.
+#[cfg(unix)]
+fn hostname() -> Option<String> {
+    let mut buffer = [0u8; 256];
+    let pointer = buffer.as_mut_ptr() as *mut libc::c_char;
+    let status = unsafe { libc::gethostname(pointer, buffer.len()) };
+    if status != 0 {
+        return None;
+    }
+    let length = buffer.iter().position(|&byte| byte == 0).unwrap_or(buffer.len());
+    String::from_utf8(buffer[..length].to_vec()).ok()
+}
+
+
+/// Private function that returns the host name of this machine.
+#[cfg(not(unix))]
+fn hostname() -> Option<String> {
+    env::var("COMPUTERNAME").ok()
+}
+
+
+/// The initial value of the FNV-1a hash.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// The multiplier of the FNV-1a hash.
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
@@ -0,0 +1,404 @@
+use std::path::Path;
+
+use rand::Rng;
+
+use dimensioned::si::*;
+use dimensioned::{Dimensionless, Recip};
+use dimensioned::f64prefixes::*;
+
+use error::Error;
+use function::Function;
+use super::experiment::{Event, FreePath};
+
+
+/// Atomic cross-sections of a single chemical element.
+///
+/// The cross-sections describe the interaction of photons with a
+/// single atom of the element. They are given separately for coherent
+/// scattering, incoherent scattering and the photo-effect.
+#[derive(Debug, Clone)]
+pub struct ElementCrossSections {
+    atomic_mass: Kilogram<f64>,
+    coherent: Function<Joule<f64>, Meter2<f64>>,
+    incoherent: Function<Joule<f64>, Meter2<f64>>,
+    photo: Function<Joule<f64>, Meter2<f64>>,
+}
+
+impl ElementCrossSections {
+    /// Creates a new element from its atomic mass and cross-sections.
+    pub fn new(
+        atomic_mass: Kilogram<f64>,
+        coherent: Function<Joule<f64>, Meter2<f64>>,
+        incoherent: Function<Joule<f64>, Meter2<f64>>,
+        photo: Function<Joule<f64>, Meter2<f64>>,
+    ) -> Self {
+        ElementCrossSections {
+            atomic_mass,
+            coherent,
+            incoherent,
+            photo,
+        }
+    }
+
+    /// Reads the cross-sections of an element from a file.
+    ///
+    /// The file must have the format described in
+    /// `Function::multiple_from_file()` with four columns: the photon
+    /// energy in keV, and the coherent, incoherent and photo-effect
+    /// cross-sections in barn.
+    ///
+    /// # Errors
+    /// This function fails if the file cannot be read, any number
+    /// cannot be parsed, or the file contains less than four columns.
+    pub fn from_file<P>(path: P, atomic_mass: Kilogram<f64>) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let mut xsections = Function::<f64>::multiple_from_file(path)?
+            .into_iter()
+            .map(|xsection| xsection.scale(KILO * EV, BARN));
+        let mut next = |name| {
+            xsections
+                .next()
+                .ok_or_else(|| Error::domain(format!("missing {} cross-section", name)))
+        };
+        let coherent = next("coherent")?;
+        let incoherent = next("incoherent")?;
+        let photo = next("photo-effect")?;
+        Ok(Self::new(atomic_mass, coherent, incoherent, photo))
+    }
+
+    /// Returns the mass of a single atom of this element.
+    pub fn atomic_mass(&self) -> Kilogram<f64> {
+        self.atomic_mass
+    }
+
+    /// Returns the coherent scattering cross-section per atom.
+    pub fn coherent(&self, energy: Joule<f64>) -> Meter2<f64> {
+        self.coherent.call(energy)
+    }
+
+    /// Returns the incoherent scattering cross-section per atom.
+    pub fn incoherent(&self, energy: Joule<f64>) -> Meter2<f64> {
+        self.incoherent.call(energy)
+    }
+
+    /// Returns the photo-effect cross-section per atom.
+    pub fn photo(&self, energy: Joule<f64>) -> Meter2<f64> {
+        self.photo.call(energy)
+    }
+
+    /// Returns the total cross-section per atom.
+    pub fn total(&self, energy: Joule<f64>) -> Meter2<f64> {
+        self.coherent(energy) + self.incoherent(energy) + self.photo(energy)
+    }
+}
+
+
+/// Macroscopic interaction data of a homogeneous material.
+///
+/// This type calculates the macroscopic cross-sections of a material
+/// from the atomic cross-sections of its elements, its composition and
+/// its density. The macroscopic cross-section `Sigma` is the sum of the
+/// atomic cross-sections, each weighted by the number density of the
+/// respective atoms. Its reciprocal is the mean free path.
+///
+/// This makes it unnecessary to pre-tabulate the mean free paths of
+/// each material.
+#[derive(Debug, Clone)]
+pub struct MaterialData {
+    components: Vec<(ElementCrossSections, PerMeter3<f64>)>,
+}
+
+impl MaterialData {
+    /// Creates a new material with the given density and composition.
+    ///
+    /// The composition is a list of elements, each paired with the
+    /// number of its atoms per molecule. For example, water would be
+    /// described by `vec![(hydrogen, 2.0), (oxygen, 1.0)]`.
+    pub fn new(density: KilogramPerMeter3<f64>, composition: Vec<(ElementCrossSections, f64)>) -> Self {
+        let molecular_mass = composition
+            .iter()
+            .fold(0.0 * KG, |mass, &(ref element, n)| mass + element.atomic_mass() * n);
+        let molecule_density = density / molecular_mass;
+        let components = composition
+            .into_iter()
+            .map(|(element, n)| (element, molecule_density * n))
+            .collect();
+        MaterialData { components }
+    }
+
+    /// Creates a new material from the mass fractions of its elements.
+    ///
+    /// This is the usual way to describe mixtures like concrete. The
+    /// mass fractions are normalized, so they need not add up to one.
+    pub fn from_mass_fractions(
+        density: KilogramPerMeter3<f64>,
+        composition: Vec<(ElementCrossSections, f64)>,
+    ) -> Self {
+        let total = composition.iter().fold(0.0, |sum, &(_, fraction)| sum + fraction);
+        let components = composition
+            .into_iter()
+            .map(|(element, fraction)| {
+                let number_density = density * (fraction / total) / element.atomic_mass();
+                (element, number_density)
+            })
+            .collect();
+        MaterialData { components }
+    }
+
+    /// Returns the elements of the material with their number
+    /// densities.
+    pub fn components(&self) -> &[(ElementCrossSections, PerMeter3<f64>)] {
+        &self.components
+    }
+
+    /// Returns the macroscopic coherent scattering cross-section.
+    pub fn coherent(&self, energy: Joule<f64>) -> PerMeter<f64> {
+        self.macroscopic(|element| element.coherent(energy))
+    }
+
+    /// Returns the macroscopic incoherent scattering cross-section.
+    pub fn incoherent(&self, energy: Joule<f64>) -> PerMeter<f64> {
+        self.macroscopic(|element| element.incoherent(energy))
+    }
+
+    /// Returns the macroscopic photo-effect cross-section.
+    pub fn photo(&self, energy: Joule<f64>) -> PerMeter<f64> {
+        self.macroscopic(|element| element.photo(energy))
+    }
+
+    /// Returns the total macroscopic cross-section.
+    pub fn total(&self, energy: Joule<f64>) -> PerMeter<f64> {
+        self.macroscopic(|element| element.total(energy))
+    }
+
+    /// Returns the mean free path of photons in this material.
+    pub fn mean_free_path(&self, energy: Joule<f64>) -> Meter<f64> {
+        self.total(energy).recip()
+    }
+
+    /// Returns the free path in a form suitable for
+    /// `Experiment::get_mean_free_path()`.
+    pub fn free_path(&self, energy: Joule<f64>) -> FreePath<f64> {
+        FreePath::Exp(self.mean_free_path(energy))
+    }
+
+    /// Randomly chooses an interaction in a form suitable for
+    /// `Experiment::gen_event()`.
+    ///
+    /// Each interaction is chosen with a probability proportional to
+    /// its macroscopic cross-section. The photo-effect is reported as
+    /// `Event::Absorbed`.
+    pub fn gen_event<R: Rng>(&self, energy: Joule<f64>, rng: &mut R) -> Event {
+        let w_coherent = *(self.coherent(energy) * M).value();
+        let w_incoherent = *(self.incoherent(energy) * M).value();
+        let w_photo = *(self.photo(energy) * M).value();
+        let choice = rng.gen_range(0.0, w_coherent + w_incoherent + w_photo);
+        if choice < w_coherent {
+            Event::CoherentScatter
+        } else if choice < w_coherent + w_incoherent {
+            Event::IncoherentScatter
+        } else {
+            Event::Absorbed
+        }
+    }
+
+    /// Tabulates the macroscopic cross-sections at the given energies.
+    ///
+    /// The result interpolates between the tabulated values and is much
+    /// cheaper to evaluate than `MaterialData` itself if the material
+    /// consists of many elements.
+    ///
+    /// # Panics
+    /// This panics if `energies` is empty or not sorted in ascending
+    /// order, or if any energy lies outside of the domain of the
+    /// elements' cross-sections.
+    pub fn tabulate(&self, energies: &[Joule<f64>]) -> MacroscopicCrossSections {
+        let table = |xsection: &dyn Fn(Joule<f64>) -> PerMeter<f64>| {
+            let mut function = Function::with_capacity(energies.len(), energies[0], xsection(energies[0]));
+            function.extend(energies[1..].iter().map(|&energy| (energy, xsection(energy))));
+            function
+        };
+        MacroscopicCrossSections {
+            coherent: table(&|energy| self.coherent(energy)),
+            incoherent: table(&|energy| self.incoherent(energy)),
+            photo: table(&|energy| self.photo(energy)),
+        }
+    }
+
+    /// Sums up an atomic cross-section over all elements, weighted by
+    /// their number density.
+    fn macroscopic<F>(&self, mut xsection: F) -> PerMeter<f64>
+    where
+        F: FnMut(&ElementCrossSections) -> Meter2<f64>,
+    {
+        self.components
+            .iter()
+            .fold(0.0 / M, |sum, &(ref element, density)| sum + density * xsection(element))
+    }
+}
+
+
+/// Builder for the `MaterialData` of compounds and mixtures.
+///
+/// A material is built from a density and a list of ingredients, each
+/// with a mass fraction. Ingredients may be single elements or
+/// compounds given by their chemical formula. For example, NaI doped
+/// with 0.1% thallium could be described as:
+///
+/// ```ignore
+/// let scintillator = MaterialBuilder::new()
+///     .density(3670.0 * KG / M3)
+///     .compound(vec![(sodium, 1.0), (iodine, 1.0)], 0.999)
+///     .element(thallium, 0.001)
+///     .build()?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MaterialBuilder {
+    density: Option<KilogramPerMeter3<f64>>,
+    components: Vec<(ElementCrossSections, f64)>,
+}
+
+impl MaterialBuilder {
+    /// Creates a new, empty builder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the density of the material.
+    pub fn density(mut self, density: KilogramPerMeter3<f64>) -> Self {
+        self.density = Some(density);
+        self
+    }
+
+    /// Adds an element with the given mass fraction.
+    pub fn element(mut self, element: ElementCrossSections, mass_fraction: f64) -> Self {
+        self.components.push((element, mass_fraction));
+        self
+    }
+
+    /// Adds a compound with the given mass fraction.
+    ///
+    /// The compound is given by its chemical formula, i.e. a list of
+    /// elements, each paired with the number of its atoms per molecule.
+    /// The mass fraction is distributed over the elements according to
+    /// their share of the molecular mass.
+    pub fn compound(mut self, formula: Vec<(ElementCrossSections, f64)>, mass_fraction: f64) -> Self {
+        let molecular_mass = formula
+            .iter()
+            .fold(0.0 * KG, |mass, &(ref element, n)| mass + element.atomic_mass() * n);
+        for (element, n) in formula {
+            let share = *(element.atomic_mass() * n / molecular_mass).value();
+            self.components.push((element, mass_fraction * share));
+        }
+        self
+    }
+
+    /// Builds the material.
+    ///
+    /// The mass fractions are normalized, so they need not add up to
+    /// one.
+    ///
+    /// # Errors
+    /// This fails with `Error::Domain` if no density has been set or no
+    /// ingredient has been added.
+    pub fn build(self) -> Result<MaterialData, Error> {
+        let density = self.density.ok_or_else(|| Error::domain("material density not set"))?;
+        if self.components.is_empty() {
+            return Err(Error::domain("material has no ingredients"));
+        }
+        Ok(MaterialData::from_mass_fractions(density, self.components))
+    }
+}
+
+
+/// Tabulated macroscopic cross-sections of a material.
+///
+/// This is returned by `MaterialData::tabulate()`.
+#[derive(Debug, Clone)]
+pub struct MacroscopicCrossSections {
+    coherent: Function<Joule<f64>, PerMeter<f64>>,
+    incoherent: Function<Joule<f64>, PerMeter<f64>>,
+    photo: Function<Joule<f64>, PerMeter<f64>>,
+}
+
+impl MacroscopicCrossSections {
+    /// Returns the macroscopic coherent scattering cross-section.
+    pub fn coherent(&self) -> &Function<Joule<f64>, PerMeter<f64>> {
+        &self.coherent
+    }
+
+    /// Returns the macroscopic incoherent scattering cross-section.
+    pub fn incoherent(&self) -> &Function<Joule<f64>, PerMeter<f64>> {
+        &self.incoherent
+    }
+
+    /// Returns the macroscopic photo-effect cross-section.
+    pub fn photo(&self) -> &Function<Joule<f64>, PerMeter<f64>> {
+        &self.photo
+    }
+
+    /// Returns the total macroscopic cross-section at the given energy.
+    pub fn total(&self, energy: Joule<f64>) -> PerMeter<f64> {
+        self.coherent.call(energy) + self.incoherent.call(energy) + self.photo.call(energy)
+    }
+
+    /// Returns the mean free path at the given energy.
+    pub fn mean_free_path(&self, energy: Joule<f64>) -> Meter<f64> {
+        self.total(energy).recip()
+    }
+}
+
+
+/// Tabulated stopping power of a material for electrons and positrons.
+///
+/// The stopping power is the mean energy that a charged particle loses
+/// per path length. It is given as a function of the particle's kinetic
+/// energy. Since joules per meter are newtons, the values are expressed
+/// in `Newton`.
+///
+/// This is used by `Experiment::stopping_power()` to track charged
+/// secondary particles, see `ChargedParticle`.
+#[derive(Debug, Clone)]
+pub struct StoppingPower {
+    table: Function<Joule<f64>, Newton<f64>>,
+}
+
+impl StoppingPower {
+    /// Creates a new stopping power from a table of kinetic energies
+    /// and the respective stopping power.
+    pub fn new(table: Function<Joule<f64>, Newton<f64>>) -> Self {
+        StoppingPower { table }
+    }
+
+    /// Reads the stopping power from a file.
+    ///
+    /// The file must have the format described in
+    /// `Function::from_file()` with two columns: the kinetic energy in
+    /// keV, and the total stopping power in keV/cm.
+    ///
+    /// # Errors
+    /// This fails if the file cannot be read or any number cannot be
+    /// parsed.
+    pub fn from_file<P>(path: P) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let table = Function::<f64>::from_file(path)?.scale(KILO * EV, KILO * EV / (CENTI * M));
+        Ok(Self::new(table))
+    }
+
+    /// Returns the table of stopping powers.
+    pub fn table(&self) -> &Function<Joule<f64>, Newton<f64>> {
+        &self.table
+    }
+
+    /// Returns the stopping power at the given kinetic energy.
+    ///
+    /// If `energy` lies outside of the tabulated range, `None` is
+    /// returned.
+    pub fn at(&self, energy: Joule<f64>) -> Option<Newton<f64>> {
+        self.table.try_call(energy).ok()
+    }
+}
@@ -1,10 +1,38 @@
+pub mod scan;
+pub mod live;
+pub mod tally;
+pub mod runner;
 pub mod source;
+pub mod config;
+pub mod events;
+pub mod tracks;
 pub mod geometry;
+pub mod library;
+pub mod manifest;
+pub mod buildup;
+pub mod deadtime;
+pub mod detector;
 pub mod particle;
+pub mod material;
+pub mod importance;
 pub mod experiment;
 
 
+pub use self::scan::*;
+pub use self::live::*;
+pub use self::tally::*;
+pub use self::runner::*;
 pub use self::source::*;
+pub use self::config::*;
+pub use self::events::*;
+pub use self::tracks::*;
 pub use self::geometry::*;
+pub use self::library::*;
+pub use self::manifest::*;
+pub use self::buildup::*;
+pub use self::deadtime::*;
+pub use self::detector::*;
 pub use self::particle::*;
+pub use self::material::*;
+pub use self::importance::*;
 pub use self::experiment::*;
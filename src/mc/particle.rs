@@ -1,7 +1,9 @@
 use dimensioned::si::Joule;
 
 use dimensioned::si::*;
+use dimensioned::Dimensionless;
 
+use constants;
 use mc::geometry::{Point, Direction};
 
 
@@ -13,6 +15,11 @@ use mc::geometry::{Point, Direction};
 /// - a `direction`, and
 /// - an energy.
 ///
+/// Additionally, each photon counts how often it has been scattered,
+/// keeps track of the time since its emission started, and carries a
+/// statistical weight. The weight is `1.0` unless
+/// variance reduction (e.g. an `ImportanceMap`) is used.
+///
 /// The typical lifecycle of a photon is:
 /// 1. It is created by some source.
 /// 2. It travels in a certain direction for a while.
@@ -20,11 +27,14 @@ use mc::geometry::{Point, Direction};
 ///    may either be *scattering*, which may change the photons
 ///    direction and energy, or *absorption*, which ends the photon's
 ///    lifecycle.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Photon {
     location: Point,
     direction: Direction,
     energy: Joule<f64>,
+    scatters: u32,
+    weight: f64,
+    time: Second<f64>,
 }
 
 impl Photon {
@@ -34,6 +44,9 @@ impl Photon {
             location,
             direction,
             energy,
+            scatters: 0,
+            weight: 1.0,
+            time: 0.0 * S,
         }
     }
 
@@ -64,10 +77,75 @@ impl Photon {
         self.energy = energy
     }
 
+    /// Returns the time of the photon.
+    ///
+    /// This is the emission time given by the source plus the time of
+    /// flight, i.e. the path length travelled so far divided by the
+    /// speed of light.
+    pub fn time(&self) -> Second<f64> {
+        self.time
+    }
+
+    /// Set the time of the photon to a new value.
+    ///
+    /// This is used by sources to set the emission time.
+    pub fn set_time(&mut self, time: Second<f64>) {
+        self.time = time
+    }
+
+    /// Returns the statistical weight of the photon.
+    ///
+    /// Each detected photon contributes to a tally proportionally to
+    /// its weight.
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// Set the statistical weight of the photon to a new value.
+    pub fn set_weight(&mut self, weight: f64) {
+        self.weight = weight
+    }
+
+    /// Returns how often the photon has been scattered so far.
+    ///
+    /// A photon that has never been scattered is called *uncollided*.
+    pub fn num_scatters(&self) -> u32 {
+        self.scatters
+    }
+
+    /// Replaces the photon by a secondary photon emitted at its
+    /// location.
+    ///
+    /// This is used e.g. for fluorescence X-rays. The secondary photon
+    /// counts as having scattered once more than the original one.
+    pub fn reemit(&mut self, direction: Direction, energy: Joule<f64>) {
+        self.direction = direction;
+        self.energy = energy;
+        self.scatters += 1;
+    }
+
+    /// Rotates the photon's direction by the given scattering angle.
+    ///
+    /// This also increases the photon's scatter counter by one.
+    pub fn scatter(&mut self, angle: Unitless<f64>) {
+        self.direction.rotate(angle);
+        self.scatters += 1;
+    }
+
+    /// Deflects the photon's direction by a polar and an azimuthal
+    /// angle.
+    ///
+    /// This is the 3D equivalent of `scatter()`. See
+    /// `Direction::deflect()` for the meaning of the angles.
+    pub fn scatter_3d(&mut self, theta: Unitless<f64>, phi: Unitless<f64>) {
+        self.direction.deflect(theta, phi);
+        self.scatters += 1;
+    }
+
     /// Moves the photon for a given length into its current direction.
     ///
-    /// This updates the photon's `location`, but leaves its other
-    /// parameters untouched.
+    /// This updates the photon's `location` and advances its time by
+    /// the time of flight, but leaves its other parameters untouched.
     ///
     /// # Errors
     /// This fails with `Error::WrongDirection` if `length` is negative
@@ -75,6 +153,7 @@ impl Photon {
     pub fn step(&mut self, length: Meter<f64>) -> Result<(), Error> {
         if length > 0.0 * M {
             self.location.step(&self.direction, length);
+            self.time += length / C0;
             Ok(())
         } else {
             Err(Error::WrongDirection)
@@ -109,7 +188,187 @@ impl Photon {
 }
 
 
-/// The error type returned by the moving functions of `Photon`.
+/// The kinds of charged particles, see `ChargedParticle`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Species {
+    /// A negatively charged electron.
+    Electron,
+    /// A positively charged positron, the electron's antiparticle.
+    Positron,
+}
+
+
+/// Type that represents an electron or a positron.
+///
+/// Charged particles are set free when photons interact with the
+/// medium. Unlike photons, they interact with the medium continuously.
+/// They are simulated in the *continuous-slowing-down approximation*:
+/// they move in a straight line and steadily lose energy according to
+/// the stopping power of the material, see `StoppingPower`. Deflections
+/// due to multiple scattering are neglected.
+///
+/// Like photons, charged particles have a `location`, a `direction`, a
+/// kinetic energy, a time and a statistical weight. Secondary particles
+/// inherit the time and weight of the photon that created them.
+#[derive(Debug, Clone)]
+pub struct ChargedParticle {
+    species: Species,
+    location: Point,
+    direction: Direction,
+    energy: Joule<f64>,
+    scatters: u32,
+    weight: f64,
+    time: Second<f64>,
+}
+
+impl ChargedParticle {
+    /// Creates a new particle with the given properties.
+    ///
+    /// `energy` is the kinetic energy of the particle.
+    pub fn new(
+        species: Species,
+        location: Point,
+        direction: Direction,
+        energy: Joule<f64>,
+    ) -> Self {
+        ChargedParticle {
+            species,
+            location,
+            direction,
+            energy,
+            scatters: 0,
+            weight: 1.0,
+            time: 0.0 * S,
+        }
+    }
+
+    /// Creates a new electron with the given properties.
+    pub fn electron(location: Point, direction: Direction, energy: Joule<f64>) -> Self {
+        ChargedParticle::new(Species::Electron, location, direction, energy)
+    }
+
+    /// Creates a new positron with the given properties.
+    pub fn positron(location: Point, direction: Direction, energy: Joule<f64>) -> Self {
+        ChargedParticle::new(Species::Positron, location, direction, energy)
+    }
+
+    /// Creates a secondary particle set free by `photon`.
+    ///
+    /// The particle starts at the photon's location with its time and
+    /// weight. It counts as having scattered once more than the photon.
+    pub fn secondary(
+        species: Species,
+        photon: &Photon,
+        direction: Direction,
+        energy: Joule<f64>,
+    ) -> Self {
+        ChargedParticle {
+            scatters: photon.num_scatters() + 1,
+            weight: photon.weight(),
+            time: photon.time(),
+            ..ChargedParticle::new(species, photon.location().clone(), direction, energy)
+        }
+    }
+
+    /// Returns whether this is an electron or a positron.
+    pub fn species(&self) -> Species {
+        self.species
+    }
+
+    /// Immutably borrows the location of the particle.
+    pub fn location(&self) -> &Point {
+        &self.location
+    }
+
+    /// Immutably borrows the direction of the particle.
+    pub fn direction(&self) -> &Direction {
+        &self.direction
+    }
+
+    /// Returns the kinetic energy of the particle.
+    pub fn energy(&self) -> Joule<f64> {
+        self.energy
+    }
+
+    /// Set the kinetic energy of the particle to a new value.
+    pub fn set_energy(&mut self, energy: Joule<f64>) {
+        self.energy = energy
+    }
+
+    /// Returns the time of the particle.
+    pub fn time(&self) -> Second<f64> {
+        self.time
+    }
+
+    /// Returns the statistical weight of the particle.
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// Set the statistical weight of the particle to a new value.
+    pub fn set_weight(&mut self, weight: f64) {
+        self.weight = weight
+    }
+
+    /// Returns how often the particle and the photons before it have
+    /// been scattered.
+    ///
+    /// This is passed on to photons emitted by this particle, see
+    /// `emit()`.
+    pub fn num_scatters(&self) -> u32 {
+        self.scatters
+    }
+
+    /// Creates a photon emitted by this particle.
+    ///
+    /// This is used e.g. for the annihilation photons of a positron.
+    /// The photon starts at the particle's location with its time and
+    /// weight and counts as having scattered as often as the particle.
+    pub fn emit(&self, direction: Direction, energy: Joule<f64>) -> Photon {
+        Photon {
+            location: self.location.clone(),
+            direction,
+            energy,
+            scatters: self.scatters,
+            weight: self.weight,
+            time: self.time,
+        }
+    }
+
+    /// Returns the speed of the particle.
+    ///
+    /// This takes relativistic effects into account, so the speed is
+    /// always less than the speed of light.
+    pub fn speed(&self) -> MeterPerSecond<f64> {
+        let gamma = 1.0 + *(self.energy / constants::electron_rest_energy()).value();
+        let beta = (1.0 - 1.0 / (gamma * gamma)).sqrt();
+        beta * C0
+    }
+
+    /// Moves the particle for a given length into its current
+    /// direction.
+    ///
+    /// This updates the particle's `location` and advances its time by
+    /// the time of flight at its current speed. Its energy is left
+    /// untouched.
+    ///
+    /// # Errors
+    /// This fails with `Error::WrongDirection` if `length` is negative
+    /// or zero.
+    pub fn step(&mut self, length: Meter<f64>) -> Result<(), Error> {
+        if length > 0.0 * M {
+            self.location.step(&self.direction, length);
+            self.time += length / self.speed();
+            Ok(())
+        } else {
+            Err(Error::WrongDirection)
+        }
+    }
+}
+
+
+/// The error type returned by the moving functions of `Photon` and
+/// `ChargedParticle`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Error {
     WrongDirection,
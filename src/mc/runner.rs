@@ -0,0 +1,830 @@
+use rand::Rng;
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+
+use time::ProgressTimer;
+use rng::StreamRng;
+use logging;
+use statistics::Statistics;
+use super::tally::Tally;
+use super::geometry::Point;
+use super::particle::Photon;
+use super::manifest::RunManifest;
+use super::experiment::{Experiment, Material, Event, TransportError, simulate_primary};
+
+
+/// A snapshot of the state of a simulation run.
+///
+/// This type is passed to the progress callback of `Runner` and is
+/// also returned at the end of `Runner::run()`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Progress {
+    histories: usize,
+    total_histories: usize,
+    detected: f64,
+    squares: f64,
+    hits: usize,
+    failed: usize,
+    last_error: Option<TransportError>,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    elapsed: Second<f64>,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    eta: Option<Second<f64>>,
+}
+
+impl Progress {
+    /// Private function that creates the progress of a new run.
+    fn new(total_histories: usize) -> Self {
+        Progress {
+            histories: 0,
+            total_histories,
+            detected: 0.0,
+            squares: 0.0,
+            hits: 0,
+            failed: 0,
+            last_error: None,
+            elapsed: 0.0 * S,
+            eta: None,
+        }
+    }
+
+    /// Returns the number of histories that have been simulated so
+    /// far.
+    pub fn histories(&self) -> usize {
+        self.histories
+    }
+
+    /// Returns the number of histories that the run will simulate in
+    /// total.
+    pub fn total_histories(&self) -> usize {
+        self.total_histories
+    }
+
+    /// Returns the total weight of the photons that have been detected
+    /// so far.
+    ///
+    /// Without variance reduction, every photon has a weight of one and
+    /// this is the number of detected photons.
+    pub fn detected(&self) -> f64 {
+        self.detected
+    }
+
+    /// Returns the number of histories that have failed so far.
+    ///
+    /// A history fails if the transport of a photon returns a
+    /// `TransportError`. Failed histories are skipped, i.e. they are
+    /// not taken into account by `efficiency()`.
+    pub fn failed(&self) -> usize {
+        self.failed
+    }
+
+    /// Returns the error of the most recent failed history, if any.
+    pub fn last_error(&self) -> Option<TransportError> {
+        self.last_error
+    }
+
+    /// Returns the detected weight per history.
+    ///
+    /// Without variance reduction and secondary photons, this is the
+    /// fraction of histories that ended in the detector. Otherwise, it
+    /// is the expected number of detected photons per source photon,
+    /// which may exceed one. Failed histories are not counted. If no
+    /// history has been simulated successfully yet, this returns
+    /// `None`.
+    pub fn efficiency(&self) -> Option<f64> {
+        let histories = self.histories - self.failed;
+        if histories > 0 {
+            Some(self.detected / histories as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the relative statistical error of the efficiency.
+    ///
+    /// The score of a history is the total weight detected in it. The
+    /// error is the standard error of the mean score, divided by the
+    /// mean. Without variance reduction, each history is a Bernoulli
+    /// trial and this reduces to `sqrt((1 - p) / k)`, where `p` is the
+    /// efficiency and `k` the number of detected photons. If nothing
+    /// has been detected yet, this returns `None`.
+    pub fn relative_error(&self) -> Option<f64> {
+        if self.detected > 0.0 {
+            let histories = (self.histories - self.failed) as f64;
+            let mean = self.detected / histories;
+            let variance = (self.squares / histories - mean * mean).max(0.0);
+            Some((variance / histories).sqrt() / mean)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the time that has passed since the run started.
+    pub fn elapsed(&self) -> Second<f64> {
+        self.elapsed
+    }
+
+    /// Returns the estimated time until the run is finished.
+    ///
+    /// The estimate assumes that all histories take the same time on
+    /// average. If no history has been simulated yet, this returns
+    /// `None`.
+    pub fn eta(&self) -> Option<Second<f64>> {
+        self.eta
+    }
+}
+
+
+/// Type that executes a given number of histories of an experiment.
+///
+/// Simulation runs can take a long time. A runner can periodically
+/// invoke a callback with the current `Progress` of the run, e.g. to
+/// print the number of detected photons and an estimate of the
+/// remaining time.
+pub struct Runner<'a, E>
+where
+    E: 'a + Experiment,
+{
+    experiment: &'a E,
+    interval: Option<usize>,
+    callback: Option<Callback<'a>>,
+}
+
+
+/// The type of the progress callback of a `Runner`.
+type Callback<'a> = Box<dyn FnMut(&Progress) + 'a>;
+
+impl<'a, E> Runner<'a, E>
+where
+    E: 'a + Experiment,
+{
+    /// Creates a new runner for the given experiment.
+    ///
+    /// By default, the runner reports its progress after every
+    /// percent of the total number of histories.
+    pub fn new(experiment: &'a E) -> Self {
+        Runner {
+            experiment,
+            interval: None,
+            callback: None,
+        }
+    }
+
+    /// Sets the number of histories between two progress reports.
+    pub fn report_every(mut self, interval: usize) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Sets the callback that is invoked to report progress.
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&Progress) + 'a,
+    {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Simulates `n_histories` histories of the experiment.
+    ///
+    /// Histories that fail with a `TransportError` do not abort the
+    /// run; they are counted by `Progress::failed()` instead. Each
+    /// photon detected in a successful history is passed to
+    /// `on_detect`; photons of failed histories are discarded along
+    /// with their history. `rng` is used as
+    /// a source of randomness. The return value describes the final
+    /// state of the run.
+    ///
+    /// # Examples
+    ///
+    /// In this experiment, every photon is detected, but its
+    /// photo-electron has a negative stopping power. Thus, every
+    /// history fails after its photon has been detected.
+    ///
+    /// ```
+    /// extern crate mcgen;
+    /// extern crate dimensioned;
+    /// extern crate rand;
+    ///
+    /// use mcgen::{Function, StreamRng};
+    /// use mcgen::mc::*;
+    /// use dimensioned::si::*;
+    /// use rand::Rng;
+    ///
+    /// struct Faulty {
+    ///     source: EastPointingSource,
+    ///     stopping_power: StoppingPower,
+    /// }
+    ///
+    /// impl Experiment for Faulty {
+    ///     type Source = EastPointingSource;
+    ///
+    ///     fn source(&self) -> &EastPointingSource {
+    ///         &self.source
+    ///     }
+    ///
+    ///     fn x_start(&self) -> Meter<f64> {
+    ///         0.0 * M
+    ///     }
+    ///
+    ///     fn get_material(&self, _: &Point) -> Material {
+    ///         Material::Detector
+    ///     }
+    ///
+    ///     fn get_mean_free_path(&self, _: Material, _: Joule<f64>) -> FreePath<f64> {
+    ///         FreePath::Fix(0.01 * M)
+    ///     }
+    ///
+    ///     fn stopping_power(&self, _: Material) -> Option<&StoppingPower> {
+    ///         Some(&self.stopping_power)
+    ///     }
+    ///
+    ///     fn gen_event<R: Rng>(&self, _: Material, _: Joule<f64>, _: &mut R) -> Event {
+    ///         Event::Absorbed
+    ///     }
+    ///
+    ///     fn gen_coherent_scatter<R: Rng>(&self, _: Material, _: Joule<f64>, _: &mut R) -> Unitless<f64> {
+    ///         unreachable!()
+    ///     }
+    ///
+    ///     fn gen_incoherent_scatter<R: Rng>(
+    ///         &self,
+    ///         _: Material,
+    ///         _: Joule<f64>,
+    ///         _: &mut R,
+    ///     ) -> (Unitless<f64>, Joule<f64>) {
+    ///         unreachable!()
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut table = Function::new(0.0 * J, -1.0 * N);
+    ///     table.push(1e-12 * J, -1.0 * N);
+    ///     let exp = Faulty {
+    ///         source: EastPointingSource::new(Point::new(-1.0 * M, 0.0 * M), 662e3 * EV),
+    ///         stopping_power: StoppingPower::new(table),
+    ///     };
+    ///     let mut rng = StreamRng::new(42).stream(0);
+    ///     let mut detected = 0;
+    ///     let progress = Runner::new(&exp).run(10, &mut rng, |_| detected += 1);
+    ///     assert_eq!(progress.failed(), 10);
+    ///     assert_eq!(progress.detected(), 0.0);
+    ///     assert_eq!(detected, 0);
+    /// }
+    /// ```
+    pub fn run<R, F>(&mut self, n_histories: usize, rng: &mut R, on_detect: F) -> Progress
+    where
+        R: Rng,
+        F: FnMut(Photon),
+    {
+        self.run_with_tally(n_histories, rng, &mut (), on_detect)
+    }
+
+    /// Like `run()`, but additionally reports to a tally.
+    ///
+    /// Like detected photons, the notifications of a history are only
+    /// passed on to `tally` once the history has ended successfully.
+    /// Thus, the tally never sees failed histories. See `Tally` for
+    /// more information.
+    pub fn run_with_tally<R, T, F>(
+        &mut self,
+        n_histories: usize,
+        rng: &mut R,
+        tally: &mut T,
+        mut on_detect: F,
+    ) -> Progress
+    where
+        R: Rng,
+        T: Tally,
+        F: FnMut(Photon),
+    {
+        log_info!("starting run of {} histories", n_histories);
+        let interval = self.interval_for(n_histories);
+        let mut timer = ProgressTimer::new(n_histories);
+        let mut progress = Progress::new(n_histories);
+        for i in 1..n_histories + 1 {
+            self.simulate_one(&mut progress, rng, tally, &mut on_detect);
+            if i % interval == 0 || i == n_histories {
+                self.report(&mut progress, &mut timer);
+            }
+        }
+        self.finish(&mut progress, &timer);
+        progress
+    }
+
+    /// Like `run()`, but gives each history its own random-number
+    /// stream.
+    ///
+    /// History `i` (counting from zero) uses `streams.stream(i)`. This
+    /// makes the result of each history independent of all others, so
+    /// that a run split across several threads or processes gives the
+    /// same result as a single run.
+    pub fn run_streams<F>(&mut self, n_histories: usize, streams: &StreamRng, on_detect: F) -> Progress
+    where
+        F: FnMut(Photon),
+    {
+        self.run_streams_with_tally(n_histories, streams, &mut (), on_detect)
+    }
+
+    /// Like `run_streams()`, but additionally reports to a tally.
+    ///
+    /// See `Tally` for more information.
+    pub fn run_streams_with_tally<T, F>(
+        &mut self,
+        n_histories: usize,
+        streams: &StreamRng,
+        tally: &mut T,
+        mut on_detect: F,
+    ) -> Progress
+    where
+        T: Tally,
+        F: FnMut(Photon),
+    {
+        log_info!("starting run of {} histories", n_histories);
+        let interval = self.interval_for(n_histories);
+        let mut timer = ProgressTimer::new(n_histories);
+        let mut progress = Progress::new(n_histories);
+        for i in 1..n_histories + 1 {
+            let mut rng = streams.stream(i as u64 - 1);
+            self.simulate_one(&mut progress, &mut rng, tally, &mut on_detect);
+            if i % interval == 0 || i == n_histories {
+                self.report(&mut progress, &mut timer);
+            }
+        }
+        self.finish(&mut progress, &timer);
+        progress
+    }
+
+    /// Simulates histories until the efficiency is known precisely
+    /// enough.
+    ///
+    /// The run stops as soon as `Progress::relative_error()` drops to
+    /// `precision` or below. This is checked after every
+    /// `report_every()` histories, or every 1000 histories by default.
+    /// To avoid stopping on a lucky streak, photons must have been
+    /// detected in at least ten histories.
+    ///
+    /// Because the total number of histories is not known beforehand,
+    /// the `Progress` passed to the callback always reports it as equal
+    /// to the number of histories simulated so far.
+    ///
+    /// Note that this never returns if no photon can reach the
+    /// detector.
+    pub fn run_until<R, F>(&mut self, precision: f64, rng: &mut R, mut on_detect: F) -> Progress
+    where
+        R: Rng,
+        F: FnMut(Photon),
+    {
+        self.run_until_with(precision, |runner, progress| {
+            runner.simulate_one(progress, rng, &mut (), &mut on_detect);
+        })
+    }
+
+    /// Like `run_until()`, but gives each history its own
+    /// random-number stream.
+    ///
+    /// History `i` (counting from zero) uses `streams.stream(i)`, see
+    /// `run_streams()`. Because the run is checked for convergence
+    /// after a fixed number of histories, the same `streams` always
+    /// give the same result.
+    pub fn run_streams_until<F>(
+        &mut self,
+        precision: f64,
+        streams: &StreamRng,
+        mut on_detect: F,
+    ) -> Progress
+    where
+        F: FnMut(Photon),
+    {
+        self.run_until_with(precision, |runner, progress| {
+            let mut rng = streams.stream(progress.histories as u64);
+            runner.simulate_one(progress, &mut rng, &mut (), &mut on_detect);
+        })
+    }
+
+    /// Simulates histories until `n_detected` photons have been
+    /// detected, giving each history its own random-number stream.
+    ///
+    /// History `i` (counting from zero) uses `streams.stream(i)`, see
+    /// `run_streams()`. The run additionally reports to a tally, see
+    /// `Tally`. Like in `run_until()`, the end of the run is checked
+    /// after every `report_every()` histories, or every 1000 histories
+    /// by default; thus, slightly more than `n_detected` photons may be
+    /// detected. With variance reduction, the total weight of detected
+    /// photons is counted instead, see `Progress::detected()`.
+    ///
+    /// Note that this never returns if no photon can reach the
+    /// detector.
+    pub fn run_streams_until_detected<T, F>(
+        &mut self,
+        n_detected: usize,
+        streams: &StreamRng,
+        tally: &mut T,
+        mut on_detect: F,
+    ) -> Progress
+    where
+        T: Tally,
+        F: FnMut(Photon),
+    {
+        log_info!("starting run until {} photons are detected", n_detected);
+        let is_done = |progress: &Progress| progress.detected >= n_detected as f64;
+        self.run_until_done(is_done, |runner, progress| {
+            let mut rng = streams.stream(progress.histories as u64);
+            runner.simulate_one(progress, &mut rng, tally, &mut on_detect);
+        })
+    }
+
+    /// Private method that implements `run_until()` and
+    /// `run_streams_until()`.
+    ///
+    /// `simulate_one` must simulate a single history and update the
+    /// progress accordingly.
+    fn run_until_with<S>(&mut self, precision: f64, simulate_one: S) -> Progress
+    where
+        S: FnMut(&Self, &mut Progress),
+    {
+        log_info!("starting run until a relative error of {}", precision);
+        let is_done = |progress: &Progress| {
+            let is_precise = progress
+                .relative_error()
+                .is_some_and(|error| error <= precision);
+            progress.hits >= 10 && is_precise
+        };
+        self.run_until_done(is_done, simulate_one)
+    }
+
+    /// Private method that simulates histories until `is_done` returns
+    /// `true`.
+    ///
+    /// `is_done` is checked after every `report_every()` histories, or
+    /// every 1000 histories by default. `simulate_one` must simulate a
+    /// single history and update the progress accordingly.
+    fn run_until_done<D, S>(&mut self, is_done: D, mut simulate_one: S) -> Progress
+    where
+        D: Fn(&Progress) -> bool,
+        S: FnMut(&Self, &mut Progress),
+    {
+        let interval = self.interval.unwrap_or(1000).max(1);
+        let mut timer = ProgressTimer::new(0);
+        let mut progress = Progress::new(0);
+        loop {
+            for _ in 0..interval {
+                simulate_one(self, &mut progress);
+            }
+            progress.total_histories = progress.histories;
+            self.report(&mut progress, &mut timer);
+            if is_done(&progress) {
+                self.finish(&mut progress, &timer);
+                return progress;
+            }
+        }
+    }
+
+    /// Private method that returns the number of histories between two
+    /// progress reports for a run of `n_histories` histories.
+    fn interval_for(&self, n_histories: usize) -> usize {
+        match self.interval {
+            Some(interval) => interval.max(1),
+            None => (n_histories / 100).max(1),
+        }
+    }
+
+    /// Private method that simulates a single history and updates
+    /// `progress` accordingly.
+    fn simulate_one<R, T, F>(&self, progress: &mut Progress, rng: &mut R, tally: &mut T, on_detect: &mut F)
+    where
+        R: Rng,
+        T: Tally,
+        F: FnMut(Photon),
+    {
+        // Detected photons and tally notifications are only passed on
+        // once the history has ended successfully.
+        let mut buffer = HistoryBuffer::default();
+        match simulate_primary(self.experiment, rng, &mut buffer) {
+            Ok(photons) => {
+                buffer.replay(tally);
+                if !photons.is_empty() {
+                    let score: f64 = photons.iter().map(Photon::weight).sum();
+                    progress.detected += score;
+                    progress.squares += score * score;
+                    progress.hits += 1;
+                }
+                for photon in photons {
+                    on_detect(photon);
+                }
+            },
+            Err(err) => {
+                log_debug!("history {} failed: {}", progress.histories, err);
+                progress.failed += 1;
+                progress.last_error = Some(err);
+            },
+        }
+        progress.histories += 1;
+    }
+
+    /// Private method that updates the elapsed and the estimated
+    /// remaining time, and invokes the progress callback.
+    fn report(&mut self, progress: &mut Progress, timer: &mut ProgressTimer) {
+        timer.tick(progress.histories);
+        progress.elapsed = timer.elapsed();
+        progress.eta = timer.eta();
+        log_debug!(
+            "{} of {} histories, {} detected, {} failed",
+            progress.histories,
+            progress.total_histories,
+            progress.detected,
+            progress.failed
+        );
+        if let Some(ref mut callback) = self.callback {
+            callback(progress);
+        }
+    }
+
+    /// Private method that sets the final elapsed time of a run and
+    /// logs its results.
+    fn finish(&self, progress: &mut Progress, timer: &ProgressTimer) {
+        progress.elapsed = timer.elapsed();
+        log_info!(
+            "finished run of {} histories in {:.3} s: {} detected, {} failed",
+            progress.histories,
+            *(progress.elapsed / S).value(),
+            progress.detected,
+            progress.failed
+        );
+        logging::log_statistics();
+    }
+}
+
+
+/// Private tally that records all notifications of a history.
+///
+/// `Runner` simulates each history into this buffer and only passes the
+/// notifications on to the actual tally once the history has ended
+/// successfully.
+#[derive(Debug, Default)]
+struct HistoryBuffer {
+    notifications: Vec<Notification>,
+}
+
+/// Private type of the notifications recorded by `HistoryBuffer`.
+#[derive(Debug)]
+enum Notification {
+    Deposit(Material, Point, Joule<f64>, f64),
+    Start(Photon),
+    Split(Photon, usize),
+    StartCopy(Photon),
+    Step(Photon),
+    Interact(Material, Photon, Event),
+    Detect(Photon),
+}
+
+impl HistoryBuffer {
+    /// Passes all recorded notifications on to `tally` as one history.
+    fn replay<T: Tally>(&mut self, tally: &mut T) {
+        tally.begin_history();
+        for notification in self.notifications.drain(..) {
+            match notification {
+                Notification::Deposit(material, location, energy, weight) => {
+                    tally.deposit(material, &location, energy, weight);
+                },
+                Notification::Start(photon) => tally.start(&photon),
+                Notification::Split(photon, copies) => tally.split(&photon, copies),
+                Notification::StartCopy(photon) => tally.start_copy(&photon),
+                Notification::Step(photon) => tally.step(&photon),
+                Notification::Interact(material, photon, event) => {
+                    tally.interact(material, &photon, event);
+                },
+                Notification::Detect(photon) => tally.detect(&photon),
+            }
+        }
+        tally.end_history();
+    }
+}
+
+impl Tally for HistoryBuffer {
+    fn begin_history(&mut self) {
+        self.notifications.clear();
+    }
+
+    fn deposit(&mut self, material: Material, location: &Point, energy: Joule<f64>, weight: f64) {
+        self.notifications
+            .push(Notification::Deposit(material, location.clone(), energy, weight));
+    }
+
+    fn start(&mut self, photon: &Photon) {
+        self.notifications.push(Notification::Start(photon.clone()));
+    }
+
+    fn split(&mut self, photon: &Photon, copies: usize) {
+        self.notifications.push(Notification::Split(photon.clone(), copies));
+    }
+
+    fn start_copy(&mut self, photon: &Photon) {
+        self.notifications.push(Notification::StartCopy(photon.clone()));
+    }
+
+    fn step(&mut self, photon: &Photon) {
+        self.notifications.push(Notification::Step(photon.clone()));
+    }
+
+    fn interact(&mut self, material: Material, photon: &Photon, event: Event) {
+        self.notifications
+            .push(Notification::Interact(material, photon.clone(), event));
+    }
+
+    fn detect(&mut self, photon: &Photon) {
+        self.notifications.push(Notification::Detect(photon.clone()));
+    }
+}
+
+
+/// The result of `Experiment::run()` and `Experiment::run_until()`.
+///
+/// This contains the final `Progress` of the run, the energy and weight
+/// of every detected photon, and the `RunManifest` of the run. From the
+/// photons, the energy spectrum can be calculated with arbitrary
+/// binning.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RunReport {
+    progress: Progress,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    photons: Vec<(Joule<f64>, f64)>,
+    manifest: RunManifest,
+}
+
+impl RunReport {
+    /// Simulates `n_histories` histories of `exp`.
+    ///
+    /// The run is reproducible: the same `seed` always gives the same
+    /// result.
+    pub fn simulate<E: Experiment>(exp: &E, n_histories: usize, seed: u64) -> Self {
+        let mut photons = Vec::new();
+        let mut manifest = RunManifest::new(Some(seed));
+        let streams = StreamRng::new(seed);
+        let progress = Runner::new(exp).run_streams(n_histories, &streams, |photon| {
+            photons.push((photon.energy(), photon.weight()));
+        });
+        manifest.set_wall_time(progress.elapsed());
+        RunReport {
+            progress,
+            photons,
+            manifest,
+        }
+    }
+
+    /// Simulates `exp` until its efficiency is known with a relative
+    /// error of `precision`.
+    ///
+    /// Like `simulate()`, the run is reproducible. See
+    /// `Runner::run_streams_until()` for details.
+    pub fn simulate_until<E: Experiment>(exp: &E, precision: f64, seed: u64) -> Self {
+        let mut photons = Vec::new();
+        let mut manifest = RunManifest::new(Some(seed));
+        let streams = StreamRng::new(seed);
+        let progress = Runner::new(exp).run_streams_until(precision, &streams, |photon| {
+            photons.push((photon.energy(), photon.weight()));
+        });
+        manifest.set_wall_time(progress.elapsed());
+        RunReport {
+            progress,
+            photons,
+            manifest,
+        }
+    }
+
+    /// Returns the final state of the run.
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    /// Returns the energy and weight of each detected photon.
+    pub fn photons(&self) -> &[(Joule<f64>, f64)] {
+        &self.photons
+    }
+
+    /// Returns the manifest of the run.
+    pub fn manifest(&self) -> &RunManifest {
+        &self.manifest
+    }
+
+    /// Returns the manifest of the run for modification.
+    ///
+    /// The experiment cannot know which files it has been read from, so
+    /// the caller should add them with `RunManifest::set_config()` and
+    /// `RunManifest::add_data_file()`.
+    pub fn manifest_mut(&mut self) -> &mut RunManifest {
+        &mut self.manifest
+    }
+
+    /// Returns the energy spectrum of detected photons.
+    ///
+    /// The range from `low` to `high` is divided into `nbins` bins of
+    /// equal width. For each bin, the detected weight per source
+    /// photon and its statistical error are calculated. Photons
+    /// outside of the range are ignored. If `nbins` is zero, the
+    /// spectrum is empty. If no history has been simulated
+    /// successfully, all bins are zero.
+    pub fn spectrum(&self, nbins: usize, low: Joule<f64>, high: Joule<f64>) -> Vec<SpectrumBin> {
+        if nbins == 0 {
+            return Vec::new();
+        }
+        let width = (high - low) / nbins as f64;
+        let mut sums = vec![(0.0, 0.0); nbins];
+        for &(energy, weight) in &self.photons {
+            let index = *((energy - low) / width).value();
+            if energy >= low && index < nbins as f64 {
+                let sum = &mut sums[index as usize];
+                sum.0 += weight;
+                sum.1 += weight * weight;
+            }
+        }
+        // Without successful histories, nothing has been detected and
+        // all sums are zero.
+        let histories = (self.progress.histories - self.progress.failed).max(1) as f64;
+        sums.into_iter()
+            .enumerate()
+            .map(|(i, (sum, sum_of_squares))| SpectrumBin {
+                energy: low + width * (i as f64 + 0.5),
+                value: sum / histories,
+                error: sum_of_squares.sqrt() / histories,
+            })
+            .collect()
+    }
+}
+
+
+/// A single bin of the spectrum returned by `RunReport::spectrum()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpectrumBin {
+    /// The energy at the center of the bin.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_si"))]
+    pub energy: Joule<f64>,
+    /// The detected weight in this bin per source photon.
+    pub value: f64,
+    /// The statistical error of `value`.
+    pub error: f64,
+}
+
+
+/// Runs an experiment in batches and returns statistics over them.
+///
+/// The run is split into `n_batches` batches of `histories_per_batch`
+/// histories each. Every detected photon is passed to `score`. The
+/// tally of a batch is the sum of all scores, each multiplied by the
+/// photon's weight, divided by the number of successful histories in
+/// the batch. The returned `Statistics` describe the distribution of
+/// these per-batch tallies: Its mean is the expected score per history
+/// and its `error_of_mean()` is the corresponding Monte-Carlo
+/// uncertainty.
+///
+/// Histories that fail with a `TransportError` are not counted, like in
+/// `Progress`. Batches in which every history fails are skipped.
+///
+/// For example, to get the probability that a source photon ends up
+/// in the 662 keV peak, `score` should return `1.0` for photons inside
+/// the peak and `0.0` otherwise.
+///
+/// If `histories_per_batch` is zero, no batch can be scored and the
+/// returned `Statistics` are empty.
+pub fn run_batches<E, R, F>(
+    exp: &E,
+    n_batches: usize,
+    histories_per_batch: usize,
+    rng: &mut R,
+    mut score: F,
+) -> Statistics<f64>
+where
+    E: Experiment,
+    R: Rng,
+    F: FnMut(&Photon) -> f64,
+{
+    let mut stats = Statistics::new();
+    if histories_per_batch == 0 {
+        return stats;
+    }
+    for batch in 0..n_batches {
+        let mut tally = 0.0;
+        let mut histories = 0;
+        for _ in 0..histories_per_batch {
+            if let Ok(photons) = simulate_primary(exp, rng, &mut ()) {
+                for photon in &photons {
+                    tally += score(photon) * photon.weight();
+                }
+                histories += 1;
+            }
+        }
+        if histories == 0 {
+            log_debug!("batch {} of {}: all histories failed", batch + 1, n_batches);
+            continue;
+        }
+        let mean = tally / histories as f64;
+        log_debug!("batch {} of {}: {:e}", batch + 1, n_batches, mean);
+        stats.push(mean);
+    }
+    logging::log_statistics();
+    stats
+}
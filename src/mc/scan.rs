@@ -0,0 +1,95 @@
+use std::io::{self, Write};
+
+use error::Error;
+use rng::StreamRng;
+use super::experiment::Experiment;
+use super::runner::{Progress, Runner};
+
+
+/// The result of a single point of a parameter scan.
+///
+/// This is returned by `scan()`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScanPoint {
+    /// The value of the scanned parameter.
+    pub parameter: f64,
+    /// The final state of the run at this point.
+    pub progress: Progress,
+}
+
+impl ScanPoint {
+    /// Returns the fraction of histories that ended in the detector.
+    ///
+    /// See `Progress::efficiency()` for details.
+    pub fn efficiency(&self) -> Option<f64> {
+        self.progress.efficiency()
+    }
+
+    /// Returns the absolute statistical error of the efficiency.
+    ///
+    /// See `Progress::relative_error()` for details.
+    pub fn error(&self) -> Option<f64> {
+        match (self.progress.efficiency(), self.progress.relative_error()) {
+            (Some(efficiency), Some(relative_error)) => Some(efficiency * relative_error),
+            _ => None,
+        }
+    }
+}
+
+
+/// Runs the same experiment for each of the given parameter values.
+///
+/// For each value, `make_experiment` is called to set up the
+/// experiment, e.g. a collimator with the given slit width. Then,
+/// `n_histories` histories are simulated. All points use the same
+/// random-number streams derived from `seed`. Thus, the differences
+/// between neighboring points are not blurred by independent
+/// statistical fluctuations, which makes trends much easier to see.
+///
+/// # Errors
+/// This fails as soon as `make_experiment` fails.
+pub fn scan<I, F, E>(
+    parameters: I,
+    n_histories: usize,
+    seed: u64,
+    mut make_experiment: F,
+) -> Result<Vec<ScanPoint>, Error>
+where
+    I: IntoIterator<Item = f64>,
+    F: FnMut(f64) -> Result<E, Error>,
+    E: Experiment,
+{
+    let streams = StreamRng::new(seed);
+    parameters
+        .into_iter()
+        .map(|parameter| {
+            log_info!("scanning parameter value {}", parameter);
+            let experiment = make_experiment(parameter)?;
+            let progress = Runner::new(&experiment).run_streams(n_histories, &streams, |_| {});
+            Ok(ScanPoint { parameter, progress })
+        })
+        .collect()
+}
+
+
+/// Writes the results of a parameter scan as a table.
+///
+/// The table has four tab-separated columns: the parameter value, the
+/// efficiency, its absolute error, and the number of failed histories.
+/// Points without a detected photon have an efficiency and error of
+/// `NaN`.
+pub fn write_scan_table<W: Write>(points: &[ScanPoint], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "parameter\tefficiency\terror\tfailed")?;
+    for point in points {
+        writeln!(
+            writer,
+            "{}\t{:e}\t{:e}\t{}",
+            point.parameter,
+            point.efficiency().unwrap_or(f64::NAN),
+            point.error().unwrap_or(f64::NAN),
+            point.progress.failed()
+        )?;
+    }
+    Ok(())
+}
@@ -86,3 +86,80 @@ impl Source for EastPointingSource {
         Photon::new(self.location().clone(), direction, self.energy())
     }
 }
+
+
+/// Like `SimpleSource`, but it emits photons into all of 3D-space.
+///
+/// This source should be used with experiments that are simulated in
+/// 3D, see `Experiment::is_3d()`.
+pub struct SphericalSource(SimpleSource);
+
+impl SphericalSource {
+    /// Creates a new source at the given location.
+    ///
+    /// The returned source produces photons of the given energy.
+    pub fn new(location: Point, energy: Joule<f64>) -> Self {
+        SphericalSource(SimpleSource { location, energy })
+    }
+
+    /// Returns the source's location.
+    pub fn location(&self) -> &Point {
+        self.0.location()
+    }
+
+    /// Returns the energy of the source's photons.
+    pub fn energy(&self) -> Joule<f64> {
+        self.0.energy()
+    }
+}
+
+impl Source for SphericalSource {
+    /// Emit a photon into a random direction.
+    ///
+    /// This uses `rng` as a source of randomness.
+    fn emit_photon<R: Rng>(&self, rng: &mut R) -> Photon {
+        let direction = Direction::random_3d(rng);
+        Photon::new(self.location().clone(), direction, self.energy())
+    }
+}
+
+
+/// A source that emits photons during a pulse of finite length.
+///
+/// This wraps another source and sets the emission time of its
+/// photons, distributed uniformly between zero and `width`. Together
+/// with `TimeTally`, this allows time-of-flight studies.
+pub struct PulsedSource<S> {
+    source: S,
+    width: Second<f64>,
+}
+
+impl<S: Source> PulsedSource<S> {
+    /// Creates a new source that emits the photons of `source` during
+    /// a pulse of the given width.
+    pub fn new(source: S, width: Second<f64>) -> Self {
+        PulsedSource { source, width }
+    }
+
+    /// Returns the wrapped source.
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Returns the width of the pulse.
+    pub fn width(&self) -> Second<f64> {
+        self.width
+    }
+}
+
+impl<S: Source> Source for PulsedSource<S> {
+    /// Emit a photon of the wrapped source at a random time.
+    ///
+    /// This uses `rng` as a source of randomness.
+    fn emit_photon<R: Rng>(&self, rng: &mut R) -> Photon {
+        let mut photon = self.source.emit_photon(rng);
+        let time = self.width * rng.gen::<f64>();
+        photon.set_time(time);
+        photon
+    }
+}
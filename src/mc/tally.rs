@@ -0,0 +1,848 @@
+use std::collections::HashMap;
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+
+use histogram::{Histogram, Histogram2D};
+use statistics::Statistics;
+use super::geometry::{Point, Mesh};
+use super::particle::Photon;
+use super::experiment::{Material, Event};
+
+
+/// The common trait of all tallies.
+///
+/// A tally is notified by `simulate_history()` of everything that
+/// happens during a history and is relevant for scoring. All methods
+/// have empty default implementations, so each tally only needs to
+/// implement the notifications it is interested in.
+pub trait Tally {
+    /// Called before a new history starts.
+    fn begin_history(&mut self) {}
+
+    /// Called when energy is deposited in the medium.
+    ///
+    /// `material` is the material at `location`, the place where the
+    /// energy has been deposited. `weight` is the statistical weight of
+    /// the depositing particle; `energy` has not been multiplied by it.
+    fn deposit(
+        &mut self,
+        _material: Material,
+        _location: &Point,
+        _energy: Joule<f64>,
+        _weight: f64,
+    ) {
+    }
+
+    /// Called when the transport of a photon begins.
+    ///
+    /// For the primary photon, this happens right after its emission.
+    /// Photons split off or emitted during the history are announced
+    /// when their transport begins, after the previous photon has been
+    /// tracked to its end.
+    fn start(&mut self, _photon: &Photon) {}
+
+    /// Called when the importance map splits a photon or plays Russian
+    /// roulette with it.
+    ///
+    /// `photon` already carries its new weight. `copies` is the number
+    /// of photons that continue in its place: zero if it has been
+    /// killed and one if it has survived the roulette or has not been
+    /// split after all. In these cases, `photon` itself continues.
+    ///
+    /// If `copies` is two or more, `photon` stops and is replaced by
+    /// this many copies. They are announced via `start_copy()` once all
+    /// other particles of the history that are still waiting to be
+    /// transported have been tracked to their end.
+    fn split(&mut self, _photon: &Photon, _copies: usize) {}
+
+    /// Called when the transport of a copy split off a photon begins.
+    ///
+    /// Copies of different splittings are started in the reverse order
+    /// of the splittings. By default, this calls `start()`.
+    fn start_copy(&mut self, photon: &Photon) {
+        self.start(photon);
+    }
+
+    /// Called when a photon has moved to a new location.
+    fn step(&mut self, _photon: &Photon) {}
+
+    /// Called when a photon interacts with the medium.
+    ///
+    /// `photon` is the photon at the place of the interaction, before
+    /// its direction or energy have changed. `material` is the material
+    /// at this place. `Event::Nothing` is not reported. Energy deposited
+    /// by the interaction, directly or through secondary particles, is
+    /// reported separately via `deposit()`.
+    fn interact(&mut self, _material: Material, _photon: &Photon, _event: Event) {}
+
+    /// Called when a photon has been detected.
+    fn detect(&mut self, _photon: &Photon) {}
+
+    /// Called after a history has ended.
+    fn end_history(&mut self) {}
+}
+
+/// The empty tally, which ignores all notifications.
+impl Tally for () {}
+
+/// A pair of tallies, which passes all notifications on to both.
+impl<A: Tally, B: Tally> Tally for (A, B) {
+    fn begin_history(&mut self) {
+        self.0.begin_history();
+        self.1.begin_history();
+    }
+
+    fn deposit(&mut self, material: Material, location: &Point, energy: Joule<f64>, weight: f64) {
+        self.0.deposit(material, location, energy, weight);
+        self.1.deposit(material, location, energy, weight);
+    }
+
+    fn start(&mut self, photon: &Photon) {
+        self.0.start(photon);
+        self.1.start(photon);
+    }
+
+    fn split(&mut self, photon: &Photon, copies: usize) {
+        self.0.split(photon, copies);
+        self.1.split(photon, copies);
+    }
+
+    fn start_copy(&mut self, photon: &Photon) {
+        self.0.start_copy(photon);
+        self.1.start_copy(photon);
+    }
+
+    fn step(&mut self, photon: &Photon) {
+        self.0.step(photon);
+        self.1.step(photon);
+    }
+
+    fn interact(&mut self, material: Material, photon: &Photon, event: Event) {
+        self.0.interact(material, photon, event);
+        self.1.interact(material, photon, event);
+    }
+
+    fn detect(&mut self, photon: &Photon) {
+        self.0.detect(photon);
+        self.1.detect(photon);
+    }
+
+    fn end_history(&mut self) {
+        self.0.end_history();
+        self.1.end_history();
+    }
+}
+
+/// An optional tally, which passes all notifications on if present.
+impl<T: Tally> Tally for Option<T> {
+    fn begin_history(&mut self) {
+        if let Some(ref mut tally) = *self {
+            tally.begin_history();
+        }
+    }
+
+    fn deposit(&mut self, material: Material, location: &Point, energy: Joule<f64>, weight: f64) {
+        if let Some(ref mut tally) = *self {
+            tally.deposit(material, location, energy, weight);
+        }
+    }
+
+    fn start(&mut self, photon: &Photon) {
+        if let Some(ref mut tally) = *self {
+            tally.start(photon);
+        }
+    }
+
+    fn split(&mut self, photon: &Photon, copies: usize) {
+        if let Some(ref mut tally) = *self {
+            tally.split(photon, copies);
+        }
+    }
+
+    fn start_copy(&mut self, photon: &Photon) {
+        if let Some(ref mut tally) = *self {
+            tally.start_copy(photon);
+        }
+    }
+
+    fn step(&mut self, photon: &Photon) {
+        if let Some(ref mut tally) = *self {
+            tally.step(photon);
+        }
+    }
+
+    fn interact(&mut self, material: Material, photon: &Photon, event: Event) {
+        if let Some(ref mut tally) = *self {
+            tally.interact(material, photon, event);
+        }
+    }
+
+    fn detect(&mut self, photon: &Photon) {
+        if let Some(ref mut tally) = *self {
+            tally.detect(photon);
+        }
+    }
+
+    fn end_history(&mut self) {
+        if let Some(ref mut tally) = *self {
+            tally.end_history();
+        }
+    }
+}
+
+impl<T: Tally> Tally for &mut T {
+    fn begin_history(&mut self) {
+        (**self).begin_history();
+    }
+
+    fn deposit(&mut self, material: Material, location: &Point, energy: Joule<f64>, weight: f64) {
+        (**self).deposit(material, location, energy, weight);
+    }
+
+    fn start(&mut self, photon: &Photon) {
+        (**self).start(photon);
+    }
+
+    fn split(&mut self, photon: &Photon, copies: usize) {
+        (**self).split(photon, copies);
+    }
+
+    fn start_copy(&mut self, photon: &Photon) {
+        (**self).start_copy(photon);
+    }
+
+    fn step(&mut self, photon: &Photon) {
+        (**self).step(photon);
+    }
+
+    fn interact(&mut self, material: Material, photon: &Photon, event: Event) {
+        (**self).interact(material, photon, event);
+    }
+
+    fn detect(&mut self, photon: &Photon) {
+        (**self).detect(photon);
+    }
+
+    fn end_history(&mut self) {
+        (**self).end_history();
+    }
+}
+
+
+/// Tally of the pulse-height spectrum of the detector.
+///
+/// This tally adds up all energy deposited in the detector material
+/// during a history. At the end of each history, the total is filled
+/// into a histogram. Histories that don't deposit any energy in the
+/// detector are not counted.
+///
+/// In contrast to histogramming the energy of detected photons, this
+/// correctly produces the photopeak, the Compton continuum and escape
+/// features, provided that the transport inside the detector is
+/// simulated.
+///
+/// With an importance map, a history branches whenever a photon is
+/// split. Each copy then continues the pulse of everything that has
+/// been deposited before the splitting and is filled separately with
+/// its weight. A history that is killed by Russian roulette is not
+/// filled at all; one that survives is filled with its increased
+/// weight. If several particles of a history are split, the copies of
+/// each are treated as separate branches.
+///
+/// # Examples
+///
+/// In this slab, each photon scatters once and deposits half of its
+/// energy. The other half is either absorbed in the detector or
+/// escapes. Splitting the photon after the scattering does not change
+/// the spectrum.
+///
+/// ```
+/// extern crate mcgen;
+/// extern crate dimensioned;
+/// extern crate rand;
+///
+/// use mcgen::{Histogram, StreamRng};
+/// use mcgen::mc::*;
+/// use dimensioned::si::*;
+/// use dimensioned::f64prefixes::KILO;
+/// use rand::Rng;
+///
+/// struct Slab {
+///     source: EastPointingSource,
+///     map: Option<ImportanceMap>,
+/// }
+///
+/// impl Experiment for Slab {
+///     type Source = EastPointingSource;
+///
+///     fn source(&self) -> &EastPointingSource {
+///         &self.source
+///     }
+///
+///     fn x_start(&self) -> Meter<f64> {
+///         0.0 * M
+///     }
+///
+///     fn get_material(&self, location: &Point) -> Material {
+///         let x = location.x();
+///         if x < 0.015 * M || (x > 0.025 * M && x < 0.035 * M) {
+///             Material::Detector
+///         } else if x < 0.035 * M {
+///             Material::Air
+///         } else {
+///             Material::Absorber
+///         }
+///     }
+///
+///     fn get_mean_free_path(&self, _: Material, _: Joule<f64>) -> FreePath<f64> {
+///         FreePath::Fix(0.01 * M)
+///     }
+///
+///     fn importance_map(&self) -> Option<&ImportanceMap> {
+///         self.map.as_ref()
+///     }
+///
+///     fn gen_event<R: Rng>(&self, material: Material, energy: Joule<f64>, rng: &mut R) -> Event {
+///         match material {
+///             Material::Air => Event::Nothing,
+///             Material::Absorber => Event::Absorbed,
+///             Material::Detector if energy > 400.0 * KILO * EV => Event::IncoherentScatter,
+///             Material::Detector if rng.gen() => Event::Absorbed,
+///             Material::Detector => Event::Nothing,
+///         }
+///     }
+///
+///     fn gen_coherent_scatter<R: Rng>(&self, _: Material, _: Joule<f64>, _: &mut R) -> Unitless<f64> {
+///         unreachable!()
+///     }
+///
+///     fn gen_incoherent_scatter<R: Rng>(
+///         &self,
+///         _: Material,
+///         energy: Joule<f64>,
+///         _: &mut R,
+///     ) -> (Unitless<f64>, Joule<f64>) {
+///         (Unitless::new(0.0), 0.5 * energy)
+///     }
+/// }
+///
+/// fn spectrum(exp: &Slab) -> Vec<f64> {
+///     let mut tally = SpectrumTally::new(Histogram::new(3, 200.0, 800.0), KILO * EV);
+///     let mut rng = StreamRng::new(42).stream(0);
+///     for _ in 0..1000 {
+///         simulate_history(exp, &mut rng, &mut tally).unwrap();
+///     }
+///     tally.histogram().bin_contents().to_vec()
+/// }
+///
+/// fn main() {
+///     let source = EastPointingSource::new(Point::new(-1.0 * M, 0.0 * M), 662.0 * KILO * EV);
+///     let mut exp = Slab { source, map: None };
+///     let analog = spectrum(&exp);
+///     // Split each photon in two after it has scattered.
+///     let mesh = Mesh::new(3, (0.0 * M, 0.045 * M), 1, (-1.0 * M, 1.0 * M));
+///     exp.map = Some(ImportanceMap::new(mesh, vec![1.0, 2.0, 2.0]));
+///     let split = spectrum(&exp);
+///     // The pulse is either 331 keV or 662 keV, never in between.
+///     assert_eq!(analog[1], 0.0);
+///     assert_eq!(split[1], 0.0);
+///     assert_eq!(analog[0] + analog[2], 1000.0);
+///     assert_eq!(split[0] + split[2], 1000.0);
+///     // Either peak contains about half of the histories.
+///     for &content in &[analog[0], analog[2], split[0], split[2]] {
+///         assert!((content - 500.0).abs() < 100.0);
+///     }
+/// }
+/// ```
+pub struct SpectrumTally {
+    histogram: Histogram,
+    unit: Joule<f64>,
+    /// The energy deposited in the current branch of the history, or
+    /// `None` if it has been killed by Russian roulette.
+    current: Option<Joule<f64>>,
+    /// The weight of the current branch, if it has been split or has
+    /// survived Russian roulette.
+    weight: Option<f64>,
+    /// The weight of the last energy deposit in the current branch.
+    last_weight: f64,
+    /// The number and weight of copies of each splitting that has not
+    /// been reflected in `copies` yet.
+    splits: Vec<(usize, f64)>,
+    /// The initial deposited energy and the weight of each copy that
+    /// has not been started yet.
+    copies: Vec<Option<(Joule<f64>, f64)>>,
+}
+
+impl SpectrumTally {
+    /// Creates a new tally filling the given histogram.
+    ///
+    /// The histogram's X-axis is measured in multiples of `unit`, e.g.
+    /// `KILO * EV`.
+    pub fn new(histogram: Histogram, unit: Joule<f64>) -> Self {
+        SpectrumTally {
+            histogram,
+            unit,
+            current: Some(0.0 * J),
+            weight: None,
+            last_weight: 1.0,
+            splits: Vec::new(),
+            copies: Vec::new(),
+        }
+    }
+
+    /// Returns the unit of the histogram's X-axis.
+    pub fn unit(&self) -> Joule<f64> {
+        self.unit
+    }
+
+    /// Returns the histogram of deposited energies.
+    pub fn histogram(&self) -> &Histogram {
+        &self.histogram
+    }
+
+    /// Consumes the tally and returns the histogram.
+    pub fn into_histogram(self) -> Histogram {
+        self.histogram
+    }
+
+    /// Private function that fills the pulse of the current branch into
+    /// the histogram.
+    fn end_branch(&mut self) {
+        if let Some(current) = self.current {
+            if current > 0.0 * J {
+                let energy = current / self.unit;
+                let weight = self.weight.unwrap_or(self.last_weight);
+                self.histogram.fill_weighted(*energy.value(), weight);
+            }
+        }
+    }
+}
+
+impl Tally for SpectrumTally {
+    fn begin_history(&mut self) {
+        self.current = Some(0.0 * J);
+        self.weight = None;
+        self.last_weight = 1.0;
+        self.splits.clear();
+        self.copies.clear();
+    }
+
+    fn deposit(&mut self, material: Material, _: &Point, energy: Joule<f64>, weight: f64) {
+        if material == Material::Detector {
+            if let Some(ref mut current) = self.current {
+                *current += energy;
+                self.last_weight = weight;
+            }
+        }
+    }
+
+    fn split(&mut self, photon: &Photon, copies: usize) {
+        match copies {
+            0 => self.current = None,
+            1 => self.weight = Some(photon.weight()),
+            _ => self.splits.push((copies, photon.weight())),
+        }
+    }
+
+    fn start_copy(&mut self, _: &Photon) {
+        if self.splits.is_empty() {
+            // The previous copy has been tracked to its end.
+            self.end_branch();
+        } else {
+            // Everything before the splittings has been tracked to its
+            // end. The copies continue the current pulse.
+            let current = self.current;
+            for (copies, weight) in self.splits.drain(..) {
+                let copy = current.map(|energy| (energy, weight));
+                self.copies.extend((0..copies).map(|_| copy));
+            }
+        }
+        let copy = self.copies.pop().flatten();
+        self.current = copy.map(|(energy, _)| energy);
+        self.weight = copy.map(|(_, weight)| weight);
+    }
+
+    fn end_history(&mut self) {
+        self.end_branch();
+    }
+}
+
+
+/// Tally of the arrival angle and position of detected photons.
+///
+/// The detector surface is assumed to be the plane `x = plane`, with
+/// photons entering it in positive X direction. When a photon is
+/// detected, it is traced back along its direction to this plane. The
+/// point where it crosses the plane is its arrival position; the angle
+/// between its direction and the X-axis is its arrival angle.
+///
+/// The angle is measured in radians in the XY-plane and is positive if
+/// the photon moves towards positive Y. The position is the Y
+/// coordinate of the crossing point in multiples of `unit`. Both are
+/// filled with the photon's weight into 1D histograms and, combined,
+/// into a 2D histogram.
+///
+/// This quantifies how much a collimator broadens the beam.
+pub struct AngularTally {
+    plane: Meter<f64>,
+    unit: Meter<f64>,
+    angles: Histogram,
+    positions: Histogram,
+    angle_vs_position: Histogram2D,
+}
+
+impl AngularTally {
+    /// Creates a new tally for the detector surface at `x = plane`.
+    ///
+    /// `angles` and `positions` are the empty 1D histograms to fill.
+    /// The 2D histogram uses the binning of both, with positions along
+    /// its X-axis and angles along its Y-axis.
+    pub fn new(plane: Meter<f64>, angles: Histogram, positions: Histogram, unit: Meter<f64>) -> Self {
+        let &(angle_low, angle_high) = angles.range();
+        let &(position_low, position_high) = positions.range();
+        let angle_vs_position = Histogram2D::new(
+            positions.num_bins(),
+            position_low,
+            position_high,
+            angles.num_bins(),
+            angle_low,
+            angle_high,
+        );
+        AngularTally {
+            plane,
+            unit,
+            angles,
+            positions,
+            angle_vs_position,
+        }
+    }
+
+    /// Returns the X coordinate of the detector surface.
+    pub fn plane(&self) -> Meter<f64> {
+        self.plane
+    }
+
+    /// Returns the unit of the position histogram's X-axis.
+    pub fn unit(&self) -> Meter<f64> {
+        self.unit
+    }
+
+    /// Returns the histogram of arrival angles.
+    pub fn angles(&self) -> &Histogram {
+        &self.angles
+    }
+
+    /// Returns the histogram of arrival positions.
+    pub fn positions(&self) -> &Histogram {
+        &self.positions
+    }
+
+    /// Returns the 2D histogram of arrival angle against position.
+    ///
+    /// The position is along the X-axis, the angle along the Y-axis.
+    pub fn angle_vs_position(&self) -> &Histogram2D {
+        &self.angle_vs_position
+    }
+}
+
+impl Tally for AngularTally {
+    fn detect(&mut self, photon: &Photon) {
+        let location = photon.location();
+        let direction = photon.direction();
+        if *direction.dx() <= 0.0 {
+            return;
+        }
+        let length = (self.plane - location.x()) / direction.dx();
+        let y = location.y() + length * direction.dy();
+        let angle = direction.dy().atan2(*direction.dx());
+        let position = *(y / self.unit).value();
+        let weight = photon.weight();
+        self.angles.fill_weighted(angle, weight);
+        self.positions.fill_weighted(position, weight);
+        self.angle_vs_position.fill_weighted((position, angle), weight);
+    }
+}
+
+
+/// Tally of the energy spectrum of detected photons, resolved by
+/// scatter order.
+///
+/// Each detected photon is filled with its weight into one of four
+/// histograms, depending on how often it has been scattered before
+/// reaching the detector: zero (uncollided), one, two, or three and
+/// more times.
+/// This separates the direct beam from the leakage through and the
+/// scattering off a collimator.
+pub struct ScatterOrderTally {
+    orders: [Histogram; 4],
+    unit: Joule<f64>,
+}
+
+impl ScatterOrderTally {
+    /// The number of separate scatter orders.
+    ///
+    /// All photons scattered `NUM_ORDERS - 1` times or more are filled
+    /// into the last histogram.
+    pub const NUM_ORDERS: usize = 4;
+
+    /// Creates a new tally with `nbins` bins between `low` and `high`.
+    ///
+    /// The histograms' X-axis is measured in multiples of `unit`, e.g.
+    /// `KILO * EV`.
+    pub fn new(nbins: usize, low: f64, high: f64, unit: Joule<f64>) -> Self {
+        ScatterOrderTally {
+            orders: [
+                Histogram::new(nbins, low, high),
+                Histogram::new(nbins, low, high),
+                Histogram::new(nbins, low, high),
+                Histogram::new(nbins, low, high),
+            ],
+            unit,
+        }
+    }
+
+    /// Returns the unit of the histograms' X-axis.
+    pub fn unit(&self) -> Joule<f64> {
+        self.unit
+    }
+
+    /// Returns the spectrum of photons of the given scatter order.
+    ///
+    /// Orders of `NUM_ORDERS - 1` and above all return the last
+    /// histogram.
+    pub fn spectrum(&self, order: u32) -> &Histogram {
+        &self.orders[Self::index(order)]
+    }
+
+    /// Returns the spectra of all scatter orders.
+    pub fn spectra(&self) -> &[Histogram] {
+        &self.orders
+    }
+
+    /// Returns the spectra stacked on top of each other.
+    ///
+    /// The `i`-th entry contains, for each bin, the weight of photons
+    /// of scatter order `i` *or lower*. The last entry is thus the
+    /// total spectrum. This is the format needed to draw a stacked
+    /// histogram.
+    pub fn stacked(&self) -> Vec<Vec<f64>> {
+        let mut result: Vec<Vec<f64>> = Vec::with_capacity(Self::NUM_ORDERS);
+        for histogram in &self.orders {
+            let mut contents = histogram.bin_contents().to_vec();
+            if let Some(below) = result.last() {
+                for (content, below) in contents.iter_mut().zip(below) {
+                    *content += *below;
+                }
+            }
+            result.push(contents);
+        }
+        result
+    }
+
+    /// Private function that maps a scatter order to a histogram.
+    fn index(order: u32) -> usize {
+        (order as usize).min(Self::NUM_ORDERS - 1)
+    }
+}
+
+impl Tally for ScatterOrderTally {
+    fn detect(&mut self, photon: &Photon) {
+        let energy = photon.energy() / self.unit;
+        let histogram = &mut self.orders[Self::index(photon.num_scatters())];
+        histogram.fill_weighted(*energy.value(), photon.weight());
+    }
+}
+
+
+/// Tally of the arrival-time spectrum of detected photons.
+///
+/// The time of a photon is its emission time plus its time of flight,
+/// see `Photon::time()`. Each detected photon is filled with its
+/// weight into a histogram of this time.
+pub struct TimeTally {
+    histogram: Histogram,
+    unit: Second<f64>,
+}
+
+impl TimeTally {
+    /// Creates a new tally filling the given histogram.
+    ///
+    /// The histogram's X-axis is measured in multiples of `unit`, e.g.
+    /// `NANO * S`.
+    pub fn new(histogram: Histogram, unit: Second<f64>) -> Self {
+        TimeTally { histogram, unit }
+    }
+
+    /// Returns the unit of the histogram's X-axis.
+    pub fn unit(&self) -> Second<f64> {
+        self.unit
+    }
+
+    /// Returns the histogram of arrival times.
+    pub fn histogram(&self) -> &Histogram {
+        &self.histogram
+    }
+
+    /// Consumes the tally and returns the histogram.
+    pub fn into_histogram(self) -> Histogram {
+        self.histogram
+    }
+}
+
+impl Tally for TimeTally {
+    fn detect(&mut self, photon: &Photon) {
+        let time = photon.time() / self.unit;
+        self.histogram.fill_weighted(*time.value(), photon.weight());
+    }
+}
+
+
+/// Private type that accumulates per-history scores lazily.
+///
+/// Most histories don't deposit any energy in a given region or mesh
+/// cell. Instead of pushing zeros to the statistics after every
+/// history, the zeros are accounted for in bulk the next time the
+/// score is non-zero.
+#[derive(Clone, Debug, Default)]
+struct Score {
+    /// The statistics of all histories accounted for so far.
+    stats: Statistics<f64>,
+    /// The number of histories accounted for in `stats`.
+    histories: u32,
+    /// The score of the current history.
+    current: f64,
+}
+
+impl Score {
+    /// Adds `x` to the score of the current history.
+    fn add(&mut self, x: f64) {
+        self.current += x;
+    }
+
+    /// Finishes the history with the given (zero-based) number.
+    fn end_history(&mut self, history: u32) {
+        if self.current != 0.0 {
+            self.stats.push_n(0.0, history - self.histories);
+            self.stats.push(self.current);
+            self.histories = history + 1;
+            self.current = 0.0;
+        }
+    }
+
+    /// Returns the statistics after the given number of histories.
+    fn statistics(&self, histories: u32) -> Statistics<f64> {
+        let mut stats = self.stats.clone();
+        stats.push_n(0.0, histories - self.histories);
+        stats
+    }
+}
+
+
+/// Tally of the energy deposited per material region.
+///
+/// For each material, this tally records the energy deposited in it
+/// per history. The statistics over all histories give the mean
+/// deposited energy and its uncertainty. Dividing it by the mass of
+/// the region gives the absorbed dose per source photon.
+///
+/// Optionally, the deposited energy can additionally be scored on a
+/// `Mesh`, regardless of the material.
+///
+/// All energies are given in joules.
+#[derive(Clone, Debug, Default)]
+pub struct DoseTally {
+    histories: u32,
+    regions: HashMap<Material, Score>,
+    mesh: Option<Mesh>,
+    cells: Vec<Score>,
+    touched_cells: Vec<usize>,
+}
+
+impl DoseTally {
+    /// Creates a new tally that scores per material.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a new tally that additionally scores per mesh cell.
+    pub fn with_mesh(mesh: Mesh) -> Self {
+        DoseTally {
+            cells: vec![Default::default(); mesh.num_cells()],
+            mesh: Some(mesh),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the number of histories that have been tallied.
+    pub fn histories(&self) -> u32 {
+        self.histories
+    }
+
+    /// Returns the mesh, if there is any.
+    pub fn mesh(&self) -> Option<&Mesh> {
+        self.mesh.as_ref()
+    }
+
+    /// Returns the statistics of the energy deposited per history in
+    /// the given material.
+    ///
+    /// If energy has never been deposited in this material, `None` is
+    /// returned.
+    pub fn region(&self, material: Material) -> Option<Statistics<f64>> {
+        self.regions
+            .get(&material)
+            .map(|score| score.statistics(self.histories))
+    }
+
+    /// Returns the statistics of the energy deposited per history in
+    /// all materials in which energy has been deposited.
+    ///
+    /// The materials are sorted in the order in which they are
+    /// declared.
+    pub fn regions(&self) -> Vec<(Material, Statistics<f64>)> {
+        let mut regions: Vec<_> = self.regions
+            .iter()
+            .map(|(&material, score)| (material, score.statistics(self.histories)))
+            .collect();
+        regions.sort_by_key(|&(material, _)| material);
+        regions
+    }
+
+    /// Returns the statistics of the energy deposited per history in
+    /// each mesh cell.
+    ///
+    /// The statistics are sorted by cell index. If there is no mesh,
+    /// the result is empty.
+    pub fn cells(&self) -> Vec<Statistics<f64>> {
+        self.cells
+            .iter()
+            .map(|score| score.statistics(self.histories))
+            .collect()
+    }
+}
+
+impl Tally for DoseTally {
+    fn deposit(&mut self, material: Material, location: &Point, energy: Joule<f64>, weight: f64) {
+        let energy = *(energy / J).value() * weight;
+        self.regions
+            .entry(material)
+            .or_default()
+            .add(energy);
+        if let Some(cell) = self.mesh.as_ref().and_then(|mesh| mesh.find_cell(location)) {
+            self.cells[cell].add(energy);
+            self.touched_cells.push(cell);
+        }
+    }
+
+    fn end_history(&mut self) {
+        let history = self.histories;
+        for score in self.regions.values_mut() {
+            score.end_history(history);
+        }
+        for cell in self.touched_cells.drain(..) {
+            self.cells[cell].end_history(history);
+        }
+        self.histories += 1;
+    }
+}
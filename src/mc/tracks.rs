@@ -0,0 +1,381 @@
+use std::fs::File;
+use std::path::Path;
+use std::io::{self, Write, BufWriter};
+
+use csv;
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+use dimensioned::f64prefixes::*;
+
+use error::Error;
+use super::tally::Tally;
+use super::geometry::Point;
+use super::particle::Photon;
+use super::experiment::Material;
+
+
+/// The path of a single photon through the setup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    /// The zero-based number of the history the photon belongs to.
+    pub history: u64,
+    /// The locations of the photon, beginning where its transport
+    /// started and ending where it was detected or lost.
+    pub points: Vec<Point>,
+    /// Whether the photon has been detected.
+    pub detected: bool,
+}
+
+
+/// Tally that records the tracks of the first few photons.
+///
+/// Each photon whose transport begins while fewer than `max_tracks`
+/// tracks have been recorded gets its own track. This includes photons
+/// split off by an importance map and fluorescence photons. Every step
+/// of the photon adds a point to its track.
+///
+/// The tracks can be written to a file with `write_tracks()`. This is
+/// mostly useful for debugging the geometry of a setup.
+#[derive(Debug, Clone, Default)]
+pub struct TrackRecorder {
+    max_tracks: usize,
+    /// The number of the current history plus one.
+    histories: u64,
+    tracks: Vec<Track>,
+    recording: bool,
+}
+
+impl TrackRecorder {
+    /// Creates a recorder that records at most `max_tracks` tracks.
+    pub fn new(max_tracks: usize) -> Self {
+        TrackRecorder {
+            max_tracks,
+            tracks: Vec::with_capacity(max_tracks),
+            ..Default::default()
+        }
+    }
+
+    /// Returns `true` if no further tracks will be recorded.
+    pub fn is_full(&self) -> bool {
+        self.tracks.len() >= self.max_tracks
+    }
+
+    /// Returns the tracks recorded so far.
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// Consumes the recorder and returns the tracks.
+    pub fn into_tracks(self) -> Vec<Track> {
+        self.tracks
+    }
+}
+
+impl Tally for TrackRecorder {
+    fn begin_history(&mut self) {
+        self.histories += 1;
+        self.recording = false;
+    }
+
+    fn start(&mut self, photon: &Photon) {
+        self.recording = !self.is_full();
+        if self.recording {
+            self.tracks.push(Track {
+                history: self.histories.saturating_sub(1),
+                points: vec![photon.location().clone()],
+                detected: false,
+            });
+        }
+    }
+
+    fn step(&mut self, photon: &Photon) {
+        if let (true, Some(track)) = (self.recording, self.tracks.last_mut()) {
+            track.points.push(photon.location().clone());
+        }
+    }
+
+    fn detect(&mut self, _: &Photon) {
+        if let (true, Some(track)) = (self.recording, self.tracks.last_mut()) {
+            track.detected = true;
+        }
+    }
+
+    fn end_history(&mut self) {
+        self.recording = false;
+    }
+}
+
+
+/// The outline of a part of the setup, drawn behind the tracks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outline {
+    /// The material of the part; this determines its color.
+    pub material: Material,
+    /// The corners of the closed polygon around the part.
+    pub corners: Vec<Point>,
+}
+
+impl Outline {
+    /// Creates the outline of a rectangle between the given lower and
+    /// upper X- and Y-coordinates.
+    pub fn rectangle(
+        material: Material,
+        x: (Meter<f64>, Meter<f64>),
+        y: (Meter<f64>, Meter<f64>),
+    ) -> Self {
+        let corners = vec![
+            Point::new(x.0, y.0),
+            Point::new(x.1, y.0),
+            Point::new(x.1, y.1),
+            Point::new(x.0, y.1),
+        ];
+        Outline { material, corners }
+    }
+}
+
+
+/// Writes tracks to a file, choosing the format by its extension.
+///
+/// Files ending in `.svg` get a picture of the tracks on top of the
+/// outline, see `write_tracks_svg()`. Files ending in `.gp` or `.plt`
+/// get a gnuplot script, see `write_tracks_gnuplot()`. All others get
+/// the points of the tracks as CSV, see `write_tracks_csv()`; the
+/// outline is ignored in this case.
+///
+/// # Errors
+/// This fails if the file cannot be written.
+pub fn write_tracks<P>(path: P, tracks: &[Track], outline: &[Outline]) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    let file = BufWriter::new(File::create(path)?);
+    match extension {
+        "svg" => write_tracks_svg(tracks, outline, file)?,
+        "gp" | "plt" => write_tracks_gnuplot(tracks, outline, file)?,
+        _ => write_tracks_csv(tracks, file)?,
+    }
+    Ok(())
+}
+
+
+/// Writes the points of all tracks as CSV with a header line.
+///
+/// Each row contains the index of the track, its history, whether it
+/// has been detected, and the coordinates of one point in centimeters.
+///
+/// # Errors
+/// This fails if `writer` fails.
+pub fn write_tracks_csv<W: Write>(tracks: &[Track], writer: W) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_writer(writer);
+    for (index, track) in tracks.iter().enumerate() {
+        for point in &track.points {
+            let (x_cm, y_cm, z_cm) = to_cm(point);
+            writer.serialize(TrackPoint {
+                track: index,
+                history: track.history,
+                detected: track.detected,
+                x_cm,
+                y_cm,
+                z_cm,
+            })?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+
+/// Draws the tracks on top of the outline as an SVG picture.
+///
+/// The picture shows the XY-plane in centimeters, with the Y-axis
+/// pointing up. It is just large enough to contain all tracks; parts
+/// of the setup outside of it are cut off. Detected photons are drawn
+/// in red, all others in blue.
+///
+/// # Errors
+/// This fails if `writer` fails.
+pub fn write_tracks_svg<W>(tracks: &[Track], outline: &[Outline], mut writer: W) -> io::Result<()>
+where
+    W: Write,
+{
+    let (x_range, y_range) = bounding_box(tracks, outline);
+    let (x, width) = (x_range.0, x_range.1 - x_range.0);
+    let (y, height) = (-y_range.1, y_range.1 - y_range.0);
+    let pixels = SVG_WIDTH * height / width;
+    writeln!(
+        writer,
+        r#"<svg xmlns="{}" width="{}" height="{:.0}" viewBox="{} {} {} {}">"#,
+        SVG_NAMESPACE, SVG_WIDTH, pixels, x, y, width, height
+    )?;
+    writeln!(writer, r#"<g transform="scale(1,-1)">"#)?;
+    for part in outline {
+        writeln!(
+            writer,
+            r#"<polygon points="{}" fill="{}" stroke="black" vector-effect="non-scaling-stroke"/>"#,
+            svg_points(&part.corners),
+            fill_color(part.material)
+        )?;
+    }
+    for track in tracks {
+        let color = if track.detected { "red" } else { "blue" };
+        writeln!(
+            writer,
+            r#"<polyline points="{}" fill="none" stroke="{}" vector-effect="non-scaling-stroke"/>"#,
+            svg_points(&track.points),
+            color
+        )?;
+    }
+    writeln!(writer, "</g>")?;
+    writeln!(writer, "</svg>")?;
+    Ok(())
+}
+
+
+/// Writes a gnuplot script that draws the tracks on top of the
+/// outline.
+///
+/// The data is included in the script, so it can be run on its own,
+/// e.g. with `gnuplot -p tracks.gp`. Coordinates are given in
+/// centimeters. Like with `write_tracks_svg()`, the plotted area is
+/// chosen to contain all tracks. This requires gnuplot 5 or later.
+///
+/// # Errors
+/// This fails if `writer` fails.
+pub fn write_tracks_gnuplot<W>(
+    tracks: &[Track],
+    outline: &[Outline],
+    mut writer: W,
+) -> io::Result<()>
+where
+    W: Write,
+{
+    writeln!(writer, "$outline << EOD")?;
+    for part in outline {
+        // Repeat the first corner to close the polygon.
+        write_gnuplot_polyline(&mut writer, part.corners.iter().chain(part.corners.first()))?;
+    }
+    writeln!(writer, "EOD")?;
+    for &(name, detected) in &[("lost", false), ("detected", true)] {
+        writeln!(writer, "${} << EOD", name)?;
+        for track in tracks.iter().filter(|track| track.detected == detected) {
+            write_gnuplot_polyline(&mut writer, track.points.iter())?;
+        }
+        writeln!(writer, "EOD")?;
+    }
+    let (x_range, y_range) = bounding_box(tracks, outline);
+    writeln!(writer, "set size ratio -1")?;
+    writeln!(writer, "set xrange [{}:{}]", x_range.0, x_range.1)?;
+    writeln!(writer, "set yrange [{}:{}]", y_range.0, y_range.1)?;
+    writeln!(writer, "set xlabel \"x [cm]\"")?;
+    writeln!(writer, "set ylabel \"y [cm]\"")?;
+    writeln!(
+        writer,
+        "plot $outline with lines lc \"black\" title \"setup\", \\\n     \
+         $lost with lines lc \"blue\" title \"lost\", \\\n     \
+         $detected with lines lc \"red\" title \"detected\""
+    )?;
+    Ok(())
+}
+
+
+/// Private type of a row written by `write_tracks_csv()`.
+#[derive(Serialize)]
+struct TrackPoint {
+    track: usize,
+    history: u64,
+    detected: bool,
+    x_cm: f64,
+    y_cm: f64,
+    z_cm: f64,
+}
+
+
+/// Private function that converts a point to plain centimeters.
+fn to_cm(point: &Point) -> (f64, f64, f64) {
+    let cm = CENTI * M;
+    let (x, y, z) = point.to_triple();
+    (*(x / cm).value(), *(y / cm).value(), *(z / cm).value())
+}
+
+
+/// Private function that returns the X- and Y-range in centimeters
+/// of the area to draw.
+///
+/// The area contains all tracks plus a small margin. The outline is
+/// only considered if there are no tracks, since regions of the setup
+/// are often made practically infinite.
+fn bounding_box(tracks: &[Track], outline: &[Outline]) -> ((f64, f64), (f64, f64)) {
+    let track_points = tracks.iter().flat_map(|track| &track.points);
+    let outline_points = outline.iter().flat_map(|part| &part.corners);
+    let mut x_range = (f64::INFINITY, f64::NEG_INFINITY);
+    let mut y_range = (f64::INFINITY, f64::NEG_INFINITY);
+    let mut extend = |point: &Point| {
+        let (x, y, _) = to_cm(point);
+        x_range = (x_range.0.min(x), x_range.1.max(x));
+        y_range = (y_range.0.min(y), y_range.1.max(y));
+    };
+    if tracks.iter().any(|track| !track.points.is_empty()) {
+        track_points.for_each(&mut extend);
+    } else {
+        outline_points.for_each(&mut extend);
+    }
+    if x_range.0 > x_range.1 {
+        return ((0.0, 1.0), (0.0, 1.0));
+    }
+    let margin = 0.05 * (x_range.1 - x_range.0).max(y_range.1 - y_range.0).max(1.0);
+    (
+        (x_range.0 - margin, x_range.1 + margin),
+        (y_range.0 - margin, y_range.1 + margin),
+    )
+}
+
+
+/// Private function that formats the `points` attribute of an SVG
+/// polyline or polygon.
+fn svg_points(points: &[Point]) -> String {
+    points
+        .iter()
+        .map(|point| {
+            let (x, y, _) = to_cm(point);
+            format!("{},{}", x, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+
+/// Private function that writes one polyline of a gnuplot data block.
+///
+/// The polyline is terminated by an empty line, so that gnuplot does
+/// not connect it to the next one.
+fn write_gnuplot_polyline<'a, W, I>(writer: &mut W, points: I) -> io::Result<()>
+where
+    W: Write,
+    I: Iterator<Item = &'a Point>,
+{
+    for point in points {
+        let (x, y, _) = to_cm(point);
+        writeln!(writer, "{}\t{}", x, y)?;
+    }
+    writeln!(writer)
+}
+
+
+/// Private function that returns the SVG fill color of a material.
+fn fill_color(material: Material) -> &'static str {
+    match material {
+        Material::Air => "none",
+        Material::Absorber => "lightgray",
+        Material::Detector => "lightgreen",
+    }
+}
+
+
+/// The XML namespace of SVG.
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+
+/// The width of SVG pictures in pixels.
+const SVG_WIDTH: f64 = 800.0;
@@ -0,0 +1,383 @@
+use std::path::PathBuf;
+
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyIOError, PyRuntimeError, PyValueError};
+
+use rand::{self, Rng};
+
+use dimensioned::si::*;
+use dimensioned::Dimensionless;
+use dimensioned::f64prefixes::*;
+
+use mc;
+use error::Error;
+use rng::StreamRng;
+use function::Function;
+use histogram::Histogram;
+use statistics::Statistics;
+use mc::{Config, ConfigExperiment, Runner, Source};
+
+
+/// Python wrapper of `Function<f64, f64>`.
+#[pyclass(name = "Function")]
+pub struct PyFunction(Function<f64, f64>);
+
+#[pymethods]
+impl PyFunction {
+    /// Creates a function from lists of X- and Y-values.
+    #[new]
+    fn new(xdata: Vec<f64>, ydata: Vec<f64>) -> PyResult<Self> {
+        if xdata.is_empty() || xdata.len() != ydata.len() {
+            return Err(PyValueError::new_err("need equally many X- and Y-values"));
+        }
+        let mut func = Function::new(xdata[0], ydata[0]);
+        for (&x, &y) in xdata.iter().zip(ydata.iter()).skip(1) {
+            func.try_push(x, y)?;
+        }
+        Ok(PyFunction(func))
+    }
+
+    /// Reads a function from a CSV file, see `Function::from_file()`.
+    #[staticmethod]
+    fn from_file(path: PathBuf) -> PyResult<Self> {
+        Ok(PyFunction(Function::from_file(path)?))
+    }
+
+    fn __call__(&self, x: f64) -> PyResult<f64> {
+        Ok(self.0.try_call(x)?)
+    }
+
+    fn xdata(&self) -> Vec<f64> {
+        self.0.xdata().to_vec()
+    }
+
+    fn ydata(&self) -> Vec<f64> {
+        self.0.ydata().to_vec()
+    }
+
+    fn domain(&self) -> (f64, f64) {
+        let domain = self.0.domain();
+        (domain.start, domain.end)
+    }
+}
+
+
+/// Python wrapper of `Histogram<f64>`.
+#[pyclass(name = "Histogram")]
+pub struct PyHistogram(Histogram);
+
+#[pymethods]
+impl PyHistogram {
+    #[new]
+    fn new(nbins: usize, low: f64, high: f64) -> Self {
+        PyHistogram(Histogram::new(nbins, low, high))
+    }
+
+    #[pyo3(signature = (x, weight = 1.0))]
+    fn fill(&mut self, x: f64, weight: f64) {
+        self.0.fill_weighted(x, weight);
+    }
+
+    fn extend(&mut self, xs: Vec<f64>) {
+        self.0.extend(xs);
+    }
+
+    fn merge(&mut self, other: &PyHistogram) -> PyResult<()> {
+        if !self.0.has_same_bins(&other.0) {
+            return Err(PyValueError::new_err("histograms with different bins"));
+        }
+        self.0.merge(&other.0);
+        Ok(())
+    }
+
+    fn scale(&mut self, factor: f64) {
+        self.0.scale(factor);
+    }
+
+    fn subtract(&mut self, other: &PyHistogram) -> PyResult<()> {
+        if !self.0.has_same_bins(&other.0) {
+            return Err(PyValueError::new_err("histograms with different bins"));
+        }
+        self.0.subtract(&other.0);
+        Ok(())
+    }
+
+    fn divide(&mut self, other: &PyHistogram) -> PyResult<()> {
+        if !self.0.has_same_bins(&other.0) {
+            return Err(PyValueError::new_err("histograms with different bins"));
+        }
+        self.0.divide(&other.0);
+        Ok(())
+    }
+
+    fn range(&self) -> (f64, f64) {
+        *self.0.range()
+    }
+
+    fn bin_centers(&self) -> Vec<f64> {
+        self.0.bin_centers().collect()
+    }
+
+    fn bin_edges(&self) -> Vec<f64> {
+        let mut edges = self.0.bin_low_edges().to_vec();
+        edges.extend(self.0.bin_high_edges().last());
+        edges
+    }
+
+    fn bin_contents(&self) -> Vec<f64> {
+        self.0.bin_contents().to_vec()
+    }
+
+    fn bin_errors(&self) -> Vec<f64> {
+        self.0.bin_errors()
+    }
+}
+
+
+/// Python wrapper of `Statistics<f64>`.
+#[pyclass(name = "Statistics")]
+#[derive(Default)]
+pub struct PyStatistics(Statistics<f64>);
+
+#[pymethods]
+impl PyStatistics {
+    #[new]
+    fn new() -> Self {
+        Default::default()
+    }
+
+    fn push(&mut self, x: f64) {
+        self.0.push(x);
+    }
+
+    fn extend(&mut self, xs: Vec<f64>) {
+        for x in xs {
+            self.0.push(x);
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.count() as usize
+    }
+
+    fn mean(&self) -> Option<f64> {
+        if self.0.count() > 0 {
+            Some(self.0.mean())
+        } else {
+            None
+        }
+    }
+
+    fn variance(&self) -> Option<f64> {
+        self.0.variance()
+    }
+
+    fn standard_deviation(&self) -> Option<f64> {
+        self.0.standard_deviation()
+    }
+
+    fn error_of_mean(&self) -> Option<f64> {
+        self.0.error_of_mean()
+    }
+}
+
+
+/// Python description of a photon source, see `SourceConfig`.
+///
+/// Lengths are given in centimeters, energies in kiloelectronvolts.
+#[pyclass(name = "Source")]
+#[derive(Clone)]
+pub struct PySource(mc::SourceConfig);
+
+#[pymethods]
+impl PySource {
+    /// Creates a source of the given kind, `"isotropic"` or `"east"`.
+    #[new]
+    fn new(kind: &str, position: (f64, f64), energy: f64) -> PyResult<Self> {
+        let kind = match kind {
+            "isotropic" => mc::SourceKind::Isotropic,
+            "east" => mc::SourceKind::East,
+            _ => return Err(PyValueError::new_err(format!("unknown source kind: {}", kind))),
+        };
+        Ok(PySource(mc::SourceConfig {
+            kind,
+            position,
+            energy,
+        }))
+    }
+
+    #[getter]
+    fn position(&self) -> (f64, f64) {
+        self.0.position
+    }
+
+    #[getter]
+    fn energy(&self) -> f64 {
+        self.0.energy
+    }
+
+    /// Emits `n` photons and returns their directions `(dx, dy)`.
+    #[pyo3(signature = (n, seed = None))]
+    fn emit(&self, n: usize, seed: Option<u64>) -> Vec<(f64, f64)> {
+        let (x, y) = self.0.position;
+        let location = mc::Point::new(x * CENTI * M, y * CENTI * M);
+        let energy = self.0.energy * KILO * EV;
+        let mut rng = StreamRng::new(seed.unwrap_or_else(random_seed)).stream(0);
+        let direction = |photon: mc::Photon| {
+            let direction = photon.direction();
+            (*direction.dx().value(), *direction.dy().value())
+        };
+        match self.0.kind {
+            mc::SourceKind::Isotropic => {
+                let source = mc::SimpleSource::new(location, energy);
+                (0..n).map(|_| direction(source.emit_photon(&mut rng))).collect()
+            },
+            mc::SourceKind::East => {
+                let source = mc::EastPointingSource::new(location, energy);
+                (0..n).map(|_| direction(source.emit_photon(&mut rng))).collect()
+            },
+        }
+    }
+}
+
+
+/// Python wrapper of `Config`.
+#[pyclass(name = "Config")]
+#[derive(Clone)]
+pub struct PyConfig(Config);
+
+#[pymethods]
+impl PyConfig {
+    /// Reads a configuration file, see `Config::from_file()`.
+    #[staticmethod]
+    fn from_file(path: PathBuf) -> PyResult<Self> {
+        Ok(PyConfig(Config::from_file(path).map_err(Error::from)?))
+    }
+
+    /// Sets a numeric parameter, see `Config::set_parameter()`.
+    fn set_parameter(&mut self, path: &str, value: f64) -> PyResult<()> {
+        Ok(self.0.set_parameter(path, value)?)
+    }
+
+    #[getter]
+    fn source(&self) -> PySource {
+        PySource(self.0.source.clone())
+    }
+
+    #[setter]
+    fn set_source(&mut self, source: PySource) {
+        self.0.source = source.0;
+    }
+}
+
+
+/// The result of `Experiment.run()`.
+///
+/// Energies are given in kiloelectronvolts, times in seconds.
+#[pyclass(name = "RunResult", get_all)]
+pub struct PyRunResult {
+    histories: usize,
+    detected: f64,
+    failed: usize,
+    efficiency: Option<f64>,
+    relative_error: Option<f64>,
+    elapsed: f64,
+    energies: Vec<f64>,
+    weights: Vec<f64>,
+}
+
+
+/// Python wrapper of `ConfigExperiment`.
+#[pyclass(name = "Experiment", unsendable)]
+pub struct PyExperiment(ConfigExperiment);
+
+#[pymethods]
+impl PyExperiment {
+    #[new]
+    fn new(config: &PyConfig) -> PyResult<Self> {
+        Ok(PyExperiment(ConfigExperiment::new(&config.0).map_err(Error::from)?))
+    }
+
+    /// Simulates `n_histories` histories, see `Runner::run_streams()`.
+    #[pyo3(signature = (n_histories, seed = None))]
+    fn run(&self, n_histories: usize, seed: Option<u64>) -> PyRunResult {
+        let streams = StreamRng::new(seed.unwrap_or_else(random_seed));
+        let mut energies = Vec::new();
+        let mut weights = Vec::new();
+        let progress = Runner::new(&self.0).run_streams(n_histories, &streams, |photon| {
+            energies.push(*(photon.energy() / (KILO * EV)).value());
+            weights.push(photon.weight());
+        });
+        PyRunResult {
+            histories: progress.histories(),
+            detected: progress.detected(),
+            failed: progress.failed(),
+            efficiency: progress.efficiency(),
+            relative_error: progress.relative_error(),
+            elapsed: *(progress.elapsed() / S).value(),
+            energies,
+            weights,
+        }
+    }
+}
+
+
+/// Runs a parameter scan, see `mc::scan()`.
+///
+/// Returns a list of `(value, efficiency, error)` triples.
+#[pyfunction]
+#[pyo3(signature = (config, path, values, n_histories, seed = None))]
+fn scan(
+    config: &PyConfig,
+    path: &str,
+    values: Vec<f64>,
+    n_histories: usize,
+    seed: Option<u64>,
+) -> PyResult<Vec<ScanRow>> {
+    let seed = seed.unwrap_or_else(random_seed);
+    let points = mc::scan(values, n_histories, seed, |value| {
+        let mut config = config.0.clone();
+        config.set_parameter(path, value)?;
+        Ok(ConfigExperiment::new(&config)?)
+    })?;
+    Ok(points
+        .iter()
+        .map(|point| (point.parameter, point.efficiency(), point.error()))
+        .collect())
+}
+
+
+/// A row of the table returned by `scan()`.
+type ScanRow = (f64, Option<f64>, Option<f64>);
+
+
+/// The Python module `mcgen`.
+#[pymodule]
+fn mcgen(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyFunction>()?;
+    module.add_class::<PyHistogram>()?;
+    module.add_class::<PyStatistics>()?;
+    module.add_class::<PySource>()?;
+    module.add_class::<PyConfig>()?;
+    module.add_class::<PyRunResult>()?;
+    module.add_class::<PyExperiment>()?;
+    module.add_function(wrap_pyfunction!(self::scan, module)?)?;
+    Ok(())
+}
+
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Io(err) => PyIOError::new_err(err.to_string()),
+            Error::Domain(message) => PyValueError::new_err(message),
+            err => PyRuntimeError::new_err(err.to_string()),
+        }
+    }
+}
+
+
+/// Private function that draws a random seed.
+fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
@@ -0,0 +1,116 @@
+use rand::{SeedableRng, XorShiftRng};
+
+
+/// A source of independent random-number streams.
+///
+/// A `StreamRng` derives a separate RNG for each history from a master
+/// seed and the history's index. The RNG of a history depends on
+/// nothing else, in particular not on how many random numbers other
+/// histories have drawn or in which order they have been simulated.
+/// Thus, a parallel run gives exactly the same physics as a serial run
+/// with the same seed.
+///
+/// The streams are counter-based: the seed and index are scrambled
+/// with the SplitMix64 finalizer to seed a `XorShiftRng`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamRng {
+    seed: u64,
+}
+
+impl StreamRng {
+    /// Creates a new source of streams with the given master seed.
+    pub fn new(seed: u64) -> Self {
+        StreamRng { seed }
+    }
+
+    /// Returns the master seed.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the RNG of the stream with the given index.
+    ///
+    /// Calling this twice with the same index gives two RNGs that
+    /// produce the same sequence of numbers.
+    pub fn stream(&self, index: u64) -> XorShiftRng {
+        let first = splitmix64(self.seed ^ splitmix64(index));
+        let second = splitmix64(first);
+        let mut seed = [
+            first as u32,
+            (first >> 32) as u32,
+            second as u32,
+            (second >> 32) as u32,
+        ];
+        // XorShift must not be seeded with all zeros.
+        if seed == [0; 4] {
+            seed[0] = 1;
+        }
+        XorShiftRng::from_seed(seed)
+    }
+}
+
+
+
+/// A source of named, independent random-number streams.
+///
+/// A `SplitRng` derives a separate `StreamRng` for each name from a
+/// master seed. This allows using common random numbers when comparing
+/// two configurations of an experiment: if both draw e.g. the emission
+/// of each photon from the sub-stream `"source"` and everything else
+/// from other sub-streams, both see exactly the same source photons.
+/// The difference of their results then has a much smaller variance
+/// than that of two independent runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplitRng {
+    seed: u64,
+}
+
+impl SplitRng {
+    /// Creates a new source of named streams with the given master
+    /// seed.
+    pub fn new(seed: u64) -> Self {
+        SplitRng { seed }
+    }
+
+    /// Returns the master seed.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the per-history streams of the sub-stream with the
+    /// given name.
+    ///
+    /// The result depends only on the master seed and the name, not on
+    /// which other sub-streams have been used.
+    pub fn streams(&self, name: &str) -> StreamRng {
+        StreamRng::new(splitmix64(self.seed ^ fnv1a(name.as_bytes())))
+    }
+
+    /// Returns a single RNG for the sub-stream with the given name.
+    ///
+    /// This is the first stream of `streams(name)`.
+    pub fn rng(&self, name: &str) -> XorShiftRng {
+        self.streams(name).stream(0)
+    }
+}
+
+
+/// Private function that hashes a byte string.
+///
+/// This is the 64-bit FNV-1a hash. Unlike the hasher of the standard
+/// library, it is guaranteed to be stable across Rust versions.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01B3)
+    })
+}
+
+/// Private function that scrambles a 64-bit number.
+///
+/// This is one step of the SplitMix64 generator.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,162 @@
+use std::ops;
+use std::marker::PhantomData;
+
+use rand::Rng;
+use rand::distributions::{Sample, IndependentSample};
+
+use super::DiscreteSampler;
+
+
+/// Adapter that restricts a distribution to a range.
+///
+/// Samples outside of the range are discarded and redrawn. Thus, the
+/// result is the conditional distribution given that the sample lies
+/// within the range. The expected number of draws per sample is the
+/// reciprocal of the probability of the range, so the range should not
+/// be too unlikely.
+#[derive(Debug, Clone)]
+pub struct Truncate<D, X> {
+    dist: D,
+    range: ops::Range<X>,
+}
+
+impl<D, X> Truncate<D, X>
+where
+    X: PartialOrd,
+{
+    /// Restricts `dist` to the half-open `range`.
+    ///
+    /// # Panics
+    /// This panics if the range is empty.
+    pub fn new(dist: D, range: ops::Range<X>) -> Self {
+        assert!(range.start < range.end, "empty range");
+        Truncate { dist, range }
+    }
+}
+
+impl<D, X> Sample<X> for Truncate<D, X>
+where
+    D: Sample<X>,
+    X: PartialOrd,
+{
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> X {
+        loop {
+            let x = self.dist.sample(rng);
+            if self.range.contains(&x) {
+                return x;
+            }
+        }
+    }
+}
+
+impl<D, X> IndependentSample<X> for Truncate<D, X>
+where
+    D: IndependentSample<X>,
+    X: PartialOrd,
+{
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> X {
+        loop {
+            let x = self.dist.ind_sample(rng);
+            if self.range.contains(&x) {
+                return x;
+            }
+        }
+    }
+}
+
+
+/// Adapter that transforms the samples of a distribution.
+///
+/// Each sample `x` of the underlying distribution is turned into
+/// `f(x)`. E.g. mapping an isotropic `mu` to `mu.acos()` gives the
+/// distribution of the polar angle.
+pub struct Map<D, F, X> {
+    dist: D,
+    func: F,
+    _dummy: PhantomData<X>,
+}
+
+impl<D, F, X> Map<D, F, X> {
+    /// Transforms the samples of `dist` with `f`.
+    pub fn new(dist: D, f: F) -> Self {
+        Map {
+            dist,
+            func: f,
+            _dummy: PhantomData,
+        }
+    }
+}
+
+impl<D, F, X, Y> Sample<Y> for Map<D, F, X>
+where
+    D: Sample<X>,
+    F: FnMut(X) -> Y,
+{
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> Y {
+        (self.func)(self.dist.sample(rng))
+    }
+}
+
+impl<D, F, X, Y> IndependentSample<Y> for Map<D, F, X>
+where
+    D: IndependentSample<X>,
+    F: Fn(X) -> Y,
+{
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> Y {
+        (self.func)(self.dist.ind_sample(rng))
+    }
+}
+
+
+/// Adapter that mixes several distributions.
+///
+/// Each sample is drawn from one of the components, which is chosen
+/// randomly according to the component weights. All components must
+/// have the same type; to mix different distributions, box them or
+/// wrap them in an enum.
+#[derive(Debug, Clone)]
+pub struct Mixture<D> {
+    components: Vec<D>,
+    chooser: DiscreteSampler,
+}
+
+impl<D> Mixture<D> {
+    /// Creates a new mixture from pairs of weight and component.
+    ///
+    /// The weights need not be normalized.
+    ///
+    /// # Panics
+    /// This panics under the same conditions as `DiscreteSampler::new()`.
+    pub fn new(components: Vec<(f64, D)>) -> Self {
+        let weights: Vec<f64> = components.iter().map(|&(weight, _)| weight).collect();
+        Mixture {
+            chooser: DiscreteSampler::new(&weights),
+            components: components.into_iter().map(|(_, dist)| dist).collect(),
+        }
+    }
+
+    /// Returns the components of the mixture.
+    pub fn components(&self) -> &[D] {
+        &self.components
+    }
+}
+
+impl<D, X> Sample<X> for Mixture<D>
+where
+    D: Sample<X>,
+{
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> X {
+        let index = self.chooser.ind_sample(rng);
+        self.components[index].sample(rng)
+    }
+}
+
+impl<D, X> IndependentSample<X> for Mixture<D>
+where
+    D: IndependentSample<X>,
+{
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> X {
+        let index = self.chooser.ind_sample(rng);
+        self.components[index].ind_sample(rng)
+    }
+}
@@ -0,0 +1,91 @@
+use std::cmp::Ordering;
+
+use rand::Rng;
+use rand::distributions::{Sample, IndependentSample};
+
+
+/// Distribution given by a set of measured values.
+///
+/// By default, sampling returns one of the values at random, i.e. it
+/// follows the empirical cumulative distribution function (ECDF) of the
+/// data. This reproduces the data exactly, but never produces values
+/// that have not been measured. With `interpolated()`, the ECDF is
+/// interpolated linearly between consecutive values instead, giving a
+/// continuous distribution between the smallest and the largest value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmpiricalDistribution {
+    /// The measured values in ascending order.
+    samples: Vec<f64>,
+    interpolate: bool,
+}
+
+impl EmpiricalDistribution {
+    /// Creates a new distribution from measured values.
+    ///
+    /// The values need not be sorted.
+    ///
+    /// # Panics
+    /// This panics if `samples` is empty or contains NaN.
+    pub fn from_samples(samples: &[f64]) -> Self {
+        assert!(!samples.is_empty(), "no samples");
+        let mut samples = samples.to_vec();
+        samples.sort_by(|a, b| a.partial_cmp(b).expect("NaN in samples"));
+        EmpiricalDistribution {
+            samples,
+            interpolate: false,
+        }
+    }
+
+    /// Enables linear interpolation of the ECDF.
+    pub fn interpolated(mut self) -> Self {
+        self.interpolate = true;
+        self
+    }
+
+    /// Returns the measured values in ascending order.
+    pub fn samples(&self) -> &[f64] {
+        &self.samples
+    }
+
+    /// Returns the fraction of values that are less than or equal to
+    /// `x`.
+    pub fn cdf(&self, x: f64) -> f64 {
+        // Find the first value greater than `x`.
+        let count = self.samples
+            .binary_search_by(|&sample| {
+                if sample <= x { Ordering::Less } else { Ordering::Greater }
+            })
+            .unwrap_or_else(|i| i);
+        count as f64 / self.samples.len() as f64
+    }
+
+    /// Returns the value below which the given fraction `p` of the
+    /// distribution lies.
+    ///
+    /// # Panics
+    /// This panics if `p` is not between zero and one.
+    pub fn quantile(&self, p: f64) -> f64 {
+        assert!((0.0..=1.0).contains(&p), "probability out of range");
+        let n = self.samples.len();
+        if self.interpolate && n > 1 {
+            let position = p * (n - 1) as f64;
+            let i = (position as usize).min(n - 2);
+            let fraction = position - i as f64;
+            self.samples[i] + fraction * (self.samples[i + 1] - self.samples[i])
+        } else {
+            self.samples[((p * n as f64) as usize).min(n - 1)]
+        }
+    }
+}
+
+impl Sample<f64> for EmpiricalDistribution {
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> f64 {
+        self.ind_sample(rng)
+    }
+}
+
+impl IndependentSample<f64> for EmpiricalDistribution {
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        self.quantile(rng.gen::<f64>())
+    }
+}
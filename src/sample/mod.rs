@@ -0,0 +1,443 @@
+pub mod adapters;
+pub mod empirical;
+pub mod ziggurat;
+pub mod piecewise;
+
+use rand::Rng;
+use rand::distributions::{Sample, IndependentSample};
+use rand::distributions::normal::StandardNormal;
+
+use logging;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+#[cfg(feature = "rayon")]
+use rng::StreamRng;
+
+
+/// Extension trait that allows conversion to `SampleIter`.
+///
+/// Everything that implements `Sample` can be converted to
+/// `SampleIter`.
+pub trait IntoSampleIter<Sup>: Sized + Sample<Sup> {
+    /// Performs the conversion.
+    fn into_sample_iter<'a, R>(self, rng: &'a mut R) -> SampleIter<'a, Sup, Self, R>
+    where
+        R: 'a + Rng,
+    {
+        SampleIter::new(self, rng)
+    }
+
+    /// Performs the conversion to a `WeightedSampleIter`.
+    fn into_weighted_sample_iter<'a, F, W, R>(
+        self,
+        weight: F,
+        rng: &'a mut R,
+    ) -> WeightedSampleIter<'a, Sup, Self, F, W, R>
+    where
+        F: FnMut(&Sup) -> W,
+        R: 'a + Rng,
+    {
+        WeightedSampleIter::new(self, weight, rng)
+    }
+}
+
+impl<S: Sample<Sup>, Sup> IntoSampleIter<Sup> for S {}
+
+
+/// `Iterator` wrapper type around probability distributions.
+///
+/// This iterator wraps up a random distribution that implements
+/// `Sample` with a source of randomness. Together, this creates an
+/// infinite iterator sampling from the given distribution.
+pub struct SampleIter<'a, Sup, S, R>
+where
+    S: Sample<Sup>,
+    R: 'a + Rng,
+{
+    rng: &'a mut R,
+    sample: S,
+    _dummy: ::std::marker::PhantomData<Sup>,
+}
+
+impl<'a, Sup, S, R> SampleIter<'a, Sup, S, R>
+where
+    S: Sample<Sup>,
+    R: 'a + Rng,
+{
+    /// Wraps up a random distribution with a source of randomness.
+    pub fn new(sample: S, rng: &'a mut R) -> Self {
+        SampleIter {
+            rng,
+            sample,
+            _dummy: Default::default(),
+        }
+    }
+}
+
+impl<'a, Sup, S, R> Iterator for SampleIter<'a, Sup, S, R>
+where
+    S: Sample<Sup>,
+    R: 'a + Rng,
+{
+    type Item = Sup;
+
+    /// Samples a new value from the wrapped random distribution.
+    ///
+    /// This never returns `None`.
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.sample.sample(self.rng))
+    }
+}
+
+
+
+/// `Iterator` wrapper type around weighted samples.
+///
+/// This is like `SampleIter`, but additionally evaluates a weight
+/// function for each sample and yields pairs of sample and weight.
+/// This is the natural output of importance sampling, where the weight
+/// is the ratio of the desired density to the one sampled from.
+///
+/// `Statistics` can be `extend`ed with `(x, f64)` pairs, and
+/// `Histogram` with `(f64, f64)` pairs.
+pub struct WeightedSampleIter<'a, Sup, S, F, W, R>
+where
+    S: Sample<Sup>,
+    F: FnMut(&Sup) -> W,
+    R: 'a + Rng,
+{
+    rng: &'a mut R,
+    sample: S,
+    weight: F,
+    _dummy: ::std::marker::PhantomData<(Sup, W)>,
+}
+
+impl<'a, Sup, S, F, W, R> WeightedSampleIter<'a, Sup, S, F, W, R>
+where
+    S: Sample<Sup>,
+    F: FnMut(&Sup) -> W,
+    R: 'a + Rng,
+{
+    /// Wraps up a random distribution and a weight function with a
+    /// source of randomness.
+    pub fn new(sample: S, weight: F, rng: &'a mut R) -> Self {
+        WeightedSampleIter {
+            rng,
+            sample,
+            weight,
+            _dummy: Default::default(),
+        }
+    }
+}
+
+impl<'a, Sup, S, F, W, R> Iterator for WeightedSampleIter<'a, Sup, S, F, W, R>
+where
+    S: Sample<Sup>,
+    F: FnMut(&Sup) -> W,
+    R: 'a + Rng,
+{
+    type Item = (Sup, W);
+
+    /// Samples a new value and calculates its weight.
+    ///
+    /// This never returns `None`.
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = self.sample.sample(self.rng);
+        let weight = (self.weight)(&x);
+        Some((x, weight))
+    }
+}
+
+/// Sampler of indices with given relative weights.
+///
+/// This uses Vose's alias method: after an `O(n)` setup, each index is
+/// drawn in constant time with a single uniform index and one
+/// comparison, regardless of the number of weights.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscreteSampler {
+    /// For each index, the probability to keep it instead of its alias.
+    probabilities: Vec<f64>,
+    aliases: Vec<usize>,
+}
+
+impl DiscreteSampler {
+    /// Creates a new sampler from the relative weights of all indices.
+    ///
+    /// The weights need not be normalized.
+    ///
+    /// # Panics
+    /// This panics if `weights` is empty, any weight is negative or not
+    /// finite, or all weights are zero.
+    pub fn new(weights: &[f64]) -> Self {
+        assert!(!weights.is_empty(), "no weights");
+        assert!(
+            weights.iter().all(|&w| w >= 0.0 && w.is_finite()),
+            "invalid weight"
+        );
+        let total: f64 = weights.iter().sum();
+        assert!(total > 0.0, "all weights are zero");
+        let n = weights.len();
+        let mut scaled: Vec<f64> = weights.iter().map(|w| w * n as f64 / total).collect();
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) =
+            (0..n).partition(|&i| scaled[i] < 1.0);
+        let mut probabilities = vec![1.0; n];
+        let mut aliases: Vec<usize> = (0..n).collect();
+        // Indices that are left over at the end keep a probability of
+        // one. Up to rounding errors, this is exact.
+        while let (Some(less), Some(more)) = (small.pop(), large.pop()) {
+            probabilities[less] = scaled[less];
+            aliases[less] = more;
+            scaled[more] -= 1.0 - scaled[less];
+            if scaled[more] < 1.0 {
+                small.push(more);
+            } else {
+                large.push(more);
+            }
+        }
+        DiscreteSampler {
+            probabilities,
+            aliases,
+        }
+    }
+
+    /// Returns the number of indices.
+    pub fn len(&self) -> usize {
+        self.probabilities.len()
+    }
+
+    /// Returns `true` if there are no indices.
+    ///
+    /// This is always `false`, since `new()` rejects empty weights.
+    pub fn is_empty(&self) -> bool {
+        self.probabilities.is_empty()
+    }
+}
+
+impl Sample<usize> for DiscreteSampler {
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> usize {
+        self.ind_sample(rng)
+    }
+}
+
+impl IndependentSample<usize> for DiscreteSampler {
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> usize {
+        let index = rng.gen_range(0, self.probabilities.len());
+        if rng.gen::<f64>() < self.probabilities[index] {
+            index
+        } else {
+            self.aliases[index]
+        }
+    }
+}
+
+
+/// Metropolis–Hastings sampler of a one-dimensional distribution.
+///
+/// The distribution is given by its `density`, which need not be
+/// normalized. Starting at some point, each step proposes a new point
+/// that is normally distributed around the current one, and accepts it
+/// with probability `min(1, p(new)/p(current))`. Otherwise, the current
+/// point is repeated.
+///
+/// Successive samples are correlated. To reduce the correlation, only
+/// every `n`-th step can be returned via `set_thinning()`. Because the
+/// chain needs some time to forget its starting point, the first steps
+/// should be discarded with `burn_in()`.
+#[derive(Debug, Clone)]
+pub struct Metropolis<F> {
+    density: F,
+    width: f64,
+    thinning: usize,
+    state: f64,
+    state_density: f64,
+    proposed: u64,
+    accepted: u64,
+}
+
+impl<F> Metropolis<F>
+where
+    F: FnMut(f64) -> f64,
+{
+    /// Creates a new sampler of `density` that starts at `start`.
+    ///
+    /// `width` is the standard deviation of the proposal distribution.
+    /// It should be comparable to the width of the target distribution;
+    /// as a rule of thumb, an acceptance rate of 25 to 50 percent is
+    /// ideal.
+    ///
+    /// # Panics
+    /// This panics if `width` is not positive or `density` is not
+    /// positive at `start`.
+    pub fn new(mut density: F, start: f64, width: f64) -> Self {
+        assert!(width > 0.0, "non-positive proposal width");
+        let state_density = density(start);
+        assert!(state_density > 0.0, "zero density at start point");
+        Metropolis {
+            density,
+            width,
+            thinning: 1,
+            state: start,
+            state_density,
+            proposed: 0,
+            accepted: 0,
+        }
+    }
+
+    /// Sets the number of steps per sample.
+    ///
+    /// # Panics
+    /// This panics if `thinning` is zero.
+    pub fn set_thinning(&mut self, thinning: usize) {
+        assert!(thinning > 0, "thinning must be positive");
+        self.thinning = thinning;
+    }
+
+    /// Performs `steps` steps and discards the result.
+    ///
+    /// This also resets the acceptance rate.
+    pub fn burn_in<R: Rng>(&mut self, steps: usize, rng: &mut R) {
+        for _ in 0..steps {
+            self.step(rng);
+        }
+        self.proposed = 0;
+        self.accepted = 0;
+    }
+
+    /// Returns the fraction of proposals that have been accepted.
+    ///
+    /// If no proposals have been made yet, this is zero.
+    pub fn acceptance_rate(&self) -> f64 {
+        if self.proposed > 0 {
+            self.accepted as f64 / self.proposed as f64
+        } else {
+            0.0
+        }
+    }
+
+    /// Private method that performs a single step of the chain.
+    fn step<R: Rng>(&mut self, rng: &mut R) {
+        let StandardNormal(z) = rng.gen();
+        let proposal = self.state + self.width * z;
+        let proposal_density = (self.density)(proposal);
+        self.proposed += 1;
+        if proposal_density > 0.0 &&
+            rng.gen::<f64>() * self.state_density < proposal_density
+        {
+            self.state = proposal;
+            self.state_density = proposal_density;
+            self.accepted += 1;
+        }
+    }
+}
+
+impl<F> Sample<f64> for Metropolis<F>
+where
+    F: FnMut(f64) -> f64,
+{
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> f64 {
+        for _ in 0..self.thinning {
+            self.step(rng);
+        }
+        self.state
+    }
+}
+
+
+/// Samples from a distribution in parallel.
+///
+/// This returns a parallel iterator over `n` samples from `dist`. The
+/// samples are drawn in chunks of fixed size, and each chunk uses its
+/// own RNG stream of a `StreamRng` with the given `seed`. Thus, the
+/// result does not depend on the number of threads, and e.g.
+/// `collect::<Statistics<_>>()` gives the same result on every run.
+///
+/// This is only available with the `rayon` feature.
+#[cfg(feature = "rayon")]
+pub fn par_sample_iter<D, Sup>(dist: D, n: usize, seed: u64) -> impl ParallelIterator<Item = Sup>
+where
+    D: IndependentSample<Sup> + Send + Sync,
+    Sup: Send,
+{
+    let streams = StreamRng::new(seed);
+    let n_chunks = n.div_ceil(PAR_CHUNK_SIZE);
+    (0..n_chunks).into_par_iter().flat_map(move |chunk| {
+        let mut rng = streams.stream(chunk as u64);
+        let size = PAR_CHUNK_SIZE.min(n - chunk * PAR_CHUNK_SIZE);
+        (0..size)
+            .map(|_| dist.ind_sample(&mut rng))
+            .collect::<Vec<_>>()
+    })
+}
+
+
+/// The number of samples per RNG stream in `par_sample_iter()`.
+#[cfg(feature = "rayon")]
+const PAR_CHUNK_SIZE: usize = 10_000;
+
+
+/// Generic sampler that uses the rejection method.
+///
+/// Candidates are drawn from an `envelope` distribution and accepted
+/// with probability `target(x) / bound`. If the envelope is uniform,
+/// this samples from the distribution with density `target`, and
+/// `bound` must be an upper bound of it. Otherwise, `target` must be
+/// the ratio of the desired density to that of the envelope. In both
+/// cases, the samples are biased wherever `target` exceeds `bound`.
+///
+/// The acceptance rate is the mean of `target / bound` over the
+/// envelope. A tight bound makes the sampler fast.
+#[derive(Debug, Clone)]
+pub struct Rejection<F, D> {
+    target: F,
+    envelope: D,
+    bound: f64,
+}
+
+impl<F, D> Rejection<F, D> {
+    /// Creates a new rejection sampler.
+    ///
+    /// # Panics
+    /// This panics if `bound` is not positive.
+    pub fn new(target: F, envelope: D, bound: f64) -> Self {
+        assert!(bound > 0.0, "non-positive envelope bound");
+        Rejection {
+            target,
+            envelope,
+            bound,
+        }
+    }
+}
+
+impl<F, D, X> Sample<X> for Rejection<F, D>
+where
+    F: FnMut(X) -> f64,
+    D: Sample<X>,
+    X: Copy,
+{
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> X {
+        loop {
+            let x = self.envelope.sample(rng);
+            if logging::REJECTION.count(rng.gen::<f64>() * self.bound < (self.target)(x)) {
+                return x;
+            }
+        }
+    }
+}
+
+impl<F, D, X> IndependentSample<X> for Rejection<F, D>
+where
+    F: Fn(X) -> f64,
+    D: IndependentSample<X>,
+    X: Copy,
+{
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> X {
+        loop {
+            let x = self.envelope.ind_sample(rng);
+            if logging::REJECTION.count(rng.gen::<f64>() * self.bound < (self.target)(x)) {
+                return x;
+            }
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use rand::Rng;
+use rand::distributions::{Sample, IndependentSample};
+
+use function::Function;
+use histogram::{Float, Histogram};
+
+
+/// Distribution with a piecewise-constant density.
+///
+/// The density is constant within each interval between two
+/// consecutive edges, and zero outside of the edges. This is the
+/// distribution described by a histogram.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PiecewiseConstant {
+    edges: Vec<f64>,
+    /// The cumulative weights at each edge, starting at zero.
+    cumulative: Vec<f64>,
+}
+
+impl PiecewiseConstant {
+    /// Creates a new distribution from the interval edges and the
+    /// relative weight of each interval.
+    ///
+    /// The weights need not be normalized. Each weight is the
+    /// probability of its interval, not the density within it.
+    ///
+    /// # Panics
+    /// This panics if there is not exactly one more edge than weights,
+    /// the edges are not strictly increasing, any weight is negative,
+    /// or all weights are zero.
+    pub fn new(edges: &[f64], weights: &[f64]) -> Self {
+        assert_eq!(edges.len(), weights.len() + 1, "number of edges and weights");
+        check_increasing(edges);
+        PiecewiseConstant {
+            edges: edges.to_vec(),
+            cumulative: cumulate(weights.iter().cloned()),
+        }
+    }
+
+    /// Creates a new distribution from the bin contents of a
+    /// histogram.
+    ///
+    /// # Panics
+    /// This panics if the histogram is empty.
+    pub fn from_histogram<T: Float>(histogram: &Histogram<T>) -> Self {
+        let mut edges: Vec<f64> = histogram.bin_low_edges().iter().map(|&x| x.to_f64()).collect();
+        edges.extend(histogram.bin_high_edges().last().map(|&x| x.to_f64()));
+        Self::new(&edges, histogram.bin_contents())
+    }
+
+    /// Returns the interval edges.
+    pub fn edges(&self) -> &[f64] {
+        &self.edges
+    }
+}
+
+impl Sample<f64> for PiecewiseConstant {
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> f64 {
+        self.ind_sample(rng)
+    }
+}
+
+impl IndependentSample<f64> for PiecewiseConstant {
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        let (i, _) = choose_segment(&self.cumulative, rng);
+        rng.gen_range(self.edges[i], self.edges[i + 1])
+    }
+}
+
+
+/// Distribution with a piecewise-linear density.
+///
+/// The density is given at a number of points and interpolated
+/// linearly between them. Outside of the first and last point, it is
+/// zero. This is the distribution described by a tabulated `Function`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PiecewiseLinear {
+    points: Vec<f64>,
+    densities: Vec<f64>,
+    /// The cumulative areas at each point, starting at zero.
+    cumulative: Vec<f64>,
+}
+
+impl PiecewiseLinear {
+    /// Creates a new distribution from points and the relative
+    /// density at each point.
+    ///
+    /// The densities need not be normalized.
+    ///
+    /// # Panics
+    /// This panics if there are less than two points, there are not as
+    /// many densities as points, the points are not strictly
+    /// increasing, any density is negative, or the total area is zero.
+    pub fn new(points: &[f64], densities: &[f64]) -> Self {
+        assert!(points.len() >= 2, "too few points");
+        assert_eq!(points.len(), densities.len(), "number of points and densities");
+        check_increasing(points);
+        assert!(densities.iter().all(|&y| y >= 0.0), "negative density");
+        let areas = points
+            .windows(2)
+            .zip(densities.windows(2))
+            .map(|(x, y)| (x[1] - x[0]) * (y[0] + y[1]) / 2.0);
+        PiecewiseLinear {
+            points: points.to_vec(),
+            densities: densities.to_vec(),
+            cumulative: cumulate(areas),
+        }
+    }
+
+    /// Creates a new distribution from the points of a function.
+    ///
+    /// # Panics
+    /// This panics under the same conditions as `new()`.
+    pub fn from_function(function: &Function<f64, f64>) -> Self {
+        Self::new(function.xdata(), function.ydata())
+    }
+
+    /// Returns the points at which the density is given.
+    pub fn points(&self) -> &[f64] {
+        &self.points
+    }
+}
+
+impl Sample<f64> for PiecewiseLinear {
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> f64 {
+        self.ind_sample(rng)
+    }
+}
+
+impl IndependentSample<f64> for PiecewiseLinear {
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        let (i, u) = choose_segment(&self.cumulative, rng);
+        let (x0, x1) = (self.points[i], self.points[i + 1]);
+        let (y0, y1) = (self.densities[i], self.densities[i + 1]);
+        // Invert the cumulative distribution within the segment, which
+        // is quadratic in `x`.
+        let t = if (y1 - y0).abs() <= 1e-12 * (y0 + y1) {
+            u
+        } else {
+            ((y0 * y0 + u * (y1 * y1 - y0 * y0)).sqrt() - y0) / (y1 - y0)
+        };
+        x0 + t.clamp(0.0, 1.0) * (x1 - x0)
+    }
+}
+
+
+/// Private function that checks that the given values are strictly
+/// increasing.
+fn check_increasing(values: &[f64]) {
+    assert!(
+        values.windows(2).all(|pair| pair[0] < pair[1]),
+        "values not strictly increasing"
+    );
+}
+
+
+/// Private function that returns the cumulative sums of the given
+/// weights, starting at zero.
+///
+/// # Panics
+/// This panics if any weight is negative or all weights are zero.
+fn cumulate<I: Iterator<Item = f64>>(weights: I) -> Vec<f64> {
+    let mut sum = 0.0;
+    let mut cumulative = vec![0.0];
+    for weight in weights {
+        assert!(weight >= 0.0, "negative weight");
+        sum += weight;
+        cumulative.push(sum);
+    }
+    assert!(sum > 0.0, "all weights are zero");
+    cumulative
+}
+
+
+/// Private function that randomly chooses a segment according to the
+/// cumulative weights.
+///
+/// Returns the index of the segment and the position within it as a
+/// fraction of its weight. Segments with zero weight are never chosen.
+fn choose_segment<R: Rng>(cumulative: &[f64], rng: &mut R) -> (usize, f64) {
+    let total = cumulative[cumulative.len() - 1];
+    let choice = rng.gen::<f64>() * total;
+    let last = cumulative.len() - 2;
+    let mut segment = cumulative[1..]
+        .binary_search_by(|sum| sum.total_cmp(&choice))
+        .map(|i| i + 1)
+        .unwrap_or_else(|i| i)
+        .min(last);
+    // On an exact hit, skip segments with zero weight.
+    while segment < last && cumulative[segment + 1] <= choice {
+        segment += 1;
+    }
+    let (low, high) = (cumulative[segment], cumulative[segment + 1]);
+    (segment, (choice - low) / (high - low))
+}
@@ -0,0 +1,216 @@
+use std::ops;
+
+use rand::Rng;
+use rand::distributions::{Sample, IndependentSample};
+
+
+/// Sampler of unimodal distributions using the ziggurat method.
+///
+/// The area under the density is covered by a stack of horizontal
+/// strips of equal area, the ziggurat. A sample is generated by picking
+/// a strip uniformly and a point uniformly within it. Most points lie
+/// in the part of the strip that is completely under the density and
+/// are accepted without evaluating it. Only points in the small
+/// remainder need a rejection test. Thus, after a one-time setup,
+/// sampling takes nearly constant time.
+///
+/// The density is given by a closure and need not be normalized. It
+/// must be non-decreasing left of the mode and non-increasing right of
+/// it. The support must be bounded; densities with infinite tails must
+/// be truncated where they become negligible. For families with a scale
+/// parameter, like the exponential distribution of free path lengths,
+/// one table of the standard distribution suffices: multiply each
+/// sample by the current scale.
+pub struct Ziggurat<F> {
+    density: F,
+    mode: f64,
+    left: Option<Layers>,
+    right: Option<Layers>,
+    /// The probability to sample the right side of the mode.
+    p_right: f64,
+}
+
+impl<F> Ziggurat<F>
+where
+    F: Fn(f64) -> f64,
+{
+    /// Creates a new sampler of `density` on `range` with the given
+    /// mode.
+    ///
+    /// For monotonically decreasing densities, the mode is the start
+    /// of the range; for increasing ones, it is the end.
+    ///
+    /// # Panics
+    /// This panics if the mode lies outside of the range, the range is
+    /// empty, or the density is not positive at the mode.
+    pub fn new(density: F, range: ops::Range<f64>, mode: f64) -> Self {
+        assert!(range.start < range.end, "empty range");
+        assert!((range.start..=range.end).contains(&mode), "mode outside of range");
+        assert!(density(mode) > 0.0, "density not positive at mode");
+        let left = Layers::build(|t| density(mode - t), mode - range.start);
+        let right = Layers::build(|t| density(mode + t), range.end - mode);
+        let area = |layers: &Option<Layers>| layers.as_ref().map_or(0.0, Layers::area);
+        let p_right = area(&right) / (area(&left) + area(&right));
+        Ziggurat {
+            density,
+            mode,
+            left,
+            right,
+            p_right,
+        }
+    }
+
+    /// Returns the mode of the distribution.
+    pub fn mode(&self) -> f64 {
+        self.mode
+    }
+}
+
+impl<F> Sample<f64> for Ziggurat<F>
+where
+    F: Fn(f64) -> f64,
+{
+    fn sample<R: Rng>(&mut self, rng: &mut R) -> f64 {
+        self.ind_sample(rng)
+    }
+}
+
+impl<F> IndependentSample<f64> for Ziggurat<F>
+where
+    F: Fn(f64) -> f64,
+{
+    fn ind_sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        loop {
+            let (layers, sign) = if rng.gen::<f64>() < self.p_right {
+                (&self.right, 1.0)
+            } else {
+                (&self.left, -1.0)
+            };
+            let layers = layers.as_ref().expect("side without layers chosen");
+            let density = |t: f64| (self.density)(self.mode + sign * t);
+            if let Some(t) = layers.try_sample(density, rng) {
+                return self.mode + sign * t;
+            }
+        }
+    }
+}
+
+
+/// Private type of the ziggurat of one side of the mode.
+///
+/// The side is described by a non-increasing density `g(t)`, where `t`
+/// is the distance from the mode. Strip `i` spans the `t` range from
+/// zero to `widths[i]` and the density range from `heights[i]` to
+/// `heights[i + 1]`. All strips have the same area.
+#[derive(Debug, Clone)]
+struct Layers {
+    widths: Vec<f64>,
+    heights: Vec<f64>,
+    strip_area: f64,
+}
+
+impl Layers {
+    /// Private function that builds the ziggurat of a density on the
+    /// range from zero to `width`.
+    ///
+    /// Returns `None` if the width is zero.
+    fn build<G: Fn(f64) -> f64>(density: G, width: f64) -> Option<Self> {
+        if width <= 0.0 {
+            return None;
+        }
+        // Find the smallest strip area for which the strips cover the
+        // density.
+        let peak = density(0.0);
+        let (mut low, mut high) = (0.0, peak * width);
+        for _ in 0..ZIGGURAT_BISECTIONS {
+            let mid = (low + high) / 2.0;
+            if Self::stack(&density, width, mid).is_some() {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        let (widths, heights) =
+            Self::stack(&density, width, high).expect("strips do not cover density");
+        Some(Layers {
+            widths,
+            heights,
+            strip_area: high,
+        })
+    }
+
+    /// Private function that stacks strips of the given area.
+    ///
+    /// Returns the widths and heights if the strips reach the peak of
+    /// the density within `ZIGGURAT_STRIPS` strips, otherwise `None`.
+    /// The width after the last strip is zero.
+    fn stack<G>(density: &G, width: f64, area: f64) -> Option<(Vec<f64>, Vec<f64>)>
+    where
+        G: Fn(f64) -> f64,
+    {
+        let peak = density(0.0);
+        let mut widths = vec![width];
+        let mut heights = vec![0.0];
+        while widths.len() <= ZIGGURAT_STRIPS {
+            let height = heights[heights.len() - 1] + area / widths[widths.len() - 1];
+            heights.push(height);
+            if height >= peak {
+                widths.push(0.0);
+                return Some((widths, heights));
+            }
+            widths.push(Self::invert(density, width, height));
+        }
+        None
+    }
+
+    /// Private function that finds the largest `t` at which the
+    /// density is at least `height`.
+    fn invert<G: Fn(f64) -> f64>(density: &G, width: f64, height: f64) -> f64 {
+        if density(width) >= height {
+            return width;
+        }
+        let (mut low, mut high) = (0.0, width);
+        for _ in 0..ZIGGURAT_BISECTIONS {
+            let mid = (low + high) / 2.0;
+            if density(mid) >= height {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    /// Private method that returns the total area of all strips.
+    fn area(&self) -> f64 {
+        self.strip_area * (self.widths.len() - 1) as f64
+    }
+
+    /// Private method that samples a point of the ziggurat.
+    ///
+    /// Returns its `t` coordinate if it lies under the density, and
+    /// `None` otherwise.
+    fn try_sample<G, R>(&self, density: G, rng: &mut R) -> Option<f64>
+    where
+        G: Fn(f64) -> f64,
+        R: Rng,
+    {
+        let strip = rng.gen_range(0, self.widths.len() - 1);
+        let t = rng.gen::<f64>() * self.widths[strip];
+        // The part of the strip below the next strip is completely
+        // under the density.
+        if t < self.widths[strip + 1] {
+            return Some(t);
+        }
+        let (bottom, top) = (self.heights[strip], self.heights[strip + 1]);
+        let height = bottom + rng.gen::<f64>() * (top - bottom);
+        if height < density(t) { Some(t) } else { None }
+    }
+}
+
+
+/// The maximum number of strips per side of a `Ziggurat`.
+const ZIGGURAT_STRIPS: usize = 128;
+
+/// The number of bisection steps used to build a `Ziggurat`.
+const ZIGGURAT_BISECTIONS: usize = 60;
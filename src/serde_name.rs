@@ -0,0 +1,62 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use serde::{de, Serializer, Deserialize, Deserializer};
+
+
+/// Private function that serializes a value as the string it displays
+/// as.
+///
+/// Fields marked with `#[serde(with = "serde_name")]` are stored by
+/// name, e.g. materials in configuration files. This works whether or
+/// not the field's type implements the serde traits itself, which is
+/// only the case with the feature `serde`.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Display,
+    S: Serializer,
+{
+    serializer.collect_str(value)
+}
+
+
+/// Private function that deserializes a value by parsing its name.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    String::deserialize(deserializer)?.parse().map_err(de::Error::custom)
+}
+
+
+/// Like the parent module, but for optional fields.
+///
+/// `None` is stored as a missing value, e.g. an empty CSV field.
+pub mod option {
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    use serde::{de, Serialize, Serializer, Deserialize, Deserializer};
+
+    pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Display,
+        S: Serializer,
+    {
+        value.as_ref().map(T::to_string).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(name) => name.parse().map(Some).map_err(de::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+use dimensioned::si::SI;
+
+
+/// Private trait of types that can be converted to and from a
+/// serializable representation without units.
+///
+/// `dimensioned` does not implement the serde traits. Fields that
+/// contain dimensioned quantities are marked with
+/// `#[serde(with = "serde_si")]` and stored as plain numbers in SI base
+/// units instead, e.g. energies in joules and lengths in meters.
+pub trait WithoutUnits: Sized {
+    type Repr: Serialize + for<'de> Deserialize<'de>;
+
+    fn to_repr(&self) -> Self::Repr;
+
+    fn from_repr(repr: Self::Repr) -> Self;
+}
+
+impl WithoutUnits for f64 {
+    type Repr = f64;
+
+    fn to_repr(&self) -> f64 {
+        *self
+    }
+
+    fn from_repr(repr: f64) -> Self {
+        repr
+    }
+}
+
+impl<U> WithoutUnits for SI<f64, U> {
+    type Repr = f64;
+
+    fn to_repr(&self) -> f64 {
+        self.value_unsafe
+    }
+
+    fn from_repr(repr: f64) -> Self {
+        SI::new(repr)
+    }
+}
+
+impl<T: WithoutUnits> WithoutUnits for Option<T> {
+    type Repr = Option<T::Repr>;
+
+    fn to_repr(&self) -> Self::Repr {
+        self.as_ref().map(T::to_repr)
+    }
+
+    fn from_repr(repr: Self::Repr) -> Self {
+        repr.map(T::from_repr)
+    }
+}
+
+impl<A: WithoutUnits, B: WithoutUnits> WithoutUnits for (A, B) {
+    type Repr = (A::Repr, B::Repr);
+
+    fn to_repr(&self) -> Self::Repr {
+        (self.0.to_repr(), self.1.to_repr())
+    }
+
+    fn from_repr((a, b): Self::Repr) -> Self {
+        (A::from_repr(a), B::from_repr(b))
+    }
+}
+
+impl<T: WithoutUnits> WithoutUnits for Vec<T> {
+    type Repr = Vec<T::Repr>;
+
+    fn to_repr(&self) -> Self::Repr {
+        self.iter().map(T::to_repr).collect()
+    }
+
+    fn from_repr(repr: Self::Repr) -> Self {
+        repr.into_iter().map(T::from_repr).collect()
+    }
+}
+
+
+/// Private function that serializes a value via its `WithoutUnits`
+/// representation.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: WithoutUnits,
+    S: Serializer,
+{
+    value.to_repr().serialize(serializer)
+}
+
+
+/// Private function that deserializes a value via its `WithoutUnits`
+/// representation.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: WithoutUnits,
+    D: Deserializer<'de>,
+{
+    T::Repr::deserialize(deserializer).map(T::from_repr)
+}
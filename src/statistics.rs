@@ -4,16 +4,20 @@ use std::fmt::{self, Debug, Display};
 
 pub use dimensioned::traits::Sqrt;
 
+#[cfg(feature = "rayon")]
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelIterator};
+
 
 /// A trait alias that specifies all bounds required to store a
 /// variable in a `Statistics` variable.
 ///
-/// The bounds are necessary to auto-derive `Clone`, `Default`, and
-/// `Debug`. The purpose of this trait is to simplify the signature
-/// of the next-higher trait alias, `Cumulable`.
-pub trait Primitive: Copy + Default + Debug {}
+/// The bounds are necessary to auto-derive `Clone` and `Debug`. No
+/// `Default` is required so that dimensioned quantities qualify. The
+/// purpose of this trait is to simplify the signature of the
+/// next-higher trait alias, `Cumulable`.
+pub trait Primitive: Copy + Debug {}
 
-impl<T: Copy + Default + Debug> Primitive for T {}
+impl<T: Copy + Debug> Primitive for T {}
 
 
 /// The trait of all types that can be accumulated.
@@ -82,11 +86,20 @@ where
 ///
 /// The algorithm has been copied from Wikipedia:
 /// https://en.wikipedia.org/wiki/Algorithms_for_calculating_variance
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "X: ::serde::Serialize, X::Variance: ::serde::Serialize",
+        deserialize = "X: ::serde::Deserialize<'de>, X::Variance: ::serde::Deserialize<'de>"
+    ))
+)]
 pub struct Statistics<X: Stat> {
     count: u32,
-    mean: X,
-    sum_of_squares: X::Variance,
+    /// The mean and the sum of squares, or `None` if the sample is
+    /// empty.
+    moments: Option<(X, X::Variance)>,
 }
 
 impl<X: Stat> Statistics<X> {
@@ -95,24 +108,89 @@ impl<X: Stat> Statistics<X> {
         Default::default()
     }
 
+    /// Private method that returns the current mean and sum of
+    /// squares.
+    ///
+    /// If the sample is empty, both are zero. Because `X` need not
+    /// implement `Default`, the zeros are derived from `x`.
+    #[allow(clippy::eq_op)]
+    fn moments_or_zero(&self, x: X) -> (X, X::Variance) {
+        self.moments.unwrap_or_else(|| {
+            let zero = x - x;
+            (zero, X::mul(zero, zero))
+        })
+    }
+
     /// Takes a new sample point into consideration.
     pub fn push(&mut self, x: X) {
+        let (mut mean, mut sum_of_squares) = self.moments_or_zero(x);
         // Update the counter.
         self.count += 1;
         // Update the mean.
-        let delta = x - self.mean;
-        self.mean += delta / self.count as f64;
+        let delta = x - mean;
+        mean += delta / self.count as f64;
         // Update the sum of squares.
-        let delta_2 = x - self.mean;
-        self.sum_of_squares += X::mul(delta, delta_2);
+        let delta_2 = x - mean;
+        sum_of_squares += X::mul(delta, delta_2);
+        self.moments = Some((mean, sum_of_squares));
+    }
+
+    /// Takes the same sample point into consideration `n` times.
+    ///
+    /// This is equivalent to, but much faster than, calling `push(x)`
+    /// `n` times in a row.
+    pub fn push_n(&mut self, x: X, n: u32) {
+        if n == 0 {
+            return;
+        }
+        let (mut mean, mut sum_of_squares) = self.moments_or_zero(x);
+        // This merges `self` with a sample of `n` identical points,
+        // which has a mean of `x` and a sum of squares of zero.
+        let count = self.count + n;
+        let delta = x - mean;
+        mean += delta / (count as f64 / n as f64);
+        if self.count > 0 {
+            let weight = count as f64 / (self.count as f64 * n as f64);
+            sum_of_squares += X::mul(delta, delta) / weight;
+        }
+        self.moments = Some((mean, sum_of_squares));
+        self.count = count;
+    }
+
+    /// Merges the statistics of another sample into `self`.
+    ///
+    /// Afterwards, `self` describes the union of both samples. This
+    /// allows accumulating statistics on several threads and combining
+    /// them at the end.
+    pub fn merge(&mut self, other: &Self) {
+        let (other_mean, other_sum_of_squares) = match other.moments {
+            Some(moments) => moments,
+            None => return,
+        };
+        let (mut mean, mut sum_of_squares) = self.moments_or_zero(other_mean);
+        let count = self.count + other.count;
+        let delta = other_mean - mean;
+        mean += delta / (count as f64 / other.count as f64);
+        sum_of_squares += other_sum_of_squares;
+        if self.count > 0 {
+            let weight = count as f64 / (self.count as f64 * other.count as f64);
+            sum_of_squares += X::mul(delta, delta) / weight;
+        }
+        self.moments = Some((mean, sum_of_squares));
+        self.count = count;
+    }
+
+    /// Returns the number of sample points.
+    pub fn count(&self) -> u32 {
+        self.count
     }
 
     /// Returns the empirical mean of the sample.
     ///
-    /// An empty `Statistics` object returns the default value of the
-    /// sample type.
+    /// # Panics
+    /// This panics if no sample points have been `push`ed.
     pub fn mean(&self) -> X {
-        self.mean
+        self.moments.expect("empty sample").0
     }
 
     /// Returns the empirical (unbiased) variance of the sample.
@@ -121,12 +199,13 @@ impl<X: Stat> Statistics<X> {
     /// the variance. If enough data is available, this function
     /// returns `Some(variance)`, otherwise `None` is returned.
     pub fn variance(&self) -> Option<X::Variance> {
-        if self.count > 1 {
+        match self.moments {
             // Subtract one from `count` to get an unbiased estimator
             // for the variance.
-            Some(self.sum_of_squares / (self.count - 1) as f64)
-        } else {
-            None
+            Some((_, sum_of_squares)) if self.count > 1 => {
+                Some(sum_of_squares / (self.count - 1) as f64)
+            },
+            _ => None,
         }
     }
 
@@ -157,6 +236,15 @@ impl<X: Stat> Statistics<X> {
     }
 }
 
+impl<X: Stat> Default for Statistics<X> {
+    fn default() -> Self {
+        Statistics {
+            count: 0,
+            moments: None,
+        }
+    }
+}
+
 impl<X: Stat> Extend<X> for Statistics<X> {
     /// Successively `push`es all elements of the iterator to `self`.
     fn extend<T>(&mut self, iter: T)
@@ -169,6 +257,25 @@ impl<X: Stat> Extend<X> for Statistics<X> {
     }
 }
 
+impl<X> Extend<(X, f64)> for Statistics<X>
+where
+    X: Stat + Mul<f64, Output = X>,
+{
+    /// Successively `push`es all weighted sample points to `self`.
+    ///
+    /// Each point `x` with weight `w` is pushed as `x * w`. Thus, if
+    /// the weights are importance-sampling weights, the mean is an
+    /// unbiased estimate of the mean of the desired distribution.
+    fn extend<T>(&mut self, iter: T)
+    where
+        T: IntoIterator<Item = (X, f64)>,
+    {
+        for (point, weight) in iter {
+            self.push(point * weight);
+        }
+    }
+}
+
 impl<X: Stat> FromIterator<X> for Statistics<X> {
     /// Calculates the statistics of the sample provided by the
     /// iterator `iter`.
@@ -182,6 +289,33 @@ impl<X: Stat> FromIterator<X> for Statistics<X> {
     }
 }
 
+#[cfg(feature = "rayon")]
+impl<X> FromParallelIterator<X> for Statistics<X>
+where
+    X: Stat + Send,
+    X::Variance: Send,
+{
+    /// Calculates the statistics of the sample provided by a parallel
+    /// iterator.
+    ///
+    /// Each thread accumulates its own statistics, which are then
+    /// `merge`d.
+    fn from_par_iter<T>(iter: T) -> Self
+    where
+        T: IntoParallelIterator<Item = X>,
+    {
+        iter.into_par_iter()
+            .fold(Self::new, |mut stats, x| {
+                stats.push(x);
+                stats
+            })
+            .reduce(Self::new, |mut left, right| {
+                left.merge(&right);
+                left
+            })
+    }
+}
+
 
 impl<X> Display for Statistics<X>
 where
@@ -202,7 +336,10 @@ where
     }
 }
 
-/// Prints statistics and execution time of a process.
+/// Prints statistics, execution time and throughput of a process.
+///
+/// The throughput is the number of samples in the statistics per
+/// second.
 pub fn print_stats_and_time<X, Func>(func: Func)
 where
     X: Stat + Display,
@@ -211,8 +348,8 @@ where
     Func: FnOnce() -> Statistics<X>,
 {
     use super::time;
-    let mut stats = Statistics::new();
-    let secs = time::measure_seconds(|| stats = func());
+    let (stats, secs) = time::measure_with(func);
     println!("{}", stats);
     println!("time: {:.3}", secs);
+    println!("throughput: {:.3e}", time::throughput(stats.count() as usize, secs));
 }
@@ -1,8 +1,12 @@
+use std::fmt;
 use std::time::{Instant, Duration};
 
 use dimensioned::si::*;
+use dimensioned::Dimensionless;
 use dimensioned::f64prefixes::*;
 
+use statistics::Statistics;
+
 
 /// Measures and returns the time it takes to execute a function.
 pub fn measure<F: FnOnce()>(func: F) -> Duration {
@@ -15,8 +19,236 @@ pub fn measure<F: FnOnce()>(func: F) -> Duration {
 
 /// Wrapper around `measure` that returns the time in seconds.
 pub fn measure_seconds<F: FnOnce()>(func: F) -> Second<f64> {
-    let duration = measure(func);
+    to_seconds(measure(func))
+}
+
+
+/// Like `measure_seconds`, but also returns the result of the function.
+pub fn measure_with<T, F: FnOnce() -> T>(func: F) -> (T, Second<f64>) {
+    let start = Instant::now();
+    let result = func();
+    let end = Instant::now();
+    (result, to_seconds(end.duration_since(start)))
+}
+
+
+/// Converts a `Duration` into a dimensioned number of seconds.
+pub fn to_seconds(duration: Duration) -> Second<f64> {
     let secs = duration.as_secs() as f64;
     let nanosecs = duration.subsec_nanos() as f64;
     secs * S + nanosecs * NANO * S
 }
+
+
+/// Returns the number of items processed per second.
+///
+/// If `duration` is zero, the result is infinite.
+pub fn throughput(n_items: usize, duration: Second<f64>) -> Hertz<f64> {
+    n_items as f64 / duration
+}
+
+
+/// Measures the runtime of a function repeatedly.
+///
+/// `func` is first called `WARM_UP_RUNS` times to fill caches and
+/// trigger lazy initialization; these runs are discarded. Then, it is
+/// called `n_repeats` times and the runtime of each call is recorded.
+/// The mean of the returned statistics is the typical runtime, and its
+/// error of the mean tells whether two runtimes differ significantly.
+pub fn benchmark<F: FnMut()>(n_repeats: usize, mut func: F) -> Statistics<Second<f64>> {
+    for _ in 0..WARM_UP_RUNS {
+        func();
+    }
+    (0..n_repeats).map(|_| measure_seconds(&mut func)).collect()
+}
+
+
+/// Type that tracks the progress of a long computation.
+///
+/// The timer is created with the total number of work units, e.g.
+/// histories or samples, and is told periodically via `tick()` how many
+/// of them are done. From this, it calculates the throughput and the
+/// estimated time until the computation is finished. On each tick, an
+/// optional callback is invoked with the timer, e.g. to print a
+/// progress line with `print_to_terminal()`.
+pub struct ProgressTimer<'a> {
+    total: usize,
+    done: usize,
+    start: Instant,
+    callback: Option<TickCallback<'a>>,
+}
+
+
+/// The type of the callback of a `ProgressTimer`.
+type TickCallback<'a> = Box<dyn FnMut(&ProgressTimer) + 'a>;
+
+impl<'a> ProgressTimer<'a> {
+    /// Creates a new timer for `total` work units.
+    ///
+    /// The clock starts running immediately.
+    pub fn new(total: usize) -> Self {
+        ProgressTimer {
+            total,
+            done: 0,
+            start: Instant::now(),
+            callback: None,
+        }
+    }
+
+    /// Sets the callback that is invoked on each tick.
+    pub fn on_tick<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&ProgressTimer) + 'a,
+    {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Prints the progress on a single, updating line of stderr on
+    /// each tick.
+    ///
+    /// Once the computation is done, call `eprintln!()` to move to the
+    /// next line.
+    pub fn print_to_terminal(self) -> Self {
+        self.on_tick(|timer| eprint!("\r{}   ", timer))
+    }
+
+    /// Reports that `done` work units are finished in total.
+    pub fn tick(&mut self, done: usize) {
+        self.done = done;
+        if let Some(mut callback) = self.callback.take() {
+            callback(self);
+            self.callback = Some(callback);
+        }
+    }
+
+    /// Returns the number of work units that are done.
+    pub fn done(&self) -> usize {
+        self.done
+    }
+
+    /// Returns the total number of work units.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns the fraction of work units that are done.
+    pub fn fraction(&self) -> f64 {
+        if self.total > 0 {
+            self.done as f64 / self.total as f64
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns the time that has passed since the timer was created.
+    pub fn elapsed(&self) -> Second<f64> {
+        to_seconds(self.start.elapsed())
+    }
+
+    /// Returns the number of work units done per second.
+    ///
+    /// If no time has passed yet, this returns `None`.
+    pub fn throughput(&self) -> Option<Hertz<f64>> {
+        let elapsed = self.elapsed();
+        if elapsed > 0.0 * S {
+            Some(throughput(self.done, elapsed))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the estimated time until all work units are done.
+    ///
+    /// The estimate assumes that all work units take the same time on
+    /// average. If no work unit is done yet, this returns `None`.
+    pub fn eta(&self) -> Option<Second<f64>> {
+        if self.done > 0 {
+            let remaining = self.total.saturating_sub(self.done);
+            Some(self.elapsed() * (remaining as f64 / self.done as f64))
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> fmt::Display for ProgressTimer<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:5.1}% done", 100.0 * self.fraction())?;
+        if let Some(throughput) = self.throughput() {
+            write!(f, ", {:.3e} per s", *(throughput * S).value())?;
+        }
+        if let Some(eta) = self.eta() {
+            write!(f, ", ETA {:.0} s", *(eta / S).value())?;
+        }
+        Ok(())
+    }
+}
+
+
+/// The wall time and CPU time of a computation.
+///
+/// The CPU time is summed over all threads of the process. Comparing
+/// it to the wall time tells how well a parallel computation uses the
+/// available cores.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CpuTime {
+    /// The time that has passed in the real world.
+    pub wall: Second<f64>,
+    /// The time that the process has spent on the CPU.
+    pub cpu: Second<f64>,
+}
+
+impl CpuTime {
+    /// Returns the average number of cores that were busy.
+    ///
+    /// For a single-threaded computation, this is at most one. For a
+    /// parallel computation, it is the speedup over running the same
+    /// work on one thread.
+    pub fn speedup(&self) -> f64 {
+        *(self.cpu / self.wall).value()
+    }
+
+    /// Returns the fraction of `n_threads` cores that were busy.
+    pub fn efficiency(&self, n_threads: usize) -> f64 {
+        self.speedup() / n_threads as f64
+    }
+}
+
+impl fmt::Display for CpuTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "wall: {:.3}, CPU: {:.3}", self.wall, self.cpu)
+    }
+}
+
+
+/// Measures the wall time and the CPU time of the process while
+/// executing a function.
+///
+/// # Panics
+/// This panics if the operating system cannot report the CPU time.
+#[cfg(unix)]
+pub fn measure_cpu<F: FnOnce()>(func: F) -> CpuTime {
+    let cpu_start = process_cpu_time();
+    let wall = measure_seconds(func);
+    let cpu = process_cpu_time() - cpu_start;
+    CpuTime { wall, cpu }
+}
+
+
+/// Private function that returns the CPU time used by the process so
+/// far.
+#[cfg(unix)]
+fn process_cpu_time() -> Second<f64> {
+    let mut time = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    let status = unsafe { libc::clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID, &mut time) };
+    assert_eq!(status, 0, "cannot get CPU time");
+    time.tv_sec as f64 * S + time.tv_nsec as f64 * NANO * S
+}
+
+
+/// The number of runs discarded by `benchmark()`.
+pub const WARM_UP_RUNS: usize = 1;